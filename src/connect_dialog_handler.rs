@@ -64,6 +64,7 @@ pub struct ConnectDialog {
     nodeid: String,
     relay_url: String,
     iroh_connection_error: String,
+    client_nodeid: Option<NodeId>,
     pub show_modal: bool,
     show_spinner: bool,
     disable_widgets: bool,
@@ -91,6 +92,7 @@ impl ConnectDialog {
             nodeid: String::new(),
             relay_url: String::new(),
             iroh_connection_error: String::new(),
+            client_nodeid: None,
             show_modal: false,
             show_spinner: false,
             disable_widgets: false,
@@ -102,10 +104,16 @@ impl ConnectDialog {
         self.iroh_connection_error = error;
     }
 
+    /// Set the nodeid this piggui connects with, shown so that it can be added to the
+    /// allow-list of a piglet
+    pub fn set_client_nodeid(&mut self, nodeid: NodeId) {
+        self.client_nodeid = Some(nodeid);
+    }
+
     async fn empty() {}
 
     pub fn update(&mut self, message: ConnectDialogMessage) -> Command<Message> {
-        return match message {
+        match message {
             ConnectButtonPressed(node_id, url) => {
                 if node_id.trim().is_empty() {
                     self.iroh_connection_error = String::from("Please Enter Node Id");
@@ -132,9 +140,9 @@ impl ConnectDialog {
                             }
                         };
 
-                        return Command::perform(Self::empty(), move |_| {
+                        Command::perform(Self::empty(), move |_| {
                             Message::ConnectRequest(Remote(nodeid, relay_url))
-                        });
+                        })
                     }
                     Err(err) => {
                         self.iroh_connection_error = format!("{}", err);
@@ -196,7 +204,7 @@ impl ConnectDialog {
                 self.enable_widgets_and_hide_spinner();
                 Command::none()
             }
-        };
+        }
     }
 
     pub fn view<'a>(&self) -> Element<'a, Message> {
@@ -245,12 +253,22 @@ impl ConnectDialog {
                 .padding(10)
                 .style(TEXT_BOX_CONTAINER_STYLE.get_container_style());
 
+        let client_nodeid = match &self.client_nodeid {
+            Some(nodeid) => text(format!(
+                "This piggui's node id, for piglet's allow-list: {nodeid}"
+            )),
+            None => text(""),
+        }
+        .size(12)
+        .style(IROH_INFO_TEXT_STYLE.get_text_color());
+
         if self.disable_widgets {
             container(
                 column![column![
                     text("Connect To Remote Pi").size(20),
                     column![
                         text_container,
+                        client_nodeid,
                         text(self.iroh_connection_error.clone())
                             .style(CONNECTION_ERROR_DISPLAY.get_text_color()),
                         text("Node Id").size(12),
@@ -277,6 +295,7 @@ impl ConnectDialog {
                     text("Connect To Remote Pi").size(20),
                     column![
                         text_container,
+                        client_nodeid,
                         text(self.iroh_connection_error.clone())
                             .style(CONNECTION_ERROR_DISPLAY.get_text_color()),
                         text("Node Id").size(12),
@@ -393,6 +412,15 @@ mod tests {
         assert!(!connect_dialog.iroh_connection_error.is_empty());
     }
 
+    #[test]
+    fn test_set_client_nodeid() {
+        let mut connect_dialog = ConnectDialog::new();
+        let nodeid = NodeId::from_str("rxci3kuuxljxqej7hau727aaemcjo43zvf2zefnqla4p436sqwhq")
+            .expect("Could not create nodeid");
+        connect_dialog.set_client_nodeid(nodeid);
+        assert_eq!(connect_dialog.client_nodeid, Some(nodeid));
+    }
+
     #[test]
     fn test_connection_error() {
         let mut connect_dialog = ConnectDialog::new();
@@ -1,4 +1,4 @@
-use crate::hw::config::HardwareConfig;
+use crate::hw::config::{ConfigFormat, HardwareConfig};
 use crate::views::message_row::MessageMessage::{Error, Info};
 use crate::views::message_row::MessageRowMessage::ShowStatusMessage;
 use crate::Message;
@@ -19,7 +19,7 @@ async fn load(filename: String) -> io::Result<(String, HardwareConfig)> {
 /// If the user cancels the selection it will return `Ok(None)`
 async fn load_via_picker() -> io::Result<Option<(String, HardwareConfig)>> {
     if let Some(handle) = rfd::AsyncFileDialog::new()
-        .add_filter("Pigg Config", &["pigg"])
+        .add_filter("Pigg Config", &ConfigFormat::EXTENSIONS)
         .set_title("Choose config file to load")
         .set_directory(env::current_dir().unwrap())
         .pick_file()
@@ -40,6 +40,7 @@ async fn load_via_picker() -> io::Result<Option<(String, HardwareConfig)>> {
 async fn save_via_picker(gpio_config: HardwareConfig) -> io::Result<bool> {
     if let Some(handle) = rfd::AsyncFileDialog::new()
         .add_filter("Pigg Config", &["pigg"])
+        .add_filter("Pigg Config (YAML)", &["yaml", "yml"])
         .set_title("Choose file")
         .set_directory(env::current_dir().unwrap())
         .save_file()
@@ -5,9 +5,12 @@ use iced_futures::futures::sink::SinkExt;
 use iced_futures::futures::StreamExt;
 
 use crate::hw;
+use crate::hw::backend::{Backend, HW};
 use crate::hw::config::HardwareConfig;
 use crate::hw::pin_function::PinFunction;
-use crate::hw::HardwareConfigMessage::{IOLevelChanged, NewConfig, NewPinConfig};
+use crate::hw::stimulus::Stimulus;
+use crate::hw::uart::{UARTConfig, UARTEvent};
+use crate::hw::HardwareConfigMessage::{IOLevelChanged, NewConfig, NewPinConfig, SwitchProfile};
 use crate::hw::LevelChange;
 use crate::hw::{Hardware, HardwareConfigMessage};
 use crate::views::hardware_view::HardwareEventMessage;
@@ -29,7 +32,7 @@ fn send_current_input_states(
 ) {
     // Send initial levels
     for (bcm_pin_number, pin_function) in &config.pins {
-        if let PinFunction::Input(_pullup) = pin_function {
+        if let PinFunction::Input(..) = pin_function {
             // Update UI with initial state
             if let Ok(initial_level) = connected_hardware.get_input_level(*bcm_pin_number) {
                 let _ = tx.try_send(InputChange(
@@ -41,16 +44,24 @@ fn send_current_input_states(
     }
 }
 
-/// `subscribe` implements an async sender of events from inputs, reading from the hardware and
-/// forwarding to the GUI
-pub fn subscribe() -> Subscription<HardwareEventMessage> {
+/// `subscribe` implements an async sender of events from inputs, reading from the hardware of
+/// the selected [Backend] and forwarding to the GUI
+pub fn subscribe(
+    backend: Backend,
+    stimulus: Option<Stimulus>,
+) -> Subscription<HardwareEventMessage> {
     struct Connect;
     subscription::channel(
-        std::any::TypeId::of::<Connect>(),
+        (std::any::TypeId::of::<Connect>(), backend),
         100,
         move |mut gui_sender| async move {
             let mut state = State::Disconnected;
-            let mut connected_hardware = hw::get();
+            let mut connected_hardware = hw::get(backend);
+            if let Some(stimulus) = stimulus {
+                if let Err(e) = connected_hardware.set_stimulus(stimulus) {
+                    eprintln!("{e}");
+                }
+            }
             let hardware_description = connected_hardware.description().unwrap();
 
             loop {
@@ -89,22 +100,42 @@ pub fn subscribe() -> Subscription<HardwareEventMessage> {
 
 /// Apply a config change to the local hardware
 fn apply_config_change(
-    hardware: &mut impl Hardware,
+    hardware: &mut HW,
     config_change: HardwareConfigMessage,
     mut gui_sender_clone: Sender<HardwareEventMessage>,
     gui_sender: &mut Sender<HardwareEventMessage>,
 ) {
     match config_change {
         NewConfig(config) => {
-            hardware
-                .apply_config(&config, move |bcm_pin_number, level| {
-                    gui_sender_clone
-                        .try_send(InputChange(bcm_pin_number, LevelChange::new(level)))
-                        .unwrap();
-                })
-                .unwrap();
+            if let Err(e) = hardware.apply_config(&config, move |bcm_pin_number, level| {
+                gui_sender_clone
+                    .try_send(InputChange(bcm_pin_number, LevelChange::new(level)))
+                    .unwrap();
+            }) {
+                eprintln!("Could not apply config: {e}");
+            }
 
             send_current_input_states(gui_sender, &config, hardware);
+
+            if let Some(uart_config) = &config.uart {
+                apply_uart_config(hardware, uart_config, gui_sender);
+            }
+        }
+        SwitchProfile(name, profile) => {
+            if let Err(e) = hardware.switch_profile(&profile, move |bcm_pin_number, level| {
+                gui_sender_clone
+                    .try_send(InputChange(bcm_pin_number, LevelChange::new(level)))
+                    .unwrap();
+            }) {
+                eprintln!("Could not switch to profile '{name}': {e}");
+                return;
+            }
+
+            send_current_input_states(gui_sender, &profile, hardware);
+
+            if let Some(uart_config) = &profile.uart {
+                apply_uart_config(hardware, uart_config, gui_sender);
+            }
         }
         NewPinConfig(bcm_pin_number, new_function) => {
             let _ = hardware.apply_pin_config(
@@ -120,5 +151,51 @@ fn apply_config_change(
         IOLevelChanged(bcm_pin_number, level_change) => {
             let _ = hardware.set_output_level(bcm_pin_number, level_change.new_level);
         }
+        HardwareConfigMessage::I2CRequest(request) => {
+            let response = hardware.i2c_request(&request);
+            let _ = gui_sender.try_send(HardwareEventMessage::I2CResponse(response));
+        }
+        HardwareConfigMessage::NewSPIConfig(bus, spi_config) => {
+            let _ = hardware.apply_spi_config(bus, &spi_config);
+        }
+        HardwareConfigMessage::SPITransfer(transfer) => {
+            let response = hardware.spi_request(&transfer);
+            let _ = gui_sender.try_send(HardwareEventMessage::SPIResponse(response));
+        }
+        HardwareConfigMessage::NewUARTConfig(uart_config) => {
+            apply_uart_config(hardware, &uart_config, gui_sender);
+        }
+        HardwareConfigMessage::UARTWrite(data) => {
+            if let Err(e) = hardware.uart_write(&data) {
+                let _ = gui_sender.try_send(HardwareEventMessage::UARTEvent(UARTEvent::Error(
+                    e.to_string(),
+                )));
+            }
+        }
+        // Only sent from the hardware to the GUI
+        HardwareConfigMessage::I2CResponse(_)
+        | HardwareConfigMessage::SPIResponse(_)
+        | HardwareConfigMessage::UARTEvent(_)
+        | HardwareConfigMessage::Control(_)
+        | HardwareConfigMessage::Ack(_)
+        | HardwareConfigMessage::Failed(..) => {}
+    }
+}
+
+/// Open the UART with `uart_config`, forwarding the bytes received on it to the GUI, or
+/// reporting the error to the GUI if it could not be opened
+fn apply_uart_config(
+    hardware: &mut impl Hardware,
+    uart_config: &UARTConfig,
+    gui_sender: &mut Sender<HardwareEventMessage>,
+) {
+    let mut gui_sender_clone = gui_sender.clone();
+    if let Err(e) = hardware.apply_uart_config(uart_config, move |data| {
+        let _ =
+            gui_sender_clone.try_send(HardwareEventMessage::UARTEvent(UARTEvent::Received(data)));
+    }) {
+        let _ = gui_sender.try_send(HardwareEventMessage::UARTEvent(UARTEvent::Error(
+            e.to_string(),
+        )));
     }
 }
@@ -0,0 +1,669 @@
+/// Registry of the hardware backends compiled into a binary, so that one can be selected at
+/// run time, and [HW] that dispatches the [Hardware] trait to the one selected
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::str::FromStr;
+
+use crate::hw::config::{validate, HardwareConfig};
+use crate::hw::i2c::I2CAddress;
+use crate::hw::pin_function::AltMode;
+use crate::hw::spi::{SPIBus, SPIConfig};
+use crate::hw::stimulus::Stimulus;
+use crate::hw::uart::UARTConfig;
+use crate::hw::{BCMPinNumber, HardwareDescription, PinFunction, PinLevel, PinProbe};
+
+#[cfg(feature = "cdev_hw")]
+use super::cdev_hw;
+#[cfg(feature = "fake_hw")]
+use super::fake_hw;
+#[cfg(feature = "pi_hw")]
+use super::pi_hw;
+use super::Hardware;
+
+/// The hardware backends that can be selected at run time. Only those whose feature was enabled
+/// when building exist, in addition to [Backend::Auto]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Backend {
+    /// Use the first of [Backend::COMPILED] that is available on this host, or if none are,
+    /// the first of them
+    #[default]
+    Auto,
+    /// Raspberry Pi GPIO, using the "rppal" crate
+    #[cfg(feature = "pi_hw")]
+    Pi,
+    /// Fake GPIO, for hosts without any
+    #[cfg(feature = "fake_hw")]
+    Fake,
+    /// The first GPIO chip of a Linux board, using the GPIO character device
+    #[cfg(feature = "cdev_hw")]
+    Cdev,
+}
+
+impl Backend {
+    /// The backends compiled in, in the order [Backend::Auto] tries them. The GPIO character
+    /// device comes after fake hardware, as many Linux hosts have a GPIO chip that is not
+    /// connected to any header, so it must be selected explicitly when both are compiled in
+    pub const COMPILED: &'static [Backend] = &[
+        #[cfg(feature = "pi_hw")]
+        Backend::Pi,
+        #[cfg(feature = "fake_hw")]
+        Backend::Fake,
+        #[cfg(feature = "cdev_hw")]
+        Backend::Cdev,
+    ];
+
+    /// The name used to select this backend
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Auto => "auto",
+            #[cfg(feature = "pi_hw")]
+            Backend::Pi => "pi",
+            #[cfg(feature = "fake_hw")]
+            Backend::Fake => "fake",
+            #[cfg(feature = "cdev_hw")]
+            Backend::Cdev => "cdev",
+        }
+    }
+
+    /// The names of all the backends that can be selected, for use in command line options
+    pub fn names() -> Vec<&'static str> {
+        std::iter::once(&Backend::Auto)
+            .chain(Backend::COMPILED)
+            .map(Backend::name)
+            .collect()
+    }
+
+    /// Can this backend be used on this host
+    fn is_available(&self) -> bool {
+        match self {
+            Backend::Auto => true,
+            #[cfg(feature = "pi_hw")]
+            Backend::Pi => pi_hw::is_available(),
+            #[cfg(feature = "fake_hw")]
+            Backend::Fake => true,
+            #[cfg(feature = "cdev_hw")]
+            Backend::Cdev => cdev_hw::is_available(),
+        }
+    }
+
+    /// Resolve [Backend::Auto] to the backend to use on this host
+    pub fn resolve(self) -> Backend {
+        match self {
+            Backend::Auto => Backend::COMPILED
+                .iter()
+                .find(|backend| backend.is_available())
+                .or(Backend::COMPILED.first())
+                .copied()
+                .unwrap_or(Backend::Auto),
+            backend => backend,
+        }
+    }
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Backend {
+    type Err = io::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        std::iter::once(&Backend::Auto)
+            .chain(Backend::COMPILED)
+            .find(|backend| backend.name() == name)
+            .copied()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Unknown hardware backend '{name}', expected one of: {}",
+                        Backend::names().join(", ")
+                    ),
+                )
+            })
+    }
+}
+
+/// The [Hardware] implementations that can be selected
+enum BackendHW {
+    #[cfg(feature = "pi_hw")]
+    Pi(pi_hw::PiHW),
+    #[cfg(feature = "fake_hw")]
+    Fake(fake_hw::FakeHW),
+    #[cfg(feature = "cdev_hw")]
+    Cdev(cdev_hw::CdevHW),
+}
+
+/// The [Hardware] implementation of the selected [Backend]. It keeps the config applied to the
+/// pins, so it can switch profiles and revert pins to the safe state declared in the config
+/// when it is dropped
+pub struct HW {
+    hw: BackendHW,
+    config: HardwareConfig,
+}
+
+/// Call the same method on whichever backend `$hw` is
+macro_rules! dispatch {
+    ($hw:expr, $backend:ident => $call:expr) => {
+        match $hw {
+            #[cfg(feature = "pi_hw")]
+            BackendHW::Pi($backend) => $call,
+            #[cfg(feature = "fake_hw")]
+            BackendHW::Fake($backend) => $call,
+            #[cfg(feature = "cdev_hw")]
+            BackendHW::Cdev($backend) => $call,
+        }
+    };
+}
+
+impl HW {
+    /// Create the [Hardware] implementation for `backend`, resolving [Backend::Auto] first
+    pub fn new(backend: Backend) -> Self {
+        let hw = match backend.resolve() {
+            #[cfg(feature = "pi_hw")]
+            Backend::Pi => BackendHW::Pi(pi_hw::get()),
+            #[cfg(feature = "fake_hw")]
+            Backend::Fake => BackendHW::Fake(fake_hw::get()),
+            #[cfg(feature = "cdev_hw")]
+            Backend::Cdev => BackendHW::Cdev(cdev_hw::get()),
+            Backend::Auto => unreachable!("There is always at least one backend compiled in"),
+        };
+        HW {
+            hw,
+            config: HardwareConfig::default(),
+        }
+    }
+
+    /// Put the pins that have a safe state in the last config applied into it, such as when
+    /// the client that configured them goes away. Every pin is tried even if some fail, so as
+    /// many as possible are left safe, and the pins that failed are in the error returned
+    pub fn apply_safe_state(&mut self) -> io::Result<()> {
+        let mut failures = vec![];
+        for (bcm_pin_number, pin_function) in &self.config.safe_state {
+            match dispatch!(&mut self.hw, hw => hw.apply_pin_config(*bcm_pin_number, pin_function, |_, _| {}))
+            {
+                Ok(()) if *pin_function == PinFunction::None => {
+                    self.config.pins.remove(bcm_pin_number);
+                }
+                Ok(()) => {
+                    self.config
+                        .pins
+                        .insert(*bcm_pin_number, pin_function.clone());
+                }
+                Err(e) => failures.push(format!("pin #{bcm_pin_number}: {e}")),
+            }
+        }
+
+        if failures.is_empty() {
+            return Ok(());
+        }
+        Err(io::Error::other(format!(
+            "Could not put {} pin(s) into their safe state: {}",
+            failures.len(),
+            failures.join(", ")
+        )))
+    }
+
+    /// Switch the pins from the config applied to them to the `profile` config in one step.
+    /// The profile is checked against the pins of the hardware first, and nothing is changed if
+    /// there are problems with it. Pins that only the previous config used are released
+    pub fn switch_profile<C>(&mut self, profile: &HardwareConfig, callback: C) -> io::Result<()>
+    where
+        C: FnMut(BCMPinNumber, PinLevel) + Send + Sync + Clone + 'static,
+    {
+        let problems = validate(profile, &self.description()?.pins);
+        if !problems.is_empty() {
+            let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Profile not applied: {}", problems.join(", ")),
+            ));
+        }
+
+        let transition = self.config.switch_to(profile);
+        self.apply_pins(&transition, callback)?;
+        self.config = profile.clone();
+        Ok(())
+    }
+
+    /// Apply the pins and SPI settings of `config` in order of pin number, recording each one
+    /// applied in the config, so that if one fails the config still matches the hardware and
+    /// the pins already changed get the safe state `config` declares for them. Pins being
+    /// released are done last, so they are left as they were if the others cannot be applied
+    fn apply_pins<C>(&mut self, config: &HardwareConfig, callback: C) -> io::Result<()>
+    where
+        C: FnMut(BCMPinNumber, PinLevel) + Send + Sync + Clone + 'static,
+    {
+        let mut pins: Vec<_> = config.pins.iter().collect();
+        pins.sort_by_key(|(bcm_pin_number, pin_function)| {
+            (**pin_function == PinFunction::None, **bcm_pin_number)
+        });
+        for (bcm_pin_number, pin_function) in pins {
+            self.apply_pin_config(*bcm_pin_number, pin_function, callback.clone())?;
+            match config.safe_state.get(bcm_pin_number) {
+                Some(safe_state) => {
+                    self.config
+                        .safe_state
+                        .insert(*bcm_pin_number, safe_state.clone());
+                }
+                None => {
+                    self.config.safe_state.remove(bcm_pin_number);
+                }
+            }
+        }
+
+        for (bus, spi_config) in &config.spi {
+            self.apply_spi_config(*bus, spi_config)?;
+        }
+
+        Ok(())
+    }
+
+    /// The config last applied to the hardware
+    #[allow(dead_code)] // for piglet
+    pub fn config(&self) -> &HardwareConfig {
+        &self.config
+    }
+
+    /// Drive inputs using the waveforms in `stimulus`, which only the fake backend can do
+    pub fn set_stimulus(&mut self, stimulus: Stimulus) -> io::Result<()> {
+        match &mut self.hw {
+            #[cfg(feature = "fake_hw")]
+            BackendHW::Fake(fake) => {
+                fake.set_stimulus(stimulus);
+                Ok(())
+            }
+            #[allow(unreachable_patterns)] // when the fake backend is the only one compiled in
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!(
+                    "A stimulus ({} pins) can only be used with the fake backend, not '{}'",
+                    stimulus.pins.len(),
+                    self.backend()
+                ),
+            )),
+        }
+    }
+
+    /// The [Backend] this is the implementation of
+    pub fn backend(&self) -> Backend {
+        match self.hw {
+            #[cfg(feature = "pi_hw")]
+            BackendHW::Pi(_) => Backend::Pi,
+            #[cfg(feature = "fake_hw")]
+            BackendHW::Fake(_) => Backend::Fake,
+            #[cfg(feature = "cdev_hw")]
+            BackendHW::Cdev(_) => Backend::Cdev,
+        }
+    }
+}
+
+/// Leave no output driven, such as a relay energized, when the hardware is no longer used
+impl Drop for HW {
+    fn drop(&mut self) {
+        if let Err(e) = self.apply_safe_state() {
+            eprintln!("{e}");
+        }
+    }
+}
+
+/// Forward the [Hardware] trait methods that backends implement to the selected one
+impl Hardware for HW {
+    fn description(&self) -> io::Result<HardwareDescription> {
+        dispatch!(&self.hw, hw => hw.description())
+    }
+
+    /// Apply the config in place of the one applied before, releasing the pins only that one
+    /// used, and remember it for the safe state it declares for its pins. If it could not all
+    /// be applied, only the pins that were are remembered
+    fn apply_config<C>(&mut self, config: &HardwareConfig, callback: C) -> io::Result<()>
+    where
+        C: FnMut(BCMPinNumber, PinLevel) + Send + Sync + Clone + 'static,
+    {
+        let transition = self.config.switch_to(config);
+        self.apply_pins(&transition, callback)?;
+        self.config = config.clone();
+        Ok(())
+    }
+
+    fn apply_pin_config<C>(
+        &mut self,
+        bcm_pin_number: BCMPinNumber,
+        pin_function: &PinFunction,
+        callback: C,
+    ) -> io::Result<()>
+    where
+        C: FnMut(BCMPinNumber, PinLevel) + Send + Sync + Clone + 'static,
+    {
+        dispatch!(&mut self.hw, hw => hw.apply_pin_config(bcm_pin_number, pin_function, callback))?;
+        if *pin_function == PinFunction::None {
+            self.config.pins.remove(&bcm_pin_number);
+        } else {
+            self.config
+                .pins
+                .insert(bcm_pin_number, pin_function.clone());
+        }
+        Ok(())
+    }
+
+    fn apply_alt_mode(&mut self, bcm_pin_number: BCMPinNumber, mode: AltMode) -> io::Result<()> {
+        dispatch!(&mut self.hw, hw => hw.apply_alt_mode(bcm_pin_number, mode))
+    }
+
+    fn probe_pins(&self) -> io::Result<HashMap<BCMPinNumber, PinProbe>> {
+        dispatch!(&self.hw, hw => hw.probe_pins())
+    }
+
+    fn get_input_level(&self, bcm_pin_number: BCMPinNumber) -> io::Result<PinLevel> {
+        dispatch!(&self.hw, hw => hw.get_input_level(bcm_pin_number))
+    }
+
+    /// Set the level of an output, remembering it in the config of the pin
+    fn set_output_level(
+        &mut self,
+        bcm_pin_number: BCMPinNumber,
+        level: PinLevel,
+    ) -> io::Result<()> {
+        dispatch!(&mut self.hw, hw => hw.set_output_level(bcm_pin_number, level))?;
+        if let Some(PinFunction::Output(output_level)) = self.config.pins.get_mut(&bcm_pin_number) {
+            *output_level = Some(level);
+        }
+        Ok(())
+    }
+
+    fn i2c_scan(&mut self) -> io::Result<Vec<I2CAddress>> {
+        dispatch!(&mut self.hw, hw => hw.i2c_scan())
+    }
+
+    fn i2c_read(&mut self, address: I2CAddress, length: usize) -> io::Result<Vec<u8>> {
+        dispatch!(&mut self.hw, hw => hw.i2c_read(address, length))
+    }
+
+    fn i2c_write(&mut self, address: I2CAddress, data: &[u8]) -> io::Result<()> {
+        dispatch!(&mut self.hw, hw => hw.i2c_write(address, data))
+    }
+
+    fn i2c_read_register(
+        &mut self,
+        address: I2CAddress,
+        register: u8,
+        length: usize,
+    ) -> io::Result<Vec<u8>> {
+        dispatch!(&mut self.hw, hw => hw.i2c_read_register(address, register, length))
+    }
+
+    fn i2c_write_register(
+        &mut self,
+        address: I2CAddress,
+        register: u8,
+        data: &[u8],
+    ) -> io::Result<()> {
+        dispatch!(&mut self.hw, hw => hw.i2c_write_register(address, register, data))
+    }
+
+    fn apply_spi_config(&mut self, bus: SPIBus, config: &SPIConfig) -> io::Result<()> {
+        dispatch!(&mut self.hw, hw => hw.apply_spi_config(bus, config))?;
+        self.config.spi.insert(bus, *config);
+        Ok(())
+    }
+
+    fn spi_transfer(&mut self, bus: SPIBus, chip_select: u8, data: &[u8]) -> io::Result<Vec<u8>> {
+        dispatch!(&mut self.hw, hw => hw.spi_transfer(bus, chip_select, data))
+    }
+
+    fn apply_uart_config<D>(&mut self, config: &UARTConfig, callback: D) -> io::Result<()>
+    where
+        D: FnMut(Vec<u8>) + Send + 'static,
+    {
+        dispatch!(&mut self.hw, hw => hw.apply_uart_config(config, callback))?;
+        self.config.uart = Some(*config);
+        Ok(())
+    }
+
+    fn uart_write(&mut self, data: &[u8]) -> io::Result<()> {
+        dispatch!(&mut self.hw, hw => hw.uart_write(data))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::backend::{Backend, HW};
+    #[cfg(feature = "fake_hw")]
+    use crate::hw::config::HardwareConfig;
+    #[cfg(feature = "fake_hw")]
+    use crate::hw::stimulus::{PinStimulus, Stimulus, Waveform};
+    #[cfg(feature = "fake_hw")]
+    use crate::hw::{Hardware, PinFunction};
+    use std::str::FromStr;
+    #[cfg(feature = "fake_hw")]
+    use std::sync::mpsc;
+
+    #[test]
+    fn backend_names_parse() {
+        for name in Backend::names() {
+            let backend = Backend::from_str(name).expect("Could not parse backend name");
+            assert_eq!(backend.to_string(), name);
+        }
+    }
+
+    #[test]
+    fn unknown_backend_name() {
+        assert!(Backend::from_str("abacus").is_err());
+    }
+
+    #[test]
+    fn auto_resolves_to_compiled_backend() {
+        let backend = Backend::Auto.resolve();
+        assert_ne!(backend, Backend::Auto);
+        assert!(Backend::COMPILED.contains(&backend));
+        assert_eq!(HW::new(Backend::Auto).backend(), backend);
+    }
+
+    #[cfg(feature = "fake_hw")]
+    #[test]
+    fn fake_is_selectable() {
+        assert_eq!(
+            Backend::from_str("fake").expect("Could not parse 'fake'"),
+            Backend::Fake
+        );
+        assert_eq!(HW::new(Backend::Fake).backend(), Backend::Fake);
+    }
+
+    #[cfg(feature = "fake_hw")]
+    #[test]
+    fn safe_state_applied_when_dropped() {
+        let mut hw = HW::new(Backend::Fake);
+        let mut stimulus = Stimulus::default();
+        stimulus.pins.insert(
+            6,
+            PinStimulus {
+                waveform: Waveform::Loopback(17),
+                repeat: false,
+            },
+        );
+        hw.set_stimulus(stimulus).expect("Could not set stimulus");
+
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+        config
+            .safe_state
+            .insert(17, PinFunction::Output(Some(false)));
+        hw.apply_config(&config, |_, _| {})
+            .expect("Could not apply config");
+
+        // Wire the output back to an input, to see the level it is left at
+        let (sender, receiver) = mpsc::channel();
+        hw.apply_pin_config(6, &PinFunction::Input(None, None), move |_, level| {
+            let _ = sender.send(level);
+        })
+        .expect("Could not configure input");
+        assert!(hw.get_input_level(6).expect("Could not read input"));
+
+        drop(hw);
+        assert_eq!(receiver.try_recv(), Ok(false));
+    }
+
+    #[cfg(feature = "fake_hw")]
+    #[test]
+    fn switch_profile_releases_pins() {
+        let mut hw = HW::new(Backend::Fake);
+        let mut run = HardwareConfig::default();
+        run.pins.insert(17, PinFunction::Output(Some(true)));
+        hw.apply_config(&run, |_, _| {})
+            .expect("Could not apply config");
+
+        let mut test = HardwareConfig::default();
+        test.pins.insert(22, PinFunction::Output(Some(false)));
+        hw.switch_profile(&test, |_, _| {})
+            .expect("Could not switch profile");
+
+        let configured = hw.config.pins.keys().copied().collect::<Vec<_>>();
+        assert_eq!(configured, vec![22]);
+        // The pin the previous profile used is no longer configured, so it is probed again
+        let probe = hw.probe_pins().expect("Could not probe pins");
+        assert!(probe.contains_key(&17));
+        assert!(!probe.contains_key(&22));
+    }
+
+    #[cfg(feature = "fake_hw")]
+    #[test]
+    fn new_config_releases_pins() {
+        let mut hw = HW::new(Backend::Fake);
+        let mut first = HardwareConfig::default();
+        first.pins.insert(17, PinFunction::Output(Some(true)));
+        first
+            .safe_state
+            .insert(17, PinFunction::Output(Some(false)));
+        hw.apply_config(&first, |_, _| {})
+            .expect("Could not apply config");
+
+        let mut second = HardwareConfig::default();
+        second.pins.insert(22, PinFunction::Output(Some(false)));
+        hw.apply_config(&second, |_, _| {})
+            .expect("Could not apply config");
+
+        let configured = hw.config().pins.keys().copied().collect::<Vec<_>>();
+        assert_eq!(configured, vec![22]);
+        assert!(hw.config().safe_state.is_empty());
+        // The pin only the first config used is no longer configured, so it is probed again
+        let probe = hw.probe_pins().expect("Could not probe pins");
+        assert!(probe.contains_key(&17));
+        assert!(!probe.contains_key(&22));
+    }
+
+    #[cfg(feature = "fake_hw")]
+    #[test]
+    fn invalid_profile_not_applied() {
+        let mut hw = HW::new(Backend::Fake);
+        let mut run = HardwareConfig::default();
+        run.pins.insert(17, PinFunction::Output(Some(true)));
+        hw.apply_config(&run, |_, _| {})
+            .expect("Could not apply config");
+
+        let mut invalid = HardwareConfig::default();
+        invalid.pins.insert(22, PinFunction::Output(None));
+        invalid.pins.insert(99, PinFunction::Output(None));
+        assert!(hw.switch_profile(&invalid, |_, _| {}).is_err());
+
+        // Nothing was changed
+        assert!(hw.config.pins.contains_key(&17));
+        let probe = hw.probe_pins().expect("Could not probe pins");
+        assert!(probe.contains_key(&22));
+        assert!(!probe.contains_key(&17));
+    }
+
+    #[cfg(feature = "fake_hw")]
+    #[test]
+    fn failed_config_not_remembered() {
+        let mut hw = HW::new(Backend::Fake);
+        let mut run = HardwareConfig::default();
+        run.pins.insert(17, PinFunction::Output(Some(true)));
+        hw.apply_config(&run, |_, _| {})
+            .expect("Could not apply config");
+
+        let mut bad = HardwareConfig::default();
+        bad.pins.insert(
+            18,
+            PinFunction::Pwm {
+                frequency: 0.0,
+                duty_cycle: 0.5,
+            },
+        );
+        assert!(hw.apply_config(&bad, |_, _| {}).is_err());
+
+        let configured = hw.config().pins.keys().copied().collect::<Vec<_>>();
+        assert_eq!(configured, vec![17]);
+    }
+
+    #[cfg(feature = "fake_hw")]
+    #[test]
+    fn partly_applied_config_remembered() {
+        let mut hw = HW::new(Backend::Fake);
+        let mut run = HardwareConfig::default();
+        run.pins.insert(22, PinFunction::Input(None, None));
+        hw.apply_config(&run, |_, _| {})
+            .expect("Could not apply config");
+
+        let mut bad = HardwareConfig::default();
+        bad.pins.insert(17, PinFunction::Output(Some(true)));
+        bad.pins.insert(
+            18,
+            PinFunction::Pwm {
+                frequency: 0.0,
+                duty_cycle: 0.5,
+            },
+        );
+        bad.pins.insert(22, PinFunction::Output(Some(false)));
+        bad.safe_state.insert(17, PinFunction::Output(Some(false)));
+        assert!(hw.apply_config(&bad, |_, _| {}).is_err());
+
+        // The pin applied before the one that failed is remembered, with its safe state
+        assert_eq!(
+            hw.config().pins.get(&17),
+            Some(&PinFunction::Output(Some(true)))
+        );
+        assert!(!hw.config().pins.contains_key(&18));
+        assert_eq!(
+            hw.config().pins.get(&22),
+            Some(&PinFunction::Input(None, None))
+        );
+        assert_eq!(
+            hw.config().safe_state.get(&17),
+            Some(&PinFunction::Output(Some(false)))
+        );
+    }
+
+    #[cfg(feature = "fake_hw")]
+    #[test]
+    fn safe_state_applied_to_all_pins() {
+        let mut hw = HW::new(Backend::Fake);
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+        config
+            .safe_state
+            .insert(17, PinFunction::Output(Some(false)));
+        config.safe_state.insert(
+            18,
+            PinFunction::Pwm {
+                frequency: 0.0,
+                duty_cycle: 0.5,
+            },
+        );
+        hw.apply_config(&config, |_, _| {})
+            .expect("Could not apply config");
+
+        let error = hw
+            .apply_safe_state()
+            .expect_err("Invalid safe state was applied");
+        assert!(error.to_string().contains("pin #18"), "{error}");
+        assert_eq!(
+            hw.config().pins.get(&17),
+            Some(&PinFunction::Output(Some(false)))
+        );
+        assert!(!hw.config().pins.contains_key(&18));
+    }
+}
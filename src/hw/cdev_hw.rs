@@ -0,0 +1,414 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Instant;
+/// Implementation of GPIO for any Linux board, using the GPIO character device ("/dev/gpiochipN")
+/// API. It can be tested on a Linux host without GPIO using the kernel's "gpio-sim" or
+/// "gpio-mockup" modules, which create simulated GPIO chips
+use std::{fs, io, thread};
+
+use gpio_cdev::{
+    Chip, EventRequestFlags, EventType, LineDirection, LineEventHandle, LineHandle,
+    LineRequestFlags,
+};
+
+use crate::hw::hat::{HatInfo, HAT_DEVICE_TREE};
+use crate::hw::i2c::I2CAddress;
+use crate::hw::pin_description::{PinDescription, PinDescriptionSet};
+use crate::hw::pin_function::AltMode;
+use crate::hw::spi::{SPIBus, SPIConfig};
+use crate::hw::uart::UARTConfig;
+use crate::hw::{BCMPinNumber, InputEdge, InputPull, PinFunction, PinLevel, PinMode, PinProbe};
+
+use super::{check_supported, Hardware, LevelFilter};
+use super::{HardwareDescription, HardwareDetails};
+
+/// The GPIO chip used, which on a Pi is the one connected to the GPIO header
+const DEFAULT_CHIP: &str = "/dev/gpiochip0";
+/// Label the lines requested by pigg have, as shown by tools such as "gpioinfo"
+const CONSUMER: &str = "pigg";
+/// How long the thread reading input events waits for one before checking if it should stop
+const EVENT_POLL_TIMEOUT_MS: libc::c_int = 100;
+
+/// The functions lines that are not used by the kernel can be configured with. The v1 character
+/// device API does not support setting a line's bias, so inputs have no pull-up or pull-down
+const LINE_OPTIONS: &[PinFunction] = &[PinFunction::Input(None, None), PinFunction::Output(None)];
+
+/// An input line, shared with the thread that reads its events. Dropping it stops the thread
+/// and waits for it to finish, so the line is released and can be requested again
+struct InputLine {
+    handle: Arc<Mutex<LineEventHandle>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for InputLine {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+enum Line {
+    Input(InputLine),
+    Output(LineHandle),
+}
+
+pub struct CdevHW {
+    /// Path of the GPIO chip device
+    chip_path: PathBuf,
+    /// Lines that have been requested from the chip, by offset
+    configured_lines: HashMap<BCMPinNumber, Line>,
+}
+
+/// Does this host have the GPIO chip the backend uses
+pub fn is_available() -> bool {
+    Path::new(DEFAULT_CHIP).exists()
+}
+
+/// This method is used to get a "handle" onto the Hardware implementation
+pub fn get() -> CdevHW {
+    CdevHW {
+        chip_path: PathBuf::from(DEFAULT_CHIP),
+        configured_lines: Default::default(),
+    }
+}
+
+/// Describe the lines of a GPIO chip, given the name of each line (if it has one) and if it is
+/// used by the kernel, as pins. The line offset is used as the [BCMPinNumber] (matching the
+/// Broadcom numbering on a Pi) and the board pin number is the offset plus one. Lines used by the
+/// kernel cannot be configured, except the `configured` lines, which the kernel reports as used
+/// because pigg requested them. [BCMPinNumber] is a u8, so only the first 255 lines are used
+fn line_pin_descriptions(
+    lines: &[(Option<String>, bool)],
+    configured: &[BCMPinNumber],
+) -> PinDescriptionSet {
+    let pins = lines
+        .iter()
+        .take(u8::MAX as usize)
+        .enumerate()
+        .map(|(offset, (name, used_by_kernel))| PinDescription {
+            bpn: offset as u8 + 1,
+            bcm: Some(offset as u8),
+            name: Cow::Owned(name.clone().unwrap_or_else(|| format!("Line {offset}"))),
+            options: if *used_by_kernel && !configured.contains(&(offset as u8)) {
+                Cow::Borrowed(&[])
+            } else {
+                Cow::Borrowed(LINE_OPTIONS)
+            },
+        })
+        .collect::<Vec<PinDescription>>();
+    PinDescriptionSet::from(pins)
+}
+
+impl CdevHW {
+    fn chip(&self) -> io::Result<Chip> {
+        Chip::new(&self.chip_path).map_err(io::Error::other)
+    }
+
+    fn get_details(chip: &Chip) -> HardwareDetails {
+        let model = fs::read_to_string("/proc/device-tree/model")
+            .map(|model| model.trim_end_matches('\0').to_string())
+            .unwrap_or_else(|_| chip.label().to_string());
+
+        HardwareDetails {
+            hardware: chip.label().to_string(),
+            revision: "Unknown".to_string(),
+            serial: "Unknown".to_string(),
+            model,
+            board: None,
+        }
+    }
+
+    fn unsupported(what: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{what} is not supported by the GPIO character device backend"),
+        )
+    }
+}
+
+/// Implement the [Hardware] trait for any GPIO chip with a Linux character device
+impl Hardware for CdevHW {
+    /// Describe the chip, with a pin for each of its lines, named as the chip names them
+    fn description(&self) -> io::Result<HardwareDescription> {
+        let chip = self.chip()?;
+        let mut lines = vec![];
+        for line in chip.lines() {
+            let info = line.info().map_err(io::Error::other)?;
+            lines.push((info.name().map(|name| name.to_string()), info.is_kernel()));
+        }
+
+        Ok(HardwareDescription {
+            details: Self::get_details(&chip),
+            pins: line_pin_descriptions(
+                &lines,
+                &self.configured_lines.keys().copied().collect::<Vec<_>>(),
+            ),
+            hat: HatInfo::read(Path::new(HAT_DEVICE_TREE)).unwrap_or(None),
+            probe: self.probe_pins().unwrap_or_default(),
+        })
+    }
+
+    /// Apply the requested config to one line, using its offset as the bcm_pin_number
+    fn apply_pin_config<C>(
+        &mut self,
+        bcm_pin_number: BCMPinNumber,
+        pin_function: &PinFunction,
+        mut callback: C,
+    ) -> io::Result<()>
+    where
+        C: FnMut(BCMPinNumber, PinLevel) + Send + Sync + Clone + 'static,
+    {
+        if *pin_function != PinFunction::None {
+            check_supported(&self.description()?.pins, bcm_pin_number, pin_function)?;
+        }
+
+        // If it was already configured, release it so it can be requested again
+        self.configured_lines.remove(&bcm_pin_number);
+
+        if *pin_function == PinFunction::None {
+            return Ok(());
+        }
+
+        match pin_function {
+            PinFunction::Input(pull, filter) => {
+                if !matches!(pull, None | Some(InputPull::None)) {
+                    return Err(Self::unsupported("Input pull-up or pull-down"));
+                }
+
+                let event_flags = match filter.map(|filter| filter.edge) {
+                    Some(InputEdge::Rising) => EventRequestFlags::RISING_EDGE,
+                    Some(InputEdge::Falling) => EventRequestFlags::FALLING_EDGE,
+                    Some(InputEdge::Both) | None => EventRequestFlags::BOTH_EDGES,
+                };
+                let handle = self
+                    .chip()?
+                    .get_line(bcm_pin_number as u32)
+                    .map_err(io::Error::other)?
+                    .events(LineRequestFlags::INPUT, event_flags, CONSUMER)
+                    .map_err(io::Error::other)?;
+
+                let fd = handle.as_raw_fd();
+                let handle = Arc::new(Mutex::new(handle));
+                let running = Arc::new(AtomicBool::new(true));
+                let thread_handle = handle.clone();
+                let thread_running = running.clone();
+                let mut level_filter = LevelFilter::new(*filter);
+                let thread = thread::spawn(move || {
+                    while thread_running.load(Ordering::Relaxed) {
+                        let mut poll_fd = libc::pollfd {
+                            fd,
+                            events: libc::POLLIN,
+                            revents: 0,
+                        };
+                        // SAFETY: poll_fd is a single valid pollfd, for the handle's fd which
+                        // stays open while this thread holds a reference to the handle
+                        let ready = unsafe { libc::poll(&mut poll_fd, 1, EVENT_POLL_TIMEOUT_MS) };
+                        if ready <= 0 {
+                            continue;
+                        }
+
+                        let event = match thread_handle.lock() {
+                            Ok(mut handle) => handle.get_event(),
+                            Err(_) => break,
+                        };
+                        match event {
+                            Ok(event) => {
+                                let level = event.event_type() == EventType::RisingEdge;
+                                if level_filter.report(level, Instant::now()) {
+                                    callback(bcm_pin_number, level);
+                                }
+                            }
+                            Err(_) => break,
+                        }
+                    }
+                });
+
+                self.configured_lines.insert(
+                    bcm_pin_number,
+                    Line::Input(InputLine {
+                        handle,
+                        running,
+                        thread: Some(thread),
+                    }),
+                );
+            }
+            PinFunction::Output(value) => {
+                let handle = self
+                    .chip()?
+                    .get_line(bcm_pin_number as u32)
+                    .map_err(io::Error::other)?
+                    .request(
+                        LineRequestFlags::OUTPUT,
+                        value.unwrap_or(false) as u8,
+                        CONSUMER,
+                    )
+                    .map_err(io::Error::other)?;
+                self.configured_lines
+                    .insert(bcm_pin_number, Line::Output(handle));
+            }
+            PinFunction::Alt { mode, .. } => self.apply_alt_mode(bcm_pin_number, *mode)?,
+            _ => return Err(Self::unsupported(&pin_function.to_string())),
+        }
+
+        Ok(())
+    }
+
+    /// Alternate functions are selected by the SoC's pin controller, not the GPIO chip
+    fn apply_alt_mode(&mut self, _bcm_pin_number: BCMPinNumber, mode: AltMode) -> io::Result<()> {
+        Err(Self::unsupported(&mode.to_string()))
+    }
+
+    /// Read the direction of the lines not configured. The level is only read from inputs that
+    /// no-one is using, as requesting them as inputs does not change them. The kernel does not
+    /// report the bias of a line
+    fn probe_pins(&self) -> io::Result<HashMap<BCMPinNumber, PinProbe>> {
+        let chip = self.chip()?;
+        let mut probe = HashMap::new();
+        for line in chip.lines().take(u8::MAX as usize) {
+            let bcm_pin_number = line.offset() as BCMPinNumber;
+            if self.configured_lines.contains_key(&bcm_pin_number) {
+                continue;
+            }
+            let info = line.info().map_err(io::Error::other)?;
+            let (mode, level) = match info.direction() {
+                LineDirection::Out => (PinMode::Output, None),
+                LineDirection::In if info.is_used() => (PinMode::Input, None),
+                LineDirection::In => (
+                    PinMode::Input,
+                    line.request(LineRequestFlags::INPUT, 0, CONSUMER)
+                        .and_then(|handle| handle.get_value())
+                        .map(|value| value == 1)
+                        .ok(),
+                ),
+            };
+            probe.insert(
+                bcm_pin_number,
+                PinProbe {
+                    mode,
+                    pull: None,
+                    level,
+                },
+            );
+        }
+        Ok(probe)
+    }
+
+    /// Read the input level of an input using the line offset
+    fn get_input_level(&self, bcm_pin_number: BCMPinNumber) -> io::Result<PinLevel> {
+        match self.configured_lines.get(&bcm_pin_number) {
+            Some(Line::Input(input)) => {
+                let handle = input
+                    .handle
+                    .lock()
+                    .map_err(|_| io::Error::other("Input line lock poisoned"))?;
+                Ok(handle.get_value().map_err(io::Error::other)? == 1)
+            }
+            _ => Err(io::Error::other("Could not find a configured input line")),
+        }
+    }
+
+    /// Write the output level of an output using the line offset
+    fn set_output_level(
+        &mut self,
+        bcm_pin_number: BCMPinNumber,
+        level: PinLevel,
+    ) -> io::Result<()> {
+        match self.configured_lines.get(&bcm_pin_number) {
+            Some(Line::Output(handle)) => handle.set_value(level as u8).map_err(io::Error::other),
+            _ => Err(io::Error::other("Could not find a configured output line")),
+        }
+    }
+
+    fn i2c_scan(&mut self) -> io::Result<Vec<I2CAddress>> {
+        Err(Self::unsupported("I2C"))
+    }
+
+    fn i2c_read(&mut self, _address: I2CAddress, _length: usize) -> io::Result<Vec<u8>> {
+        Err(Self::unsupported("I2C"))
+    }
+
+    fn i2c_write(&mut self, _address: I2CAddress, _data: &[u8]) -> io::Result<()> {
+        Err(Self::unsupported("I2C"))
+    }
+
+    fn apply_spi_config(&mut self, _bus: SPIBus, _config: &SPIConfig) -> io::Result<()> {
+        Err(Self::unsupported("SPI"))
+    }
+
+    fn spi_transfer(
+        &mut self,
+        _bus: SPIBus,
+        _chip_select: u8,
+        _data: &[u8],
+    ) -> io::Result<Vec<u8>> {
+        Err(Self::unsupported("SPI"))
+    }
+
+    fn apply_uart_config<D>(&mut self, _config: &UARTConfig, _callback: D) -> io::Result<()>
+    where
+        D: FnMut(Vec<u8>) + Send + 'static,
+    {
+        Err(Self::unsupported("UART"))
+    }
+
+    fn uart_write(&mut self, _data: &[u8]) -> io::Result<()> {
+        Err(Self::unsupported("UART"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::cdev_hw::{line_pin_descriptions, LINE_OPTIONS};
+    use crate::hw::config::{validate, HardwareConfig};
+    use crate::hw::PinFunction;
+
+    #[test]
+    fn pins_named_from_lines() {
+        let lines = vec![
+            (Some("ID_SDA".to_string()), true),
+            (None, false),
+            (Some("GPIO2".to_string()), false),
+        ];
+        let pin_set = line_pin_descriptions(&lines, &[]);
+        let pins = pin_set.pins();
+        assert_eq!(pins.len(), 3);
+        assert_eq!(pins[0].name, "ID_SDA");
+        assert!(pins[0].options.is_empty());
+        assert_eq!(pins[1].name, "Line 1");
+        assert_eq!(pins[2].bpn, 3);
+        assert_eq!(pins[2].bcm, Some(2));
+        assert_eq!(pins[2].options, LINE_OPTIONS);
+    }
+
+    #[test]
+    fn lines_limited_to_pin_numbers() {
+        let lines = vec![(None, false); 300];
+        assert_eq!(line_pin_descriptions(&lines, &[]).pins().len(), 255);
+    }
+
+    #[test]
+    fn configured_lines_can_be_reconfigured() {
+        // Line 1 was requested by pigg, so the kernel reports it as used, as it does line 0
+        let lines = vec![
+            (Some("ID_SDA".to_string()), true),
+            (Some("GPIO1".to_string()), true),
+        ];
+        let pin_set = line_pin_descriptions(&lines, &[1]);
+        assert!(pin_set.pins()[0].options.is_empty());
+        assert_eq!(pin_set.pins()[1].options, LINE_OPTIONS);
+
+        let mut config = HardwareConfig::default();
+        config.pins.insert(1, PinFunction::Output(Some(true)));
+        assert!(validate(&config, &pin_set).is_empty());
+        config.pins.insert(0, PinFunction::Output(Some(true)));
+        assert_eq!(validate(&config, &pin_set).len(), 1);
+    }
+}
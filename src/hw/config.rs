@@ -1,16 +1,227 @@
+use crate::hw::pin_description::PinDescriptionSet;
 use crate::hw::pin_function::PinFunction;
+use crate::hw::spi::{SPIBus, SPIConfig};
+use crate::hw::uart::UARTConfig;
 use crate::hw::BCMPinNumber;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{Display, Formatter};
 use std::fs::File;
 use std::io::{BufReader, Write};
+use std::path::Path;
 use std::{fmt, io};
 
-/// [HardwareConfig] captures the current configuration of programmable GPIO pins
+/// The version of the .pigg file format written by [HardwareConfig::save]. Files written before
+/// the format was versioned have no "version" field, and are treated as version 0
+pub const CONFIG_FORMAT_VERSION: u32 = 3;
+
+/// The top-level fields that may appear in a .pigg file of the current format version
+const KNOWN_FIELDS: [&str; 8] = [
+    "version",
+    "board",
+    "pins",
+    "spi",
+    "uart",
+    "safe_state",
+    "annotations",
+    "profiles",
+];
+
+/// The name of the profile made of the top-level settings of a config file, that is applied
+/// when the file is loaded
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// The formats a config file can be written in, chosen by the extension of its filename
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigFormat {
+    /// Compact single-line JSON, used for ".pigg" files and any unknown extension
+    Json,
+    /// Human-editable YAML that can have comments, used for ".yaml" and ".yml" files
+    Yaml,
+}
+
+impl ConfigFormat {
+    /// The extensions of config files that can be loaded, for file pickers
+    #[allow(dead_code)] // for piglet
+    pub const EXTENSIONS: [&'static str; 3] = ["pigg", "yaml", "yml"];
+
+    /// Choose the format of the config file named `filename` from its extension
+    pub fn from_filename(filename: &str) -> Self {
+        match Path::new(filename)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("yaml") | Some("yml") => ConfigFormat::Yaml,
+            _ => ConfigFormat::Json,
+        }
+    }
+}
+
+/// Serialize a map in order of its keys, so saved files are stable and diff well
+fn ordered<S, K, V>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    K: Ord + Serialize,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
+
+/// Convert a JSON value to YAML, with keys that are numbers (such as a [BCMPinNumber]) written
+/// unquoted, as a person editing the file would write them
+fn json_to_yaml(value: serde_json::Value) -> serde_yaml::Value {
+    match value {
+        serde_json::Value::Null => serde_yaml::Value::Null,
+        serde_json::Value::Bool(value) => serde_yaml::Value::Bool(value),
+        serde_json::Value::Number(number) => match (number.as_u64(), number.as_i64()) {
+            (Some(number), _) => serde_yaml::Value::from(number),
+            (None, Some(number)) => serde_yaml::Value::from(number),
+            (None, None) => serde_yaml::Value::from(number.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(value) => serde_yaml::Value::String(value),
+        serde_json::Value::Array(values) => {
+            serde_yaml::Value::Sequence(values.into_iter().map(json_to_yaml).collect())
+        }
+        serde_json::Value::Object(fields) => serde_yaml::Value::Mapping(
+            fields
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = match key.parse::<u64>() {
+                        Ok(number) => serde_yaml::Value::from(number),
+                        Err(_) => serde_yaml::Value::String(key),
+                    };
+                    (key, json_to_yaml(value))
+                })
+                .collect(),
+        ),
+    }
+}
+
+/// A function that upgrades the JSON of a .pigg file from one format version to the next
+type Migration = fn(&mut serde_json::Value) -> io::Result<()>;
+
+/// Migrations to upgrade older .pigg files, where the entry at index `n` upgrades a file from
+/// format version `n` to version `n + 1`
+const MIGRATIONS: [Migration; CONFIG_FORMAT_VERSION as usize] =
+    [migrate_v0_to_v1, migrate_v1_to_v2, migrate_v2_to_v3];
+
+/// Version 0 files are the bare `{"pins":{...}}` files saved before the format was versioned.
+/// Their layout is unchanged in version 1, which only adds the "version" and "board" fields
+fn migrate_v0_to_v1(_value: &mut serde_json::Value) -> io::Result<()> {
+    Ok(())
+}
+
+/// Version 2 adds the "annotations" field, so files that use it are refused by earlier versions
+/// of pigg as newer, rather than for having an unknown field. Version 1 files are unchanged
+fn migrate_v1_to_v2(_value: &mut serde_json::Value) -> io::Result<()> {
+    Ok(())
+}
+
+/// Version 3 adds the "profiles" field. Version 2 files are unchanged
+fn migrate_v2_to_v3(_value: &mut serde_json::Value) -> io::Result<()> {
+    Ok(())
+}
+
+/// [BoardMetadata] records the board a [HardwareConfig] was saved from, so a file describes
+/// what it was created for
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BoardMetadata {
+    pub model: String,
+}
+
+/// The colours a pin's name and label can be shown in
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum LabelColour {
+    #[default]
+    White,
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Blue,
+    Purple,
+}
+
+impl LabelColour {
+    #[allow(dead_code)] // for piglet
+    pub const ALL: [LabelColour; 7] = [
+        LabelColour::White,
+        LabelColour::Red,
+        LabelColour::Orange,
+        LabelColour::Yellow,
+        LabelColour::Green,
+        LabelColour::Blue,
+        LabelColour::Purple,
+    ];
+}
+
+impl Display for LabelColour {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+/// [PinAnnotation] is the user's description of a pin, such as what it is wired to on their
+/// board, e.g. GPIO17 labelled "Pump relay"
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PinAnnotation {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colour: Option<LabelColour>,
+}
+
+impl PinAnnotation {
+    /// Return true if there is nothing in the annotation worth saving
+    #[allow(dead_code)] // for piglet
+    pub fn is_empty(&self) -> bool {
+        self.label.is_empty() && self.notes.is_empty() && self.colour.is_none()
+    }
+}
+
+/// [HardwareConfig] captures the current configuration of programmable GPIO pins, and the
+/// settings of any buses they are used for
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(deny_unknown_fields)]
 pub struct HardwareConfig {
+    /// The board the config was saved from, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board: Option<BoardMetadata>,
+    #[serde(serialize_with = "ordered")]
     pub pins: HashMap<BCMPinNumber, PinFunction>,
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "ordered"
+    )]
+    pub spi: HashMap<SPIBus, SPIConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uart: Option<UARTConfig>,
+    /// The function each pin is put into when it is no longer controlled, such as when the
+    /// client disconnects or piglet is stopped, e.g. an output driven low to turn off a relay
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "ordered"
+    )]
+    pub safe_state: HashMap<BCMPinNumber, PinFunction>,
+    /// Labels, notes and colours the user has given pins. These are not used by the hardware
+    #[serde(
+        default,
+        skip_serializing_if = "HashMap::is_empty",
+        serialize_with = "ordered"
+    )]
+    pub annotations: HashMap<BCMPinNumber, PinAnnotation>,
+    /// Other named setups of the pins of the same board, such as "bring-up" or "test", that can
+    /// be switched to. The top-level settings of the config are the [DEFAULT_PROFILE]
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, HardwareConfig>,
 }
 
 impl Display for HardwareConfig {
@@ -22,25 +233,284 @@ impl Display for HardwareConfig {
             for (bcm_pin_number, pin_function) in &self.pins {
                 writeln!(f, "\tBCM Pin #: {bcm_pin_number} - {}", pin_function)?;
             }
+            for (bus, spi_config) in &self.spi {
+                writeln!(
+                    f,
+                    "\t{bus}: {} at {}Hz",
+                    spi_config.mode, spi_config.clock_speed
+                )?;
+            }
+            if let Some(uart_config) = &self.uart {
+                writeln!(f, "\tUART: {uart_config}")?;
+            }
+            for (bcm_pin_number, pin_function) in &self.safe_state {
+                writeln!(
+                    f,
+                    "\tBCM Pin #: {bcm_pin_number} - Safe State: {pin_function}"
+                )?;
+            }
+            for (bcm_pin_number, annotation) in &self.annotations {
+                writeln!(
+                    f,
+                    "\tBCM Pin #: {bcm_pin_number} - Label: '{}'",
+                    annotation.label
+                )?;
+            }
+            if !self.profiles.is_empty() {
+                writeln!(f, "Profiles: {}", self.profile_names().join(", "))?;
+            }
             Ok(())
         }
     }
 }
 
+/// A problem found when validating a [HardwareConfig] against the pins of the hardware it is
+/// to be applied to
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigProblem {
+    /// The config sets the function of a pin the hardware does not have
+    UnknownPin(BCMPinNumber),
+    /// The config sets a pin to a function it does not support
+    UnsupportedFunction(BCMPinNumber, PinFunction),
+    /// The config sets a safe state for a pin the hardware does not have
+    UnknownSafeStatePin(BCMPinNumber),
+    /// The config sets a safe state for a pin to a function it does not support
+    UnsupportedSafeState(BCMPinNumber, PinFunction),
+}
+
+impl Display for ConfigProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigProblem::UnknownPin(bcm_pin_number) => {
+                write!(f, "There is no BCM pin #{bcm_pin_number} on this hardware")
+            }
+            ConfigProblem::UnsupportedFunction(bcm_pin_number, pin_function) => {
+                write!(
+                    f,
+                    "BCM pin #{bcm_pin_number} does not support {pin_function}"
+                )
+            }
+            ConfigProblem::UnknownSafeStatePin(bcm_pin_number) => write!(
+                f,
+                "There is no BCM pin #{bcm_pin_number} on this hardware for its safe state"
+            ),
+            ConfigProblem::UnsupportedSafeState(bcm_pin_number, pin_function) => write!(
+                f,
+                "BCM pin #{bcm_pin_number} does not support {pin_function} as its safe state"
+            ),
+        }
+    }
+}
+
+/// Check that every pin in `config` exists in `pins` and supports the function it is set to,
+/// in its configuration and its safe state. Return the problems found, in order of
+/// [BCMPinNumber], which will be empty if the config can be applied
+pub fn validate(config: &HardwareConfig, pins: &PinDescriptionSet) -> Vec<ConfigProblem> {
+    let mut problems = vec![];
+
+    let mut configured: Vec<_> = config.pins.iter().collect();
+    configured.sort_by_key(|(bcm_pin_number, _)| **bcm_pin_number);
+    for (&bcm_pin_number, pin_function) in configured {
+        match pins.get_by_bcm(bcm_pin_number) {
+            None => problems.push(ConfigProblem::UnknownPin(bcm_pin_number)),
+            Some(pin) if *pin_function != PinFunction::None && !pin.supports(pin_function) => {
+                problems.push(ConfigProblem::UnsupportedFunction(
+                    bcm_pin_number,
+                    pin_function.clone(),
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+
+    let mut safe_state: Vec<_> = config.safe_state.iter().collect();
+    safe_state.sort_by_key(|(bcm_pin_number, _)| **bcm_pin_number);
+    for (&bcm_pin_number, pin_function) in safe_state {
+        match pins.get_by_bcm(bcm_pin_number) {
+            None => problems.push(ConfigProblem::UnknownSafeStatePin(bcm_pin_number)),
+            Some(pin) if *pin_function != PinFunction::None && !pin.supports(pin_function) => {
+                problems.push(ConfigProblem::UnsupportedSafeState(
+                    bcm_pin_number,
+                    pin_function.clone(),
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+
+    problems
+}
+
+/// The layout of a .pigg file: a format version header followed by the [HardwareConfig] fields
+#[derive(Serialize)]
+struct ConfigFile<'a> {
+    version: u32,
+    #[serde(flatten)]
+    config: &'a HardwareConfig,
+}
+
 impl HardwareConfig {
-    /// Load a new GPIOConfig from the file named `filename`
+    /// Load a new GPIOConfig from the file named `filename`, in the [ConfigFormat] chosen by its
+    /// extension, upgrading it from an older format version if needed
     pub fn load(filename: &str) -> io::Result<HardwareConfig> {
         let file = File::open(filename)?;
         let reader = BufReader::new(file);
-        let config = serde_json::from_reader(reader)?;
-        Ok(config)
+        // Both formats are read into a JSON value, so they share the migrations and checks
+        let value: Result<serde_json::Value, String> = match ConfigFormat::from_filename(filename) {
+            ConfigFormat::Json => serde_json::from_reader(reader).map_err(|e| e.to_string()),
+            ConfigFormat::Yaml => serde_yaml::from_reader(reader).map_err(|e| e.to_string()),
+        };
+        let value = value.map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("'{filename}' is not a valid pigg config file: {e}"),
+            )
+        })?;
+        Self::from_value(value).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Could not load config from '{filename}': {e}"),
+            )
+        })
     }
 
-    /// Save this GPIOConfig to the file named `filename`
+    /// Create a [HardwareConfig] from the JSON contents of a .pigg file of any known format
+    /// version, applying the migrations needed to bring it to [CONFIG_FORMAT_VERSION]
+    fn from_value(mut value: serde_json::Value) -> io::Result<HardwareConfig> {
+        let fields = value
+            .as_object_mut()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "expected a JSON object"))?;
+
+        let version = match fields.remove("version") {
+            None => 0,
+            Some(version) => version
+                .as_u64()
+                .and_then(|version| u32::try_from(version).ok())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid format version '{version}'"),
+                    )
+                })?,
+        };
+
+        if version > CONFIG_FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "format version {version} is newer than the latest supported version \
+                    {CONFIG_FORMAT_VERSION}, it may have been saved by a newer version of pigg"
+                ),
+            ));
+        }
+
+        for migration in &MIGRATIONS[version as usize..] {
+            migration(&mut value)?;
+        }
+
+        if let Some(fields) = value.as_object() {
+            if let Some(unknown) = fields
+                .keys()
+                .find(|field| !KNOWN_FIELDS.contains(&field.as_str()))
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "unknown field '{unknown}', expected one of: {}",
+                        KNOWN_FIELDS.join(", ")
+                    ),
+                ));
+            }
+        }
+
+        serde_json::from_value(value).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// The names of the profiles in this config, starting with the [DEFAULT_PROFILE]
+    pub fn profile_names(&self) -> Vec<String> {
+        std::iter::once(DEFAULT_PROFILE.to_string())
+            .chain(
+                self.profiles
+                    .keys()
+                    .filter(|name| name.as_str() != DEFAULT_PROFILE)
+                    .cloned(),
+            )
+            .collect()
+    }
+
+    /// Return the config of the profile called `name`, where the [DEFAULT_PROFILE] is the
+    /// top-level settings of this config. Profiles do not have profiles of their own
+    pub fn profile(&self, name: &str) -> io::Result<HardwareConfig> {
+        let profile = if name == DEFAULT_PROFILE {
+            self
+        } else {
+            self.profiles.get(name).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "No profile called '{name}', expected one of: {}",
+                        self.profile_names().join(", ")
+                    ),
+                )
+            })?
+        };
+        Ok(HardwareConfig {
+            board: self.board.clone(),
+            profiles: BTreeMap::new(),
+            ..profile.clone()
+        })
+    }
+
+    /// The config to apply to hardware configured with this config to switch it to `next` in
+    /// one step: the settings of `next`, plus the pins only this config uses released back to
+    /// [PinFunction::None], so none keep a function from the previous profile
+    #[allow(dead_code)] // for piggui
+    pub fn switch_to(&self, next: &HardwareConfig) -> HardwareConfig {
+        let mut transition = next.clone();
+        for bcm_pin_number in self.pins.keys() {
+            transition
+                .pins
+                .entry(*bcm_pin_number)
+                .or_insert(PinFunction::None);
+        }
+        transition
+    }
+
+    /// Remove the pin functions and safe states that `problems` were found with, so the rest of
+    /// the config can be applied
+    #[allow(dead_code)] // for piglet
+    pub fn remove_problems(&mut self, problems: &[ConfigProblem]) {
+        for problem in problems {
+            match problem {
+                ConfigProblem::UnknownPin(bcm_pin_number)
+                | ConfigProblem::UnsupportedFunction(bcm_pin_number, _) => {
+                    self.pins.remove(bcm_pin_number);
+                }
+                ConfigProblem::UnknownSafeStatePin(bcm_pin_number)
+                | ConfigProblem::UnsupportedSafeState(bcm_pin_number, _) => {
+                    self.safe_state.remove(bcm_pin_number);
+                }
+            }
+        }
+    }
+
+    /// Save this GPIOConfig to the file named `filename`, in the [ConfigFormat] chosen by its
+    /// extension, with a header of the current format version
     #[allow(dead_code)] // for piglet
     pub fn save(&self, filename: &str) -> io::Result<String> {
+        let config_file = ConfigFile {
+            version: CONFIG_FORMAT_VERSION,
+            config: self,
+        };
+        let contents = match ConfigFormat::from_filename(filename) {
+            ConfigFormat::Json => serde_json::to_string(&config_file)?,
+            // Written via a JSON value so enums are plain maps, as in JSON, not YAML tags
+            ConfigFormat::Yaml => {
+                serde_yaml::to_string(&json_to_yaml(serde_json::to_value(&config_file)?))
+                    .map_err(io::Error::other)?
+            }
+        };
         let mut file = File::create(filename)?;
-        let contents = serde_json::to_string(self)?;
         file.write_all(contents.as_bytes())?;
         Ok(format!("File saved successfully to {}", filename))
     }
@@ -48,12 +518,19 @@ impl HardwareConfig {
 
 #[cfg(test)]
 mod test {
-    use crate::hw::config::HardwareConfig;
-    use crate::hw::pin_function::PinFunction;
+    use crate::hw::config::{
+        validate, BoardMetadata, ConfigFormat, ConfigProblem, HardwareConfig, LabelColour,
+        PinAnnotation, CONFIG_FORMAT_VERSION,
+    };
+    use crate::hw::pin_description::{PinDescription, PinDescriptionSet};
+    use crate::hw::pin_function::{AltMode, PinFunction};
+    use crate::hw::spi::{SPIBus, SPIConfig, SPIMode};
+    use crate::hw::uart::{UARTConfig, UARTParity};
     use crate::hw::InputPull::PullUp;
     use crate::hw::LevelChange;
+    use crate::hw::{InputEdge, InputFilter};
     use chrono::Utc;
-    use std::collections::HashMap;
+    use std::borrow::Cow;
     use std::fs;
     use std::fs::File;
     use std::io::Write;
@@ -74,10 +551,8 @@ mod test {
 
     #[test]
     fn save_one_pin_config_input_no_pullup() {
-        let mut config = HardwareConfig {
-            pins: HashMap::new(),
-        };
-        config.pins.insert(1, PinFunction::Input(None));
+        let mut config = HardwareConfig::default();
+        config.pins.insert(1, PinFunction::Input(None, None));
         let output_dir = tempdir().expect("Could not create a tempdir").into_path();
         let test_file = output_dir.join("test.pigg");
 
@@ -89,7 +564,7 @@ mod test {
             )
             .expect("Config save failed");
 
-        let pin_config = r#"{"pins":{"1":{"Input":null}}}"#;
+        let pin_config = r#"{"version":3,"pins":{"1":{"Input":null}}}"#;
         let contents = fs::read_to_string(test_file).expect("Could not read test file");
         assert_eq!(contents, pin_config);
     }
@@ -106,7 +581,7 @@ mod test {
             HardwareConfig::load(test_file.to_str().expect("Could not convert path to str"))
                 .expect("Failed to load config");
         assert_eq!(config.pins.len(), 1);
-        assert_eq!(config.pins.get(&1), Some(&PinFunction::Input(None)));
+        assert_eq!(config.pins.get(&1), Some(&PinFunction::Input(None, None)));
     }
 
     #[test]
@@ -123,15 +598,38 @@ mod test {
         // GPIO26 configured as an Input - with an internal PullUp
         assert_eq!(
             config.pins.get(&26),
-            Some(&PinFunction::Input(Some(PullUp)))
+            Some(&PinFunction::Input(Some(PullUp), None))
+        );
+    }
+
+    #[test]
+    fn save_and_load_input_filter() {
+        let mut config = HardwareConfig::default();
+        let input = PinFunction::Input(
+            Some(PullUp),
+            Some(InputFilter {
+                edge: InputEdge::Falling,
+                debounce_ms: Some(20),
+            }),
         );
+        config.pins.insert(17, input.clone());
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.pigg");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let expected = r#"{"version":3,"pins":{"17":{"Input":{"pull":"PullUp","filter":{"edge":"Falling","debounce_ms":20}}}}}"#;
+        let contents = fs::read_to_string(&test_file).expect("Could not read test file");
+        assert_eq!(contents, expected);
+
+        let loaded = HardwareConfig::load(filename).expect("Could not load config");
+        assert_eq!(loaded.pins.get(&17), Some(&input));
     }
 
     #[test]
     fn save_one_pin_config_output_with_level() {
-        let mut config = HardwareConfig {
-            pins: HashMap::new(),
-        };
+        let mut config = HardwareConfig::default();
         config.pins.insert(7, PinFunction::Output(Some(true))); // GPIO7 output set to 1
 
         let output_dir = tempdir().expect("Could not create a tempdir").into_path();
@@ -141,16 +639,131 @@ mod test {
             .save(test_file.to_str().expect("Could not convert path to str"))
             .expect("Could not save config");
 
-        let pin_config = r#"{"pins":{"7":{"Output":true}}}"#;
+        let pin_config = r#"{"version":3,"pins":{"7":{"Output":true}}}"#;
         let contents = fs::read_to_string(test_file).expect("Could not read test file");
         assert_eq!(contents, pin_config);
     }
 
     #[test]
-    fn save_one_pin_config_output_no_level() {
-        let mut config = HardwareConfig {
-            pins: HashMap::new(),
+    fn save_and_load_pwm_config() {
+        let mut config = HardwareConfig::default();
+        let pwm = PinFunction::Pwm {
+            frequency: 500.0,
+            duty_cycle: 0.25,
+        };
+        config.pins.insert(18, pwm.clone());
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.pigg");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let pin_config =
+            r#"{"version":3,"pins":{"18":{"Pwm":{"frequency":500.0,"duty_cycle":0.25}}}}"#;
+        let contents = fs::read_to_string(&test_file).expect("Could not read test file");
+        assert_eq!(contents, pin_config);
+
+        let loaded = HardwareConfig::load(filename).expect("Could not load config");
+        assert_eq!(loaded.pins.get(&18), Some(&pwm));
+    }
+
+    #[test]
+    fn save_and_load_spi_config() {
+        let mut config = HardwareConfig::default();
+        config.pins.insert(11, PinFunction::SPI0_SCLK);
+        let spi_config = SPIConfig {
+            mode: SPIMode::Mode3,
+            clock_speed: 500_000,
+        };
+        config.spi.insert(SPIBus::SPI0, spi_config);
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.pigg");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let expected = r#"{"version":3,"pins":{"11":"SPI0_SCLK"},"spi":{"SPI0":{"mode":"Mode3","clock_speed":500000}}}"#;
+        let contents = fs::read_to_string(&test_file).expect("Could not read test file");
+        assert_eq!(contents, expected);
+
+        let loaded = HardwareConfig::load(filename).expect("Could not load config");
+        assert_eq!(loaded.spi.get(&SPIBus::SPI0), Some(&spi_config));
+    }
+
+    #[test]
+    fn save_and_load_uart_config() {
+        let mut config = HardwareConfig::default();
+        config.pins.insert(14, PinFunction::UART0_TXD);
+        let uart_config = UARTConfig {
+            baud_rate: 9600,
+            parity: UARTParity::Odd,
+            stop_bits: 2,
+        };
+        config.uart = Some(uart_config);
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.pigg");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let expected = r#"{"version":3,"pins":{"14":"UART0_TXD"},"uart":{"baud_rate":9600,"parity":"Odd","stop_bits":2}}"#;
+        let contents = fs::read_to_string(&test_file).expect("Could not read test file");
+        assert_eq!(contents, expected);
+
+        let loaded = HardwareConfig::load(filename).expect("Could not load config");
+        assert_eq!(loaded.uart, Some(uart_config));
+    }
+
+    #[test]
+    fn save_and_load_safe_state() {
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+        config
+            .safe_state
+            .insert(17, PinFunction::Output(Some(false)));
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.pigg");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let expected =
+            r#"{"version":3,"pins":{"17":{"Output":true}},"safe_state":{"17":{"Output":false}}}"#;
+        let contents = fs::read_to_string(&test_file).expect("Could not read test file");
+        assert_eq!(contents, expected);
+
+        let loaded = HardwareConfig::load(filename).expect("Could not load config");
+        assert_eq!(
+            loaded.safe_state.get(&17),
+            Some(&PinFunction::Output(Some(false)))
+        );
+    }
+
+    #[test]
+    fn save_and_load_alt_function() {
+        let mut config = HardwareConfig::default();
+        let alt = PinFunction::Alt {
+            mode: AltMode::Alt5,
+            name: Cow::Borrowed("I2C6_SDA"),
         };
+        config.pins.insert(22, alt.clone());
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.pigg");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let expected = r#"{"version":3,"pins":{"22":{"Alt":{"mode":"Alt5","name":"I2C6_SDA"}}}}"#;
+        let contents = fs::read_to_string(&test_file).expect("Could not read test file");
+        assert_eq!(contents, expected);
+
+        let loaded = HardwareConfig::load(filename).expect("Could not load config");
+        assert_eq!(loaded.pins.get(&22), Some(&alt));
+    }
+
+    #[test]
+    fn save_one_pin_config_output_no_level() {
+        let mut config = HardwareConfig::default();
         config.pins.insert(7, PinFunction::Output(None)); // GPIO7 output set to 1
 
         let output_dir = tempdir().expect("Could not create a tempdir").into_path();
@@ -160,8 +773,488 @@ mod test {
             .save(test_file.to_str().expect("Could not convert path to str"))
             .expect("Could not save config");
 
-        let pin_config = r#"{"pins":{"7":{"Output":null}}}"#;
+        let pin_config = r#"{"version":3,"pins":{"7":{"Output":null}}}"#;
         let contents = fs::read_to_string(test_file).expect("Could not read test file");
         assert_eq!(contents, pin_config);
     }
+
+    fn write_test_file(contents: &str) -> PathBuf {
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.pigg");
+        let mut file = File::create(&test_file).expect("Could not create test file");
+        file.write_all(contents.as_bytes())
+            .expect("Could not write to test file");
+        test_file
+    }
+
+    #[test]
+    fn save_and_load_board_metadata() {
+        let mut config = HardwareConfig {
+            board: Some(BoardMetadata {
+                model: "Raspberry Pi 4 Model B Rev 1.4".into(),
+            }),
+            ..Default::default()
+        };
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.pigg");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let expected = r#"{"version":3,"board":{"model":"Raspberry Pi 4 Model B Rev 1.4"},"pins":{"17":{"Output":true}}}"#;
+        let contents = fs::read_to_string(&test_file).expect("Could not read test file");
+        assert_eq!(contents, expected);
+
+        let loaded = HardwareConfig::load(filename).expect("Could not load config");
+        assert_eq!(loaded.board, config.board);
+    }
+
+    #[test]
+    fn load_unversioned_file_is_migrated() {
+        let test_file = write_test_file(r#"{"pins":{"17":{"Output":true}}}"#);
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        let config = HardwareConfig::load(filename).expect("Could not load unversioned config");
+        assert_eq!(config.pins.get(&17), Some(&PinFunction::Output(Some(true))));
+        assert!(config.board.is_none());
+
+        // Saving it again writes the current format version
+        config.save(filename).expect("Could not save config");
+        let contents = fs::read_to_string(&test_file).expect("Could not read test file");
+        assert!(contents.starts_with(&format!(r#"{{"version":{CONFIG_FORMAT_VERSION},"#)));
+    }
+
+    #[test]
+    fn load_version_1_file_is_migrated() {
+        let test_file = write_test_file(r#"{"version":1,"pins":{"17":{"Output":true}}}"#);
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        let config = HardwareConfig::load(filename).expect("Could not load version 1 config");
+        assert_eq!(config.pins.get(&17), Some(&PinFunction::Output(Some(true))));
+    }
+
+    #[test]
+    fn load_newer_version_fails() {
+        let test_file = write_test_file(r#"{"version":99,"pins":{}}"#);
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        let error = HardwareConfig::load(filename).expect_err("Newer version should not load");
+        assert!(error.to_string().contains("format version 99 is newer"));
+    }
+
+    #[test]
+    fn load_unknown_field_fails() {
+        let test_file = write_test_file(r#"{"version":3,"pins":{},"colours":{}}"#);
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        let error = HardwareConfig::load(filename).expect_err("Unknown field should not load");
+        assert!(error.to_string().contains("unknown field 'colours'"));
+        assert!(error.to_string().contains(filename));
+    }
+
+    #[test]
+    fn load_invalid_version_fails() {
+        let test_file = write_test_file(r#"{"version":"one","pins":{}}"#);
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        let error = HardwareConfig::load(filename).expect_err("Invalid version should not load");
+        assert!(error.to_string().contains("invalid format version"));
+    }
+
+    #[test]
+    fn save_and_load_annotations() {
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(false)));
+        let annotation = PinAnnotation {
+            label: "Pump relay".into(),
+            notes: "Active high, via the transistor on the proto board".into(),
+            colour: Some(LabelColour::Blue),
+        };
+        config.annotations.insert(17, annotation.clone());
+        config.annotations.insert(
+            22,
+            PinAnnotation {
+                label: "Spare".into(),
+                ..Default::default()
+            },
+        );
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.pigg");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let loaded = HardwareConfig::load(filename).expect("Could not load config");
+        assert_eq!(loaded.annotations.get(&17), Some(&annotation));
+        assert_eq!(
+            loaded.annotations.get(&22).map(|a| a.label.as_str()),
+            Some("Spare")
+        );
+        assert_eq!(loaded.annotations.get(&22).and_then(|a| a.colour), None);
+    }
+
+    #[test]
+    fn empty_fields_of_annotation_not_saved() {
+        let mut config = HardwareConfig::default();
+        config.annotations.insert(
+            4,
+            PinAnnotation {
+                colour: Some(LabelColour::Red),
+                ..Default::default()
+            },
+        );
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.pigg");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let expected = r#"{"version":3,"pins":{},"annotations":{"4":{"colour":"Red"}}}"#;
+        let contents = fs::read_to_string(&test_file).expect("Could not read test file");
+        assert_eq!(contents, expected);
+    }
+
+    #[test]
+    fn empty_annotation() {
+        assert!(PinAnnotation::default().is_empty());
+        assert!(!PinAnnotation {
+            notes: "Not connected".into(),
+            ..Default::default()
+        }
+        .is_empty());
+    }
+
+    fn test_pins() -> PinDescriptionSet {
+        PinDescriptionSet::from(vec![
+            PinDescription {
+                bpn: 1,
+                bcm: None,
+                name: Cow::from("3V3"),
+                options: Cow::from(vec![]),
+            },
+            PinDescription {
+                bpn: 11,
+                bcm: Some(17),
+                name: Cow::from("GPIO17"),
+                options: Cow::from(vec![
+                    PinFunction::Input(None, None),
+                    PinFunction::Output(None),
+                ]),
+            },
+            PinDescription {
+                bpn: 15,
+                bcm: Some(22),
+                name: Cow::from("GPIO22"),
+                options: Cow::from(vec![PinFunction::Input(None, None)]),
+            },
+        ])
+    }
+
+    #[test]
+    fn valid_config_has_no_problems() {
+        let mut config = HardwareConfig::default();
+        config
+            .pins
+            .insert(17, PinFunction::Input(Some(PullUp), None));
+        config.pins.insert(22, PinFunction::None);
+        config
+            .safe_state
+            .insert(17, PinFunction::Output(Some(false)));
+        assert!(validate(&config, &test_pins()).is_empty());
+    }
+
+    #[test]
+    fn invalid_config_problems() {
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+        config.pins.insert(22, PinFunction::Output(None));
+        config.pins.insert(40, PinFunction::Input(None, None));
+        config
+            .safe_state
+            .insert(22, PinFunction::Output(Some(false)));
+        config
+            .safe_state
+            .insert(41, PinFunction::Output(Some(false)));
+
+        let problems = validate(&config, &test_pins());
+        assert_eq!(
+            problems,
+            vec![
+                ConfigProblem::UnsupportedFunction(22, PinFunction::Output(None)),
+                ConfigProblem::UnknownPin(40),
+                ConfigProblem::UnsupportedSafeState(22, PinFunction::Output(Some(false))),
+                ConfigProblem::UnknownSafeStatePin(41),
+            ]
+        );
+
+        // Removing the problems leaves only the pins that can be applied
+        config.remove_problems(&problems);
+        assert_eq!(config.pins.len(), 1);
+        assert!(config.pins.contains_key(&17));
+        assert!(config.safe_state.is_empty());
+        assert!(validate(&config, &test_pins()).is_empty());
+    }
+
+    #[test]
+    fn config_problem_display() {
+        assert_eq!(
+            ConfigProblem::UnknownPin(40).to_string(),
+            "There is no BCM pin #40 on this hardware"
+        );
+        assert_eq!(
+            ConfigProblem::UnsupportedFunction(22, PinFunction::Output(None)).to_string(),
+            format!("BCM pin #22 does not support {}", PinFunction::Output(None))
+        );
+    }
+
+    #[test]
+    fn format_from_extension() {
+        assert_eq!(
+            ConfigFormat::from_filename("board.pigg"),
+            ConfigFormat::Json
+        );
+        assert_eq!(
+            ConfigFormat::from_filename("board.yaml"),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(
+            ConfigFormat::from_filename("dir/board.YML"),
+            ConfigFormat::Yaml
+        );
+        assert_eq!(ConfigFormat::from_filename("board"), ConfigFormat::Json);
+    }
+
+    #[test]
+    fn save_pins_in_order() {
+        let mut config = HardwareConfig::default();
+        for bcm_pin_number in [22, 4, 17, 2] {
+            config
+                .pins
+                .insert(bcm_pin_number, PinFunction::Output(None));
+        }
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.pigg");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let expected = r#"{"version":3,"pins":{"2":{"Output":null},"4":{"Output":null},"17":{"Output":null},"22":{"Output":null}}}"#;
+        let contents = fs::read_to_string(&test_file).expect("Could not read test file");
+        assert_eq!(contents, expected);
+    }
+
+    #[test]
+    fn save_yaml() {
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+        config
+            .pins
+            .insert(4, PinFunction::Input(Some(PullUp), None));
+        config.pins.insert(11, PinFunction::SPI0_SCLK);
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.yaml");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let expected = "version: 3
+pins:
+  4:
+    Input: PullUp
+  11: SPI0_SCLK
+  17:
+    Output: true
+";
+        let contents = fs::read_to_string(&test_file).expect("Could not read test file");
+        assert_eq!(contents, expected);
+    }
+
+    #[test]
+    fn yaml_round_trips_with_json() {
+        let mut config = HardwareConfig::default();
+        config.pins.insert(7, PinFunction::Output(None));
+        config.pins.insert(
+            17,
+            PinFunction::Input(
+                Some(PullUp),
+                Some(InputFilter {
+                    edge: InputEdge::Falling,
+                    debounce_ms: Some(20),
+                }),
+            ),
+        );
+        config.pins.insert(
+            18,
+            PinFunction::Pwm {
+                frequency: 500.0,
+                duty_cycle: 0.25,
+            },
+        );
+        config.spi.insert(
+            SPIBus::SPI0,
+            SPIConfig {
+                mode: SPIMode::Mode3,
+                clock_speed: 500_000,
+            },
+        );
+        config
+            .safe_state
+            .insert(7, PinFunction::Output(Some(false)));
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let json_file = output_dir.join("test.pigg");
+        let json_filename = json_file.to_str().expect("Could not convert path to str");
+        let yaml_file = output_dir.join("test.yml");
+        let yaml_filename = yaml_file.to_str().expect("Could not convert path to str");
+
+        // JSON -> YAML -> JSON gives the same file as was first saved
+        config.save(json_filename).expect("Could not save JSON");
+        let original = fs::read_to_string(&json_file).expect("Could not read JSON file");
+        HardwareConfig::load(json_filename)
+            .expect("Could not load JSON")
+            .save(yaml_filename)
+            .expect("Could not save YAML");
+        HardwareConfig::load(yaml_filename)
+            .expect("Could not load YAML")
+            .save(json_filename)
+            .expect("Could not save JSON");
+        let round_tripped = fs::read_to_string(&json_file).expect("Could not read JSON file");
+        assert_eq!(round_tripped, original);
+    }
+
+    #[test]
+    fn load_yaml_test_file() {
+        let root = std::env::var("CARGO_MANIFEST_DIR").expect("Could not get manifest dir");
+        let path = PathBuf::from(root).join("configs/andrews_board.yaml");
+        let config = HardwareConfig::load(path.to_str().expect("Could not get Path as str"))
+            .expect("Could not load config from YAML file");
+        assert_eq!(config.pins.get(&17), Some(&PinFunction::Output(Some(true))));
+        assert_eq!(
+            config.pins.get(&26),
+            Some(&PinFunction::Input(Some(PullUp), None))
+        );
+    }
+
+    #[test]
+    fn load_yaml_unknown_field_fails() {
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.yaml");
+        fs::write(&test_file, "version: 3\npins: {}\ncolours: {}\n")
+            .expect("Could not write test file");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        let error = HardwareConfig::load(filename).expect_err("Unknown field should not load");
+        assert!(error.to_string().contains("unknown field 'colours'"));
+    }
+
+    #[test]
+    fn load_unknown_field_in_profile_fails() {
+        let test_file = write_test_file(
+            r#"{"version":3,"pins":{},"profiles":{"test":{"pins":{},"safe_stat":{}}}}"#,
+        );
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        let error = HardwareConfig::load(filename).expect_err("Unknown field should not load");
+        assert!(error.to_string().contains("unknown field `safe_stat`"));
+    }
+
+    #[test]
+    fn load_unknown_field_in_pin_fails() {
+        let test_file = write_test_file(
+            r#"{"version":3,"pins":{"18":{"Pwm":{"frequency":1000.0,"duty":0.5}}}}"#,
+        );
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        let error = HardwareConfig::load(filename).expect_err("Unknown field should not load");
+        assert!(error.to_string().contains("unknown field `duty`"));
+    }
+
+    #[test]
+    fn load_unknown_field_in_safe_state_fails() {
+        let safe_state = r#"{"version":3,"pins":{},"safe_state":{"17":{"Input":{"pull":null,"filter":{"edge":"Rising","debounce_ms":5}}}}}"#;
+        let test_file = write_test_file(safe_state);
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        HardwareConfig::load(filename).expect("Could not load safe state");
+
+        let test_file = write_test_file(&safe_state.replace("debounce_ms", "debounce"));
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        HardwareConfig::load(filename).expect_err("Unknown field should not load");
+    }
+
+    fn profiles_config() -> HardwareConfig {
+        let mut run = HardwareConfig::default();
+        run.pins.insert(17, PinFunction::Output(Some(true)));
+        run.pins.insert(22, PinFunction::Input(None, None));
+        let mut test = HardwareConfig::default();
+        test.pins.insert(22, PinFunction::Output(Some(false)));
+
+        let mut config = HardwareConfig {
+            board: Some(BoardMetadata {
+                model: "Raspberry Pi 4 Model B Rev 1.4".into(),
+            }),
+            ..Default::default()
+        };
+        config.pins.insert(4, PinFunction::Input(None, None));
+        config.profiles.insert("run".into(), run);
+        config.profiles.insert("test".into(), test);
+        config
+    }
+
+    #[test]
+    fn profile_names_start_with_default() {
+        assert_eq!(
+            profiles_config().profile_names(),
+            vec!["default".to_string(), "run".to_string(), "test".to_string()]
+        );
+        assert_eq!(HardwareConfig::default().profile_names(), vec!["default"]);
+    }
+
+    #[test]
+    fn get_profiles() {
+        let config = profiles_config();
+
+        let default = config.profile("default").expect("Could not get default");
+        assert_eq!(default.pins.len(), 1);
+        assert!(default.profiles.is_empty());
+
+        let run = config.profile("run").expect("Could not get 'run' profile");
+        assert_eq!(run.pins.get(&17), Some(&PinFunction::Output(Some(true))));
+        assert_eq!(run.board, config.board);
+
+        let error = config
+            .profile("bring-up")
+            .expect_err("Unknown profile found");
+        assert!(error.to_string().contains("default, run, test"));
+    }
+
+    #[test]
+    fn switch_to_releases_unused_pins() {
+        let config = profiles_config();
+        let run = config.profile("run").expect("Could not get 'run' profile");
+        let test = config
+            .profile("test")
+            .expect("Could not get 'test' profile");
+
+        let transition = run.switch_to(&test);
+        assert_eq!(transition.pins.get(&17), Some(&PinFunction::None));
+        assert_eq!(
+            transition.pins.get(&22),
+            Some(&PinFunction::Output(Some(false)))
+        );
+        assert_eq!(transition.pins.len(), 2);
+    }
+
+    #[test]
+    fn save_and_load_profiles() {
+        let config = profiles_config();
+
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("test.yaml");
+        let filename = test_file.to_str().expect("Could not convert path to str");
+        config.save(filename).expect("Could not save config");
+
+        let loaded = HardwareConfig::load(filename).expect("Could not load config");
+        assert_eq!(loaded.profile_names(), config.profile_names());
+        assert_eq!(
+            loaded
+                .profile("test")
+                .expect("Could not get 'test' profile")
+                .pins
+                .get(&22),
+            Some(&PinFunction::Output(Some(false)))
+        );
+    }
 }
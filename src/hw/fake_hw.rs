@@ -1,29 +1,213 @@
 use rand::Rng;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
 /// Fake Implementation of GPIO for hosts that don't have GPIO (Linux, macOS, Windows)
 use std::{io, thread};
 
-use crate::hw::{BCMPinNumber, PinFunction, PinLevel};
+use crate::hw::i2c::{I2CAddress, I2C_SCAN_RANGE};
+use crate::hw::pin_function::AltMode;
+use crate::hw::spi::{SPIBus, SPIConfig};
+use crate::hw::stimulus::{Stimulus, Waveform};
+use crate::hw::uart::UARTConfig;
+use crate::hw::{BCMPinNumber, InputPull, PinFunction, PinLevel, PinMode, PinProbe};
 
-use super::Hardware;
+use super::{
+    check_chip_select, check_gpclk, check_pwm, check_spi_config, check_supported,
+    check_uart_config, Hardware, LevelFilter,
+};
 use super::{HardwareDescription, HardwareDetails};
 use crate::hw::pin_description::PinDescriptionSet;
-use crate::hw::pin_descriptions::*;
-
-/// FakeHW Pins - mimicking Model the 40 pin GPIO
-// TODO make private again
-//noinspection DuplicatedCode
-pub const FAKE_PIN_DESCRIPTIONS: PinDescriptionSet = PinDescriptionSet::new([
-    PIN_1, PIN_2, PIN_3, PIN_4, PIN_5, PIN_6, PIN_7, PIN_8, PIN_9, PIN_10, PIN_11, PIN_12, PIN_13,
-    PIN_14, PIN_15, PIN_16, PIN_17, PIN_18, PIN_19, PIN_20, PIN_21, PIN_22, PIN_23, PIN_24, PIN_25,
-    PIN_26, PIN_27, PIN_28, PIN_29, PIN_30, PIN_31, PIN_32, PIN_33, PIN_34, PIN_35, PIN_36, PIN_37,
-    PIN_38, PIN_39, PIN_40,
-]);
-
-pub struct FakeHW;
-
-pub fn get() -> impl Hardware {
-    FakeHW {}
+use crate::hw::pin_descriptions::HEADER_40_PINS;
+
+/// FakeHW Pins - mimicking a Pi with the 40 pin GPIO header
+pub const FAKE_PIN_DESCRIPTIONS: PinDescriptionSet = PinDescriptionSet::new(HEADER_40_PINS);
+
+pub struct FakeHW {
+    /// The functions the pins have been configured with, so outputs (including PWM) can be
+    /// simulated as if they were being driven
+    configured_pins: HashMap<BCMPinNumber, PinFunction>,
+    /// Virtual devices connected to the I2C bus, by address
+    i2c_devices: HashMap<I2CAddress, FakeI2CDevice>,
+    /// The UART is a loopback port: bytes sent on it are delivered to the receiving thread
+    uart: Option<mpsc::Sender<Vec<u8>>>,
+    /// The last level of each input simulated and each output set, shared with the threads
+    /// simulating inputs
+    levels: Arc<Mutex<HashMap<BCMPinNumber, PinLevel>>>,
+    /// Flags to stop the threads simulating inputs, cleared when the input is reconfigured
+    input_threads: HashMap<BCMPinNumber, Arc<AtomicBool>>,
+    /// Inputs wired back to an output, by the output's [BCMPinNumber]
+    loopbacks: HashMap<BCMPinNumber, Vec<LoopbackInput>>,
+    /// Waveforms to drive inputs with. Without one, inputs change to random levels
+    stimulus: Option<Stimulus>,
+}
+
+/// An input that follows the level of an output, as if wired to it
+struct LoopbackInput {
+    bcm_pin_number: BCMPinNumber,
+    level_filter: LevelFilter,
+    callback: Box<dyn FnMut(BCMPinNumber, PinLevel) + Send + Sync>,
+}
+
+pub fn get() -> FakeHW {
+    FakeHW::new()
+}
+
+/// A virtual I2C device with 256 byte-wide registers, accessed in the way most devices are:
+/// the first byte written sets the register pointer, then each byte written or read after that
+/// accesses the register pointed to and increments the pointer
+struct FakeI2CDevice {
+    registers: [u8; 256],
+    pointer: u8,
+}
+
+impl FakeI2CDevice {
+    /// Create a new device with all registers set to `fill` except those in `initial`
+    fn new(fill: u8, initial: &[(u8, u8)]) -> Self {
+        let mut registers = [fill; 256];
+        for (register, value) in initial {
+            registers[*register as usize] = *value;
+        }
+        FakeI2CDevice {
+            registers,
+            pointer: 0,
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) {
+        if let Some((register, values)) = data.split_first() {
+            self.pointer = *register;
+            for value in values {
+                self.registers[self.pointer as usize] = *value;
+                self.pointer = self.pointer.wrapping_add(1);
+            }
+        }
+    }
+
+    fn read(&mut self, length: usize) -> Vec<u8> {
+        (0..length)
+            .map(|_| {
+                let value = self.registers[self.pointer as usize];
+                self.pointer = self.pointer.wrapping_add(1);
+                value
+            })
+            .collect()
+    }
+}
+
+impl FakeHW {
+    fn new() -> Self {
+        let mut i2c_devices = HashMap::new();
+        // GPIO expander (like an MCP23017) with all pins as inputs (IODIRA and IODIRB set)
+        i2c_devices.insert(
+            0x20,
+            FakeI2CDevice::new(0x00, &[(0x00, 0xFF), (0x01, 0xFF)]),
+        );
+        // Temperature sensor (like a TMP102) reading 25.0C in its temperature register
+        i2c_devices.insert(
+            0x48,
+            FakeI2CDevice::new(0x00, &[(0x00, 0x19), (0x01, 0x00)]),
+        );
+        // 256 byte EEPROM (like a 24C02) that has been erased
+        i2c_devices.insert(0x50, FakeI2CDevice::new(0xFF, &[]));
+
+        FakeHW {
+            configured_pins: Default::default(),
+            i2c_devices,
+            uart: None,
+            levels: Default::default(),
+            input_threads: Default::default(),
+            loopbacks: Default::default(),
+            stimulus: None,
+        }
+    }
+
+    /// Drive inputs configured after this using the waveforms in `stimulus`. Inputs it has no
+    /// waveform for stay low
+    pub fn set_stimulus(&mut self, stimulus: Stimulus) {
+        self.stimulus = Some(stimulus);
+    }
+
+    /// Stop simulating the input `bcm_pin_number`, if it was being simulated
+    fn stop_input(&mut self, bcm_pin_number: BCMPinNumber) {
+        if let Some(running) = self.input_threads.remove(&bcm_pin_number) {
+            running.store(false, Ordering::Relaxed);
+        }
+        for inputs in self.loopbacks.values_mut() {
+            inputs.retain(|input| input.bcm_pin_number != bcm_pin_number);
+        }
+    }
+
+    /// Set the level of an output, and of any inputs wired back to it
+    fn drive_output(&mut self, bcm_pin_number: BCMPinNumber, level: PinLevel) {
+        let mut levels = self.levels.lock().unwrap_or_else(|e| e.into_inner());
+        levels.insert(bcm_pin_number, level);
+        if let Some(inputs) = self.loopbacks.get_mut(&bcm_pin_number) {
+            for input in inputs {
+                levels.insert(input.bcm_pin_number, level);
+                if input.level_filter.report(level, Instant::now()) {
+                    (input.callback)(input.bcm_pin_number, level);
+                }
+            }
+        }
+    }
+
+    /// Start a thread that changes the level of an input, either following `waveform` or to
+    /// random levels if there is none, until the input is reconfigured
+    fn simulate_input<C>(
+        &mut self,
+        bcm_pin_number: BCMPinNumber,
+        waveform: Option<(Vec<(PinLevel, Duration)>, bool)>,
+        mut level_filter: LevelFilter,
+        mut callback: C,
+    ) where
+        C: FnMut(BCMPinNumber, PinLevel) + Send + Sync + Clone + 'static,
+    {
+        let running = Arc::new(AtomicBool::new(true));
+        self.input_threads.insert(bcm_pin_number, running.clone());
+        let levels = self.levels.clone();
+        let mut set_level = move |level: PinLevel| {
+            if let Ok(mut levels) = levels.lock() {
+                levels.insert(bcm_pin_number, level);
+            }
+            if level_filter.report(level, Instant::now()) {
+                callback(bcm_pin_number, level);
+            }
+        };
+
+        thread::spawn(move || match waveform {
+            Some((steps, repeat)) => loop {
+                for (level, duration) in &steps {
+                    if !running.load(Ordering::Relaxed) {
+                        return;
+                    }
+                    set_level(*level);
+                    thread::sleep(*duration);
+                }
+                if !repeat {
+                    return;
+                }
+            },
+            None => {
+                let mut rng = rand::thread_rng();
+                while running.load(Ordering::Relaxed) {
+                    set_level(rng.gen());
+                    thread::sleep(Duration::from_millis(666));
+                }
+            }
+        });
+    }
+
+    /// Get the virtual device at `address` on the I2C bus
+    fn i2c_device(&mut self, address: I2CAddress) -> io::Result<&mut FakeI2CDevice> {
+        self.i2c_devices.get_mut(&address).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No I2C device at address {address:#04x}"),
+            )
+        })
+    }
 }
 
 impl Hardware for FakeHW {
@@ -34,44 +218,556 @@ impl Hardware for FakeHW {
                 revision: "Unknown".to_string(),
                 serial: "Unknown".to_string(),
                 model: "Fake Hardware".to_string(),
+                board: None,
             },
             pins: FAKE_PIN_DESCRIPTIONS,
+            hat: None,
+            probe: self.probe_pins()?,
         })
     }
 
+    /// Inputs are simulated using the [Stimulus] if one was set, or change to random levels
     fn apply_pin_config<C>(
         &mut self,
         bcm_pin_number: BCMPinNumber,
         pin_function: &PinFunction,
-        mut callback: C,
+        callback: C,
     ) -> io::Result<()>
     where
         C: FnMut(BCMPinNumber, PinLevel) + Send + Sync + Clone + 'static,
     {
-        if let PinFunction::Input(_) = pin_function {
-            std::thread::spawn(move || {
-                let mut rng = rand::thread_rng();
-                loop {
-                    let level: bool = rng.gen();
-                    callback(bcm_pin_number, level);
-                    thread::sleep(Duration::from_millis(666));
+        self.stop_input(bcm_pin_number);
+
+        match pin_function {
+            PinFunction::Input(_, filter) => {
+                let level_filter = LevelFilter::new(*filter);
+                let pin_stimulus = self
+                    .stimulus
+                    .as_ref()
+                    .map(|stimulus| stimulus.pins.get(&bcm_pin_number).cloned());
+                match pin_stimulus {
+                    None => self.simulate_input(bcm_pin_number, None, level_filter, callback),
+                    Some(None) => {
+                        self.levels
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .insert(bcm_pin_number, false);
+                    }
+                    Some(Some(pin_stimulus)) => match pin_stimulus.waveform {
+                        Waveform::Loopback(output) => {
+                            let mut levels = self.levels.lock().unwrap_or_else(|e| e.into_inner());
+                            let level = levels.get(&output).copied().unwrap_or(false);
+                            levels.insert(bcm_pin_number, level);
+                            self.loopbacks
+                                .entry(output)
+                                .or_default()
+                                .push(LoopbackInput {
+                                    bcm_pin_number,
+                                    level_filter,
+                                    callback: Box::new(callback),
+                                });
+                        }
+                        waveform => self.simulate_input(
+                            bcm_pin_number,
+                            Some((waveform.steps(), pin_stimulus.repeat)),
+                            level_filter,
+                            callback,
+                        ),
+                    },
                 }
-            });
+            }
+            PinFunction::Output(Some(level)) => self.drive_output(bcm_pin_number, *level),
+            PinFunction::Pwm {
+                frequency,
+                duty_cycle,
+            } => check_pwm(*frequency, *duty_cycle)?,
+            PinFunction::GPCLK0 { .. }
+            | PinFunction::GPCLK1 { .. }
+            | PinFunction::GPCLK2 { .. } => {
+                check_gpclk(&FAKE_PIN_DESCRIPTIONS, bcm_pin_number, pin_function)?
+            }
+            PinFunction::I2C1_SDA
+            | PinFunction::I2C1_SCL
+            | PinFunction::SPI0_MOSI
+            | PinFunction::SPI0_MISO
+            | PinFunction::SPI0_SCLK
+            | PinFunction::SPI0_CE0_N
+            | PinFunction::SPI0_CE1_N
+            | PinFunction::SPI1_MOSI
+            | PinFunction::SPI1_MISO
+            | PinFunction::SPI1_SCLK
+            | PinFunction::SPI1_CE0_N
+            | PinFunction::SPI1_CE1_N
+            | PinFunction::SPI1_CE2_N
+            | PinFunction::UART0_TXD
+            | PinFunction::UART0_RXD => {
+                check_supported(&FAKE_PIN_DESCRIPTIONS, bcm_pin_number, pin_function)?
+            }
+            PinFunction::Alt { mode, .. } => {
+                check_supported(&FAKE_PIN_DESCRIPTIONS, bcm_pin_number, pin_function)?;
+                return self.apply_alt_mode(bcm_pin_number, *mode);
+            }
+            _ => {}
         }
+
+        self.configured_pins
+            .insert(bcm_pin_number, pin_function.clone());
         Ok(())
     }
 
-    /// Read the input level of an input using the bcm pin number
-    fn get_input_level(&self, _bcm_pin_number: BCMPinNumber) -> io::Result<PinLevel> {
-        Ok(true)
+    /// Record the pin as having the alternate function of `mode`, named as in the pin
+    /// descriptions if it is one of the pin's options
+    fn apply_alt_mode(&mut self, bcm_pin_number: BCMPinNumber, mode: AltMode) -> io::Result<()> {
+        let name = FAKE_PIN_DESCRIPTIONS
+            .get_by_bcm(bcm_pin_number)
+            .and_then(|pin| {
+                pin.options.iter().find_map(|option| match option {
+                    PinFunction::Alt {
+                        mode: option_mode,
+                        name,
+                    } if *option_mode == mode => Some(name.clone()),
+                    _ => None,
+                })
+            })
+            .unwrap_or_else(|| mode.to_string().into());
+        self.configured_pins
+            .insert(bcm_pin_number, PinFunction::Alt { mode, name });
+        Ok(())
+    }
+
+    /// Pins that have not been configured are found as a Pi leaves them after reset: inputs,
+    /// pulled up (GPIO0-8) or down (the rest)
+    fn probe_pins(&self) -> io::Result<HashMap<BCMPinNumber, PinProbe>> {
+        Ok(FAKE_PIN_DESCRIPTIONS
+            .pins()
+            .iter()
+            .filter_map(|pin| pin.bcm)
+            .filter(|bcm_pin_number| {
+                matches!(
+                    self.configured_pins.get(bcm_pin_number),
+                    None | Some(PinFunction::None)
+                )
+            })
+            .map(|bcm_pin_number| {
+                let pulled_up = bcm_pin_number <= 8;
+                let probe = PinProbe {
+                    mode: PinMode::Input,
+                    pull: Some(if pulled_up {
+                        InputPull::PullUp
+                    } else {
+                        InputPull::PullDown
+                    }),
+                    level: Some(pulled_up),
+                };
+                (bcm_pin_number, probe)
+            })
+            .collect())
+    }
+
+    /// Read the level last simulated for an input using the bcm pin number
+    fn get_input_level(&self, bcm_pin_number: BCMPinNumber) -> io::Result<PinLevel> {
+        let levels = self
+            .levels
+            .lock()
+            .map_err(|e| io::Error::other(e.to_string()))?;
+        Ok(levels.get(&bcm_pin_number).copied().unwrap_or(false))
     }
 
-    /// Set the level of a Hardware Output using the bcm pin number
+    /// Set the level of a Hardware Output using the bcm pin number, and of any inputs wired
+    /// back to it
     fn set_output_level(
         &mut self,
-        _bcm_pin_number: BCMPinNumber,
-        _level: PinLevel,
+        bcm_pin_number: BCMPinNumber,
+        level: PinLevel,
     ) -> io::Result<()> {
+        self.drive_output(bcm_pin_number, level);
+        Ok(())
+    }
+
+    fn i2c_scan(&mut self) -> io::Result<Vec<I2CAddress>> {
+        let mut devices: Vec<I2CAddress> = self
+            .i2c_devices
+            .keys()
+            .filter(|address| I2C_SCAN_RANGE.contains(address))
+            .copied()
+            .collect();
+        devices.sort();
+        Ok(devices)
+    }
+
+    fn i2c_read(&mut self, address: I2CAddress, length: usize) -> io::Result<Vec<u8>> {
+        Ok(self.i2c_device(address)?.read(length))
+    }
+
+    fn i2c_write(&mut self, address: I2CAddress, data: &[u8]) -> io::Result<()> {
+        self.i2c_device(address)?.write(data);
         Ok(())
     }
+
+    fn apply_spi_config(&mut self, bus: SPIBus, config: &SPIConfig) -> io::Result<()> {
+        check_spi_config(bus, config)
+    }
+
+    /// Emulate MOSI being looped back to MISO, so the data read is the data written
+    fn spi_transfer(&mut self, bus: SPIBus, chip_select: u8, data: &[u8]) -> io::Result<Vec<u8>> {
+        check_chip_select(bus, chip_select)?;
+        Ok(data.to_vec())
+    }
+
+    /// Open a loopback UART, where a thread receives the bytes sent and passes them to
+    /// `callback`. Reconfiguring drops the previous sender, which ends the previous thread
+    fn apply_uart_config<D>(&mut self, config: &UARTConfig, mut callback: D) -> io::Result<()>
+    where
+        D: FnMut(Vec<u8>) + Send + 'static,
+    {
+        check_uart_config(config)?;
+        let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+        thread::spawn(move || {
+            while let Ok(data) = receiver.recv() {
+                callback(data);
+            }
+        });
+        self.uart = Some(sender);
+        Ok(())
+    }
+
+    fn uart_write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.uart
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "UART is not open"))?
+            .send(data.to_vec())
+            .map_err(io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::i2c::{I2CRequest, I2CResponse};
+    use crate::hw::pin_function::{AltMode, PinFunction};
+    use crate::hw::spi::{SPIBus, SPIConfig, SPIResponse, SPITransfer};
+    use crate::hw::stimulus::{PinStimulus, Step, Stimulus, Waveform};
+    use crate::hw::uart::UARTConfig;
+    use crate::hw::{Hardware, InputPull, PinMode, PinProbe};
+    use std::borrow::Cow;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn alt_function_on_supported_pin() {
+        let mut hw = super::FakeHW::new();
+        let alt = PinFunction::Alt {
+            mode: AltMode::Alt3,
+            name: Cow::Borrowed("SPI3_MOSI"),
+        };
+        hw.apply_pin_config(2, &alt, |_, _| {})
+            .expect("Could not configure ALT3");
+        assert_eq!(hw.configured_pins.get(&2), Some(&alt));
+    }
+
+    #[test]
+    fn alt_function_on_unsupported_pin() {
+        let mut hw = super::FakeHW::new();
+        // ALT0 of GPIO17 is reserved
+        let alt = PinFunction::Alt {
+            mode: AltMode::Alt0,
+            name: Cow::Borrowed("UART0_RTS"),
+        };
+        assert!(hw.apply_pin_config(17, &alt, |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn i2c_on_unsupported_pin() {
+        let mut hw = super::FakeHW::new();
+        assert!(hw
+            .apply_pin_config(4, &PinFunction::I2C1_SDA, |_, _| {})
+            .is_err());
+        hw.apply_pin_config(2, &PinFunction::I2C1_SDA, |_, _| {})
+            .expect("Could not configure I2C1_SDA");
+    }
+
+    #[test]
+    fn spi_on_unsupported_pin() {
+        let mut hw = super::FakeHW::new();
+        assert!(hw
+            .apply_pin_config(4, &PinFunction::SPI1_MOSI, |_, _| {})
+            .is_err());
+        hw.apply_pin_config(10, &PinFunction::SPI0_MOSI, |_, _| {})
+            .expect("Could not configure SPI0_MOSI");
+    }
+
+    #[test]
+    fn any_alt_mode() {
+        let mut hw = super::FakeHW::new();
+        hw.apply_alt_mode(17, AltMode::Alt0)
+            .expect("Could not set ALT0");
+        assert_eq!(
+            hw.configured_pins.get(&17),
+            Some(&PinFunction::Alt {
+                mode: AltMode::Alt0,
+                name: Cow::Borrowed("ALT0")
+            })
+        );
+    }
+
+    #[test]
+    fn gpclk_on_supported_pin() {
+        let mut hw = super::FakeHW::new();
+        let clock = PinFunction::GPCLK1 {
+            frequency: 2_000_000.0,
+        };
+        hw.apply_pin_config(21, &clock, |_, _| {})
+            .expect("Could not configure GPCLK1");
+        assert_eq!(hw.configured_pins.get(&21), Some(&clock));
+    }
+
+    #[test]
+    fn gpclk_on_unsupported_pin() {
+        let mut hw = super::FakeHW::new();
+        let clock = PinFunction::GPCLK0 {
+            frequency: 2_000_000.0,
+        };
+        assert!(hw.apply_pin_config(5, &clock, |_, _| {}).is_err());
+    }
+
+    #[test]
+    fn uart_loopback() {
+        let mut hw = super::FakeHW::new();
+        let (sender, receiver) = mpsc::channel();
+        hw.apply_uart_config(&UARTConfig::default(), move |data| {
+            let _ = sender.send(data);
+        })
+        .expect("Could not open UART");
+        hw.uart_write(b"hello").expect("Could not write to UART");
+        let received = receiver
+            .recv_timeout(Duration::from_secs(1))
+            .expect("Nothing received on UART");
+        assert_eq!(received, b"hello");
+    }
+
+    #[test]
+    fn uart_write_when_not_open() {
+        let mut hw = super::FakeHW::new();
+        assert!(hw.uart_write(b"hello").is_err());
+    }
+
+    #[test]
+    fn uart_invalid_stop_bits() {
+        let mut hw = super::FakeHW::new();
+        let config = UARTConfig {
+            stop_bits: 3,
+            ..Default::default()
+        };
+        assert!(hw.apply_uart_config(&config, |_| {}).is_err());
+    }
+
+    #[test]
+    fn spi_transfer_is_looped_back() {
+        let mut hw = super::FakeHW::new();
+        let transfer = SPITransfer {
+            bus: SPIBus::SPI1,
+            chip_select: 2,
+            data: vec![0x01, 0x80, 0xFF],
+        };
+        assert_eq!(
+            hw.spi_request(&transfer),
+            SPIResponse::Data {
+                bus: SPIBus::SPI1,
+                chip_select: 2,
+                data: vec![0x01, 0x80, 0xFF]
+            }
+        );
+    }
+
+    #[test]
+    fn spi_invalid_chip_select() {
+        let mut hw = super::FakeHW::new();
+        let transfer = SPITransfer {
+            bus: SPIBus::SPI0,
+            chip_select: 2,
+            data: vec![0x00],
+        };
+        assert_eq!(
+            hw.spi_request(&transfer),
+            SPIResponse::Error("SPI0 has no chip select CE2".into())
+        );
+    }
+
+    #[test]
+    fn spi_invalid_clock_speed() {
+        let mut hw = super::FakeHW::new();
+        let config = SPIConfig {
+            clock_speed: 0,
+            ..Default::default()
+        };
+        assert!(hw.apply_spi_config(SPIBus::SPI0, &config).is_err());
+    }
+
+    #[test]
+    fn pwm_is_simulated() {
+        let mut hw = super::FakeHW::new();
+        let pwm = PinFunction::Pwm {
+            frequency: 500.0,
+            duty_cycle: 0.2,
+        };
+        hw.apply_pin_config(18, &pwm, |_, _| {})
+            .expect("Could not configure PWM");
+        assert_eq!(hw.configured_pins.get(&18), Some(&pwm));
+    }
+
+    #[test]
+    fn invalid_pwm_rejected() {
+        let mut hw = super::FakeHW::new();
+        let pwm = PinFunction::Pwm {
+            frequency: 500.0,
+            duty_cycle: 1.5,
+        };
+        assert!(hw.apply_pin_config(18, &pwm, |_, _| {}).is_err());
+        assert!(hw.configured_pins.is_empty());
+    }
+
+    #[test]
+    fn i2c_scan_finds_virtual_devices() {
+        let mut hw = super::FakeHW::new();
+        assert_eq!(
+            hw.i2c_request(&I2CRequest::Scan),
+            I2CResponse::Devices(vec![0x20, 0x48, 0x50])
+        );
+    }
+
+    #[test]
+    fn i2c_register_write_then_read() {
+        let mut hw = super::FakeHW::new();
+        hw.i2c_write_register(0x50, 0x10, &[1, 2, 3])
+            .expect("Could not write registers");
+        assert_eq!(
+            hw.i2c_request(&I2CRequest::ReadRegister {
+                address: 0x50,
+                register: 0x11,
+                length: 3
+            }),
+            I2CResponse::Data {
+                address: 0x50,
+                register: Some(0x11),
+                data: vec![2, 3, 0xFF]
+            }
+        );
+    }
+
+    #[test]
+    fn i2c_read_temperature() {
+        let mut hw = super::FakeHW::new();
+        assert_eq!(
+            hw.i2c_read_register(0x48, 0x00, 2)
+                .expect("Could not read temperature"),
+            vec![0x19, 0x00]
+        );
+    }
+
+    #[test]
+    fn stimulus_drives_input() {
+        let mut hw = super::FakeHW::new();
+        let mut stimulus = Stimulus::default();
+        stimulus.pins.insert(
+            26,
+            PinStimulus {
+                waveform: Waveform::Sequence(vec![
+                    Step { level: true, ms: 1 },
+                    Step {
+                        level: false,
+                        ms: 1,
+                    },
+                    Step { level: true, ms: 1 },
+                ]),
+                repeat: false,
+            },
+        );
+        hw.set_stimulus(stimulus);
+        let (sender, receiver) = mpsc::channel();
+        hw.apply_pin_config(26, &PinFunction::Input(None, None), move |pin, level| {
+            let _ = sender.send((pin, level));
+        })
+        .expect("Could not configure input");
+        let levels: Vec<(u8, bool)> = receiver.iter().take(3).collect();
+        assert_eq!(levels, vec![(26, true), (26, false), (26, true)]);
+    }
+
+    #[test]
+    fn input_without_stimulus_held_low() {
+        let mut hw = super::FakeHW::new();
+        hw.set_stimulus(Stimulus::default());
+        hw.apply_pin_config(26, &PinFunction::Input(None, None), |_, _| {})
+            .expect("Could not configure input");
+        assert_eq!(hw.get_input_level(26).ok(), Some(false));
+    }
+
+    #[test]
+    fn output_looped_back_to_input() {
+        let mut hw = super::FakeHW::new();
+        let mut stimulus = Stimulus::default();
+        stimulus.pins.insert(
+            6,
+            PinStimulus {
+                waveform: Waveform::Loopback(17),
+                repeat: false,
+            },
+        );
+        hw.set_stimulus(stimulus);
+        hw.apply_pin_config(17, &PinFunction::Output(Some(true)), |_, _| {})
+            .expect("Could not configure output");
+        let (sender, receiver) = mpsc::channel();
+        hw.apply_pin_config(6, &PinFunction::Input(None, None), move |pin, level| {
+            let _ = sender.send((pin, level));
+        })
+        .expect("Could not configure input");
+        assert_eq!(hw.get_input_level(6).ok(), Some(true));
+
+        hw.set_output_level(17, false)
+            .expect("Could not set output level");
+        assert_eq!(receiver.try_recv().ok(), Some((6, false)));
+        assert_eq!(hw.get_input_level(6).ok(), Some(false));
+    }
+
+    #[test]
+    fn i2c_no_device() {
+        let mut hw = super::FakeHW::new();
+        assert!(matches!(
+            hw.i2c_request(&I2CRequest::Read {
+                address: 0x10,
+                length: 1
+            }),
+            I2CResponse::Error(_)
+        ));
+    }
+
+    #[test]
+    fn unconfigured_pins_probed_as_after_reset() {
+        let hw = super::FakeHW::new();
+        let probe = hw.probe_pins().expect("Could not probe pins");
+        assert_eq!(probe.len(), 26);
+        assert_eq!(
+            probe.get(&4),
+            Some(&PinProbe {
+                mode: PinMode::Input,
+                pull: Some(InputPull::PullUp),
+                level: Some(true),
+            })
+        );
+        assert_eq!(probe.get(&17).and_then(|pin| pin.level), Some(false));
+    }
+
+    #[test]
+    fn configured_pins_not_probed() {
+        let mut hw = super::FakeHW::new();
+        hw.apply_pin_config(17, &PinFunction::Output(Some(true)), |_, _| {})
+            .expect("Could not configure output");
+        hw.apply_pin_config(4, &PinFunction::None, |_, _| {})
+            .expect("Could not unconfigure pin");
+        let probe = hw.probe_pins().expect("Could not probe pins");
+        assert!(!probe.contains_key(&17));
+        assert!(probe.contains_key(&4));
+        assert_eq!(
+            hw.description().expect("Could not get description").probe,
+            probe
+        );
+    }
 }
@@ -0,0 +1,271 @@
+//! Programming of the General Purpose Clocks (GPCLK0-2) of the BCM2835/6/7 and BCM2711 SoCs
+//! (Pi 1-4, Zero), by mapping the registers of the Clock Manager from "/dev/mem".
+//! See the "General Purpose GPIO Clocks" section of the BCM2835 ARM Peripherals datasheet.
+//! The Pi 5 has its GPIO on the RP1 chip, which is not supported.
+use std::fs::{File, OpenOptions};
+use std::os::fd::AsRawFd;
+use std::time::Duration;
+use std::{fs, io, ptr, thread};
+
+/// Offset of the Clock Manager from the base address of the peripherals
+const CM_OFFSET: u64 = 0x10_1000;
+/// Size of the block of Clock Manager registers mapped
+const CM_BLOCK_SIZE: usize = 4096;
+/// All writes to Clock Manager registers must include this password in the top byte
+const CM_PASSWORD: u32 = 0x5A00_0000;
+/// Offset of the GP0CTL register, each clock has a CTL and DIV register pair after it
+const GP0CTL: usize = 0x70;
+const CTL_ENAB: u32 = 1 << 4;
+const CTL_BUSY: u32 = 1 << 7;
+/// MASH 1 noise shaping, which uses the fractional part of the divisor
+const CTL_MASH_1: u32 = 1 << 9;
+/// The largest value of the integer part of a divisor
+const MAX_DIVI: u32 = 4095;
+/// Base address of the peripherals on the BCM2711 (Pi 4), which has faster clock sources
+pub(crate) const BCM2711_PERIPHERAL_BASE: u64 = 0xFE00_0000;
+
+/// A clock source that can drive a General Purpose Clock
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ClockSource {
+    /// Value of the SRC field of the CTL register to select this source
+    src: u32,
+    /// Frequency of the source in Hz
+    frequency: f64,
+}
+
+/// Clock sources of the BCM2835/6/7: oscillator and PLLD
+const BCM283X_SOURCES: [ClockSource; 2] = [
+    ClockSource {
+        src: 1,
+        frequency: 19_200_000.0,
+    },
+    ClockSource {
+        src: 6,
+        frequency: 500_000_000.0,
+    },
+];
+
+/// Clock sources of the BCM2711: oscillator and PLLD
+const BCM2711_SOURCES: [ClockSource; 2] = [
+    ClockSource {
+        src: 1,
+        frequency: 54_000_000.0,
+    },
+    ClockSource {
+        src: 6,
+        frequency: 750_000_000.0,
+    },
+];
+
+/// The clock source and divisor (integer and 12-bit fractional parts) that generate a frequency
+#[derive(Debug, PartialEq)]
+pub(crate) struct ClockSettings {
+    src: u32,
+    divi: u32,
+    divf: u32,
+}
+
+/// Choose the first of `sources` that can generate `frequency`, and the divisor to use with it.
+/// The oscillator is listed first as it is not affected by changes to the PLLs.
+/// With MASH 1 the integer part of the divisor must be at least 2
+pub(crate) fn clock_settings(sources: &[ClockSource], frequency: f64) -> io::Result<ClockSettings> {
+    for source in sources {
+        let divisor = source.frequency / frequency;
+        if (2.0..MAX_DIVI as f64).contains(&divisor) {
+            let divi = divisor.trunc() as u32;
+            let divf = ((divisor - divi as f64) * 4096.0).round() as u32;
+            // Rounding the fraction up may carry into the integer part
+            let (divi, divf) = if divf >= 4096 {
+                (divi + 1, 0)
+            } else {
+                (divi, divf)
+            };
+            return Ok(ClockSettings {
+                src: source.src,
+                divi,
+                divf,
+            });
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("A clock frequency of {frequency}Hz cannot be generated"),
+    ))
+}
+
+/// Get the base address of the peripherals from the "ranges" property of the "soc" node of
+/// the device tree. The parent address is the second cell, or on the BCM2711 which uses
+/// 64-bit parent addresses, the third cell
+pub(crate) fn peripheral_base(ranges: &[u8]) -> io::Result<u64> {
+    let cell = |index: usize| {
+        ranges
+            .get(index * 4..index * 4 + 4)
+            .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    match cell(1) {
+        Some(0) => cell(2),
+        address => address,
+    }
+    .map(|address| address as u64)
+    .ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Could not find peripheral base address in device tree",
+        )
+    })
+}
+
+/// [ClockManager] gives access to the General Purpose Clock registers of the Clock Manager
+pub(crate) struct ClockManager {
+    registers: *mut u32,
+    sources: &'static [ClockSource],
+}
+
+// SAFETY: The mapping is owned by the ClockManager, is only accessed through &mut self or
+// &self with volatile reads and writes of registers, and is unmapped when it is dropped
+unsafe impl Send for ClockManager {}
+
+impl ClockManager {
+    /// Map the Clock Manager registers. This requires permission to open "/dev/mem", so
+    /// normally running as root
+    pub fn open() -> io::Result<Self> {
+        let model = fs::read_to_string("/proc/device-tree/model").unwrap_or_default();
+        if model.contains("Raspberry Pi 5") {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "General Purpose Clocks are not supported on the Pi 5",
+            ));
+        }
+
+        let base = peripheral_base(&fs::read("/proc/device-tree/soc/ranges")?)?;
+        let sources: &'static [ClockSource] = if base == BCM2711_PERIPHERAL_BASE {
+            &BCM2711_SOURCES
+        } else {
+            &BCM283X_SOURCES
+        };
+
+        let mem: File = OpenOptions::new().read(true).write(true).open("/dev/mem")?;
+        // SAFETY: Mapping a block of physical memory with no requirements on the address
+        // chosen. The result is checked before it is used
+        let mapping = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                CM_BLOCK_SIZE,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                mem.as_raw_fd(),
+                (base + CM_OFFSET) as libc::off_t,
+            )
+        };
+        if mapping == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(ClockManager {
+            registers: mapping as *mut u32,
+            sources,
+        })
+    }
+
+    fn read(&self, offset: usize) -> u32 {
+        // SAFETY: offset is that of a register within the mapped block
+        unsafe { ptr::read_volatile(self.registers.add(offset / 4)) }
+    }
+
+    fn write(&mut self, offset: usize, value: u32) {
+        // SAFETY: offset is that of a register within the mapped block
+        unsafe { ptr::write_volatile(self.registers.add(offset / 4), CM_PASSWORD | value) }
+    }
+
+    /// Start clock number `clock` (0-2) generating `frequency`, stopping it first if running
+    pub fn start(&mut self, clock: u8, frequency: f64) -> io::Result<()> {
+        let settings = clock_settings(self.sources, frequency)?;
+        self.stop(clock)?;
+        let ctl = GP0CTL + clock as usize * 8;
+        self.write(ctl + 4, (settings.divi << 12) | settings.divf);
+        self.write(ctl, CTL_MASH_1 | settings.src);
+        self.write(ctl, CTL_MASH_1 | settings.src | CTL_ENAB);
+        Ok(())
+    }
+
+    /// Stop clock number `clock` (0-2), waiting for it to finish its current cycle
+    pub fn stop(&mut self, clock: u8) -> io::Result<()> {
+        let ctl = GP0CTL + clock as usize * 8;
+        let current = self.read(ctl) & 0x00FF_FFFF;
+        self.write(ctl, current & !CTL_ENAB);
+        for _ in 0..100 {
+            if self.read(ctl) & CTL_BUSY == 0 {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_micros(10));
+        }
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!("GPCLK{clock} did not stop"),
+        ))
+    }
+}
+
+impl Drop for ClockManager {
+    fn drop(&mut self) {
+        // SAFETY: registers is the start of the block mapped in open()
+        unsafe {
+            libc::munmap(self.registers as *mut libc::c_void, CM_BLOCK_SIZE);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::gpclk::{
+        clock_settings, peripheral_base, ClockSettings, BCM2711_SOURCES, BCM283X_SOURCES,
+    };
+
+    #[test]
+    fn oscillator_preferred() {
+        assert_eq!(
+            clock_settings(&BCM283X_SOURCES, 1_000_000.0).expect("No clock settings"),
+            ClockSettings {
+                src: 1,
+                divi: 19,
+                divf: 819
+            }
+        );
+    }
+
+    #[test]
+    fn plld_for_high_frequencies() {
+        assert_eq!(
+            clock_settings(&BCM2711_SOURCES, 50_000_000.0).expect("No clock settings"),
+            ClockSettings {
+                src: 6,
+                divi: 15,
+                divf: 0
+            }
+        );
+    }
+
+    #[test]
+    fn frequency_out_of_range() {
+        assert!(clock_settings(&BCM283X_SOURCES, 1000.0).is_err());
+        assert!(clock_settings(&BCM283X_SOURCES, 300_000_000.0).is_err());
+    }
+
+    #[test]
+    fn pi3_peripheral_base() {
+        let ranges = [
+            0x7E, 0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(peripheral_base(&ranges).expect("No base"), 0x3F00_0000);
+    }
+
+    #[test]
+    fn pi4_peripheral_base() {
+        let ranges = [
+            0x7E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFE, 0x00, 0x00, 0x00, 0x01, 0x80,
+            0x00, 0x00,
+        ];
+        assert_eq!(peripheral_base(&ranges).expect("No base"), 0xFE00_0000);
+    }
+}
@@ -0,0 +1,89 @@
+//! Reading back the pull-up/pull-down of the GPIO pins of the BCM2711 SoC (Pi 4, Pi 400, CM4),
+//! by mapping the GPIO registers from "/dev/gpiomem".
+//! See the "GPIO_PUP_PDN_CNTRL_REGn" registers in the BCM2711 ARM Peripherals datasheet.
+//! Earlier SoCs only have write-only pull registers, and the Pi 5 has its GPIO on the RP1 chip,
+//! so the pulls of their pins cannot be read.
+use crate::hw::gpclk::{peripheral_base, BCM2711_PERIPHERAL_BASE};
+use crate::hw::{BCMPinNumber, InputPull};
+use std::fs::File;
+use std::os::fd::AsRawFd;
+use std::{fs, io, ptr};
+
+/// Size of the block of GPIO registers mapped
+const GPIO_BLOCK_SIZE: usize = 4096;
+/// Offset of GPIO_PUP_PDN_CNTRL_REG0, which is followed by REG1-3. Each has two bits per pin
+const GPIO_PUP_PDN_CNTRL_REG0: usize = 0xE4;
+/// Number of GPIO pins of the BCM2711
+const BCM2711_GPIO_COUNT: BCMPinNumber = 58;
+
+/// Decode the pull of pin `bcm_pin_number` from the four GPIO_PUP_PDN_CNTRL registers
+pub(crate) fn pull_from_registers(registers: &[u32; 4], bcm_pin_number: BCMPinNumber) -> InputPull {
+    let register = registers[bcm_pin_number as usize / 16];
+    match (register >> ((bcm_pin_number % 16) * 2)) & 0b11 {
+        0b01 => InputPull::PullUp,
+        0b10 => InputPull::PullDown,
+        _ => InputPull::None,
+    }
+}
+
+/// Read the pull of every GPIO pin of the BCM2711, indexed by [BCMPinNumber]. This requires
+/// permission to open "/dev/gpiomem", which members of the "gpio" group have
+pub fn read_pulls() -> io::Result<Vec<InputPull>> {
+    let base = peripheral_base(&fs::read("/proc/device-tree/soc/ranges")?)?;
+    if base != BCM2711_PERIPHERAL_BASE {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "The pulls of GPIO pins can only be read on the BCM2711",
+        ));
+    }
+
+    let gpiomem = File::open("/dev/gpiomem")?;
+    // SAFETY: Mapping the GPIO registers read-only with no requirements on the address
+    // chosen. The result is checked before it is used
+    let mapping = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            GPIO_BLOCK_SIZE,
+            libc::PROT_READ,
+            libc::MAP_SHARED,
+            gpiomem.as_raw_fd(),
+            0,
+        )
+    };
+    if mapping == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+
+    let mut registers = [0u32; 4];
+    for (index, register) in registers.iter_mut().enumerate() {
+        // SAFETY: The registers read are within the mapped block
+        *register = unsafe {
+            ptr::read_volatile((mapping as *const u32).add(GPIO_PUP_PDN_CNTRL_REG0 / 4 + index))
+        };
+    }
+    // SAFETY: mapping is the block mapped above, and is not used after this
+    unsafe {
+        libc::munmap(mapping, GPIO_BLOCK_SIZE);
+    }
+
+    Ok((0..BCM2711_GPIO_COUNT)
+        .map(|bcm_pin_number| pull_from_registers(&registers, bcm_pin_number))
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::gpio_pulls::pull_from_registers;
+    use crate::hw::InputPull;
+
+    #[test]
+    fn pulls_decoded() {
+        // GPIO0 pulled up, GPIO1 none, GPIO15 down, GPIO16 up, GPIO57 down
+        let registers = [0x8000_0001, 0x0000_0001, 0, 0x0008_0000];
+        assert_eq!(pull_from_registers(&registers, 0), InputPull::PullUp);
+        assert_eq!(pull_from_registers(&registers, 1), InputPull::None);
+        assert_eq!(pull_from_registers(&registers, 15), InputPull::PullDown);
+        assert_eq!(pull_from_registers(&registers, 16), InputPull::PullUp);
+        assert_eq!(pull_from_registers(&registers, 57), InputPull::PullDown);
+    }
+}
@@ -0,0 +1,173 @@
+/// Identification of the HAT attached to a Raspberry Pi, read from the properties the firmware
+/// copies out of the HAT's ID EEPROM (on the I2C_EEPROM_ID pins) into the device tree at boot
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hw::config::HardwareConfig;
+
+/// Where the firmware puts the properties of the HAT, if one was detected at boot
+#[allow(dead_code)] // for piggui and the fake backend
+pub const HAT_DEVICE_TREE: &str = "/proc/device-tree/hat";
+
+/// Configs for HATs whose pins are known, selected by (vendor, product). The firmware does not
+/// expose the GPIO map in the HAT's EEPROM, so these describe the pins the HAT uses instead
+const HAT_PRESETS: &[(&str, &str, &str)] = &[(
+    "Raspberry Pi",
+    "Sense HAT",
+    include_str!("../../configs/hats/sense_hat.pigg"),
+)];
+
+/// [HatInfo] has the identification of an attached HAT
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HatInfo {
+    pub vendor: String,
+    pub product: String,
+    pub uuid: String,
+    pub product_id: u16,
+    pub product_ver: u16,
+}
+
+impl HatInfo {
+    /// Read the HAT properties from the device tree directory `dir`. There is no HAT attached
+    /// if the directory does not exist
+    #[allow(dead_code)] // for piggui and the fake backend
+    pub fn read(dir: &Path) -> io::Result<Option<HatInfo>> {
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+
+        Ok(Some(HatInfo {
+            vendor: property(dir, "vendor")?,
+            product: property(dir, "product")?,
+            uuid: property(dir, "uuid")?,
+            product_id: hex_property(dir, "product_id")?,
+            product_ver: hex_property(dir, "product_ver")?,
+        }))
+    }
+
+    /// The config for this HAT's pins, if it is one of the HATs pigg knows about
+    #[allow(dead_code)] // for piglet
+    pub fn preset(&self) -> Option<HardwareConfig> {
+        HAT_PRESETS
+            .iter()
+            .find(|(vendor, product, _)| *vendor == self.vendor && *product == self.product)
+            .and_then(|(_, _, config)| serde_json::from_str(config).ok())
+    }
+}
+
+impl Display for HatInfo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "HAT: {} by {}", self.product, self.vendor)?;
+        writeln!(
+            f,
+            "HAT Product ID: {:#06x} Version: {:#06x}",
+            self.product_id, self.product_ver
+        )?;
+        write!(f, "HAT UUID: {}", self.uuid)
+    }
+}
+
+/// Read a device tree string property, which is terminated by a NUL
+fn property(dir: &Path, name: &str) -> io::Result<String> {
+    let value = fs::read_to_string(dir.join(name))?;
+    Ok(value.trim_end_matches('\0').trim().to_string())
+}
+
+/// Read a device tree string property that holds a hexadecimal number, such as "0x0001"
+fn hex_property(dir: &Path, name: &str) -> io::Result<u16> {
+    let value = property(dir, name)?;
+    u16::from_str_radix(value.trim_start_matches("0x"), 16).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("HAT property '{name}' is not a hexadecimal number: '{value}'"),
+        )
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::hat::HatInfo;
+    use crate::hw::pin_function::PinFunction;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    fn fake_device_tree(dir: &Path, properties: &[(&str, &str)]) {
+        fs::create_dir_all(dir).expect("Could not create hat directory");
+        for (name, value) in properties {
+            fs::write(dir.join(name), format!("{value}\0")).expect("Could not write property");
+        }
+    }
+
+    const SENSE_HAT: &[(&str, &str)] = &[
+        ("name", "hat"),
+        ("vendor", "Raspberry Pi"),
+        ("product", "Sense HAT"),
+        ("uuid", "0c5e5b2f-3a9a-4c1b-8f0e-2f1d6a5b4c3d"),
+        ("product_id", "0x0001"),
+        ("product_ver", "0x0100"),
+    ];
+
+    #[test]
+    fn no_hat() {
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        assert_eq!(
+            HatInfo::read(&output_dir.join("hat")).expect("Could not read HAT"),
+            None
+        );
+    }
+
+    #[test]
+    fn read_hat() {
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let hat_dir = output_dir.join("hat");
+        fake_device_tree(&hat_dir, SENSE_HAT);
+        let hat = HatInfo::read(&hat_dir)
+            .expect("Could not read HAT")
+            .expect("HAT not found");
+        assert_eq!(hat.vendor, "Raspberry Pi");
+        assert_eq!(hat.product, "Sense HAT");
+        assert_eq!(hat.uuid, "0c5e5b2f-3a9a-4c1b-8f0e-2f1d6a5b4c3d");
+        assert_eq!(hat.product_id, 1);
+        assert_eq!(hat.product_ver, 0x100);
+    }
+
+    #[test]
+    fn bad_product_id() {
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let hat_dir = output_dir.join("hat");
+        fake_device_tree(&hat_dir, SENSE_HAT);
+        fake_device_tree(&hat_dir, &[("product_id", "one")]);
+        assert!(HatInfo::read(&hat_dir).is_err());
+    }
+
+    #[test]
+    fn sense_hat_preset() {
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let hat_dir = output_dir.join("hat");
+        fake_device_tree(&hat_dir, SENSE_HAT);
+        let hat = HatInfo::read(&hat_dir)
+            .expect("Could not read HAT")
+            .expect("HAT not found");
+        let preset = hat.preset().expect("No preset for the Sense HAT");
+        assert_eq!(preset.pins.get(&2), Some(&PinFunction::I2C1_SDA));
+        assert_eq!(preset.pins.get(&3), Some(&PinFunction::I2C1_SCL));
+    }
+
+    #[test]
+    fn unknown_hat_has_no_preset() {
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let hat_dir = output_dir.join("hat");
+        fake_device_tree(&hat_dir, SENSE_HAT);
+        fake_device_tree(&hat_dir, &[("product", "Mystery HAT")]);
+        let hat = HatInfo::read(&hat_dir)
+            .expect("Could not read HAT")
+            .expect("HAT not found");
+        assert!(hat.preset().is_none());
+    }
+}
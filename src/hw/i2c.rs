@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// [I2CAddress] is the address of a device on an I2C bus
+pub type I2CAddress = u16;
+
+/// I2C addresses that can be used by devices. 0x00-0x02 and 0x78-0x7F are reserved
+#[allow(dead_code)] // for piggui
+pub const I2C_SCAN_RANGE: std::ops::RangeInclusive<I2CAddress> = 0x03..=0x77;
+
+/// [I2CRequest] is a request initiated by the user to be made on the I2C bus of the hardware
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum I2CRequest {
+    /// Scan the bus for addresses that have a device that responds
+    Scan,
+    /// Read `length` bytes from the device at `address`
+    Read { address: I2CAddress, length: usize },
+    /// Write `data` to the device at `address`
+    Write { address: I2CAddress, data: Vec<u8> },
+    /// Read `length` bytes from the device at `address`, starting at `register`
+    ReadRegister {
+        address: I2CAddress,
+        register: u8,
+        length: usize,
+    },
+    /// Write `data` to the device at `address`, starting at `register`
+    WriteRegister {
+        address: I2CAddress,
+        register: u8,
+        data: Vec<u8>,
+    },
+}
+
+/// [I2CResponse] is the result of an [I2CRequest] made on the I2C bus of the hardware
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum I2CResponse {
+    /// Addresses of the devices found in a scan of the bus
+    Devices(Vec<I2CAddress>),
+    /// Data read from a device, and the register it was read from if one was specified
+    Data {
+        address: I2CAddress,
+        register: Option<u8>,
+        data: Vec<u8>,
+    },
+    /// Data was written to a device, and the register written to if one was specified
+    Written {
+        address: I2CAddress,
+        register: Option<u8>,
+        length: usize,
+    },
+    /// The request failed
+    Error(String),
+}
+
+impl Display for I2CResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            I2CResponse::Devices(addresses) if addresses.is_empty() => {
+                write!(f, "No devices found")
+            }
+            I2CResponse::Devices(addresses) => {
+                write!(f, "Devices found at:")?;
+                for address in addresses {
+                    write!(f, " {address:#04x}")?;
+                }
+                Ok(())
+            }
+            I2CResponse::Data {
+                address,
+                register,
+                data,
+            } => {
+                write!(f, "{address:#04x}")?;
+                if let Some(register) = register {
+                    write!(f, "[{register:#04x}]")?;
+                }
+                write!(f, " read:")?;
+                for byte in data {
+                    write!(f, " {byte:02x}")?;
+                }
+                Ok(())
+            }
+            I2CResponse::Written {
+                address,
+                register,
+                length,
+            } => {
+                write!(f, "{address:#04x}")?;
+                if let Some(register) = register {
+                    write!(f, "[{register:#04x}]")?;
+                }
+                write!(f, " wrote {length} bytes")
+            }
+            I2CResponse::Error(message) => write!(f, "Error: {message}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::i2c::I2CResponse;
+
+    #[test]
+    fn display_devices() {
+        assert_eq!(
+            I2CResponse::Devices(vec![0x20, 0x48]).to_string(),
+            "Devices found at: 0x20 0x48"
+        );
+        assert_eq!(I2CResponse::Devices(vec![]).to_string(), "No devices found");
+    }
+
+    #[test]
+    fn display_register_data() {
+        let response = I2CResponse::Data {
+            address: 0x48,
+            register: Some(0),
+            data: vec![0x19, 0x00],
+        };
+        assert_eq!(response.to_string(), "0x48[0x00] read: 19 00");
+    }
+}
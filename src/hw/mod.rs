@@ -1,28 +1,60 @@
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 #[cfg(feature = "hardware")]
 use std::io;
+#[cfg(feature = "hardware")]
+use std::time::{Duration, Instant};
 
 use crate::hw::config::HardwareConfig;
+use crate::hw::hat::HatInfo;
+#[cfg(feature = "hardware")]
+use crate::hw::i2c::I2CAddress;
+use crate::hw::i2c::{I2CRequest, I2CResponse};
+use crate::hw::revision::BoardRevision;
+use crate::hw::spi::{SPIBus, SPIConfig, SPIResponse, SPITransfer};
+use crate::hw::uart::{UARTConfig, UARTEvent};
 use chrono::{DateTime, Utc};
 use pin_description::PinDescriptionSet;
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "hardware")]
+use crate::hw::backend::{Backend, HW};
+use crate::hw::pin_function::AltMode;
 use crate::hw::pin_function::PinFunction;
 
 pub mod config;
 
-/// There are two implementations of [`Hardware`] trait:
+#[cfg(feature = "hardware")]
+pub mod backend;
+/// There are three implementations of [`Hardware`] trait, any combination of which can be
+/// compiled in and then selected at run time using [Backend]:
 /// * fake_hw - used on host (macOS, Linux, etc.) to show and develop GUI without real HW
 /// * pi_hw - Raspberry Pi using "rppal" crate: Should support most Pi hardware from Model B
+/// * cdev_hw - any Linux board, using the GPIO character device API of the kernel
+#[cfg(feature = "cdev_hw")]
+mod cdev_hw;
 #[cfg(feature = "fake_hw")]
 mod fake_hw;
 #[cfg(feature = "pi_hw")]
+mod gpclk;
+#[cfg(feature = "pi_hw")]
+mod gpio_pulls;
+pub mod hat;
+pub mod i2c;
+#[cfg(feature = "pi_hw")]
 mod pi_hw;
 pub(crate) mod pin_description;
-#[cfg(feature = "hardware")]
+#[cfg(any(feature = "pi_hw", feature = "fake_hw"))]
 mod pin_descriptions;
 pub mod pin_function;
+#[cfg(feature = "pi_hw")]
+mod pin_headers;
+pub mod revision;
+pub mod spi;
+#[cfg(feature = "hardware")]
+pub mod stimulus;
+pub mod uart;
 
 /// [BCMPinNumber] is used to refer to a GPIO pin by the Broadcom Chip Number
 pub type BCMPinNumber = u8;
@@ -32,16 +64,23 @@ pub type BoardPinNumber = u8;
 /// [PinLevel] describes whether a Pin's logical level is High(true) or Low(false)
 pub type PinLevel = bool;
 
-pub const PIGLET_ALPN: &[u8] = b"pigg/piglet/0";
+/// Identifies the [protocol](crate::protocol) on iroh-net connections. It changed from
+/// "pigg/piglet/1" when messages became framed, so that clients and servers using the earlier
+/// unframed messages fail to connect. Versions after the first framed one are negotiated in
+/// its handshake
+pub const PIGLET_ALPN: &[u8] = b"pigg/piglet/2";
 
-/// Get the implementation we will use to access the underlying hardware via the [Hardware] trait
-#[cfg(feature = "pi_hw")]
-pub fn get() -> impl Hardware {
-    pi_hw::get()
-}
-#[cfg(feature = "fake_hw")]
-pub fn get() -> impl Hardware {
-    fake_hw::get()
+#[cfg(all(
+    feature = "hardware",
+    not(any(feature = "pi_hw", feature = "fake_hw", feature = "cdev_hw"))
+))]
+compile_error!("The \"hardware\" feature needs at least one of the \"pi_hw\", \"fake_hw\" or \"cdev_hw\" backends");
+
+/// Get the implementation of the selected [Backend] we will use to access the underlying
+/// hardware via the [Hardware] trait
+#[cfg(feature = "hardware")]
+pub fn get(backend: Backend) -> HW {
+    HW::new(backend)
 }
 
 /// This enum is for hardware config changes initiated in the GUI by the user,
@@ -49,15 +88,69 @@ pub fn get() -> impl Hardware {
 ///    * NewConfig
 ///    * NewPinConfig
 ///    * OutputLevelChanged
+///    * I2CRequest
+///    * NewSPIConfig
+///    * SPITransfer
+///    * NewUARTConfig
+///    * UARTWrite
+///
+/// and some that are sent from the hardware back to the GUI
+///    * IOLevelChanged
+///    * I2CResponse
+///    * SPIResponse
+///    * UARTEvent
+///    * Control
+///    * Ack
+///    * Failed
+///
+/// A remote piglet also forwards the config changes made by the client in control of it to the
+/// other clients connected, so they can show them
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum HardwareConfigMessage {
     /// A complete new hardware config has been loaded and applied to the hardware, so we should
     /// start listening for level changes on each of the input pins it contains
     NewConfig(HardwareConfig),
+    /// Switch the hardware to the named profile of the config in one step, releasing the pins
+    /// the previous config used that the profile does not
+    SwitchProfile(String, HardwareConfig),
     /// A pin has had its config changed
     NewPinConfig(BCMPinNumber, PinFunction),
     /// The level of a pin has changed
     IOLevelChanged(BCMPinNumber, LevelChange),
+    /// A request to be made on the I2C bus
+    I2CRequest(I2CRequest),
+    /// The response to an [I2CRequest]
+    I2CResponse(I2CResponse),
+    /// The settings of an SPI bus have changed
+    NewSPIConfig(SPIBus, SPIConfig),
+    /// A transfer to be made on an SPI bus
+    SPITransfer(SPITransfer),
+    /// The response to an [SPITransfer]
+    SPIResponse(SPIResponse),
+    /// The UART should be (re)opened with new settings
+    NewUARTConfig(UARTConfig),
+    /// Bytes to be sent on the UART
+    UARTWrite(Vec<u8>),
+    /// Something happened on the UART, such as bytes being received
+    UARTEvent(UARTEvent),
+    /// Whether the client holds the control lease of a piglet (true), or only observes it
+    Control(bool),
+    /// The [Request] with this id has been applied to the hardware
+    Ack(RequestId),
+    /// The [Request] with this id could not be applied to the hardware, with the error why.
+    /// A piglet follows it with the actual state of the pins the request tried to change
+    Failed(RequestId, String),
+}
+
+/// Identifies a [Request] made of a piglet, to match the response to it with it
+pub type RequestId = u64;
+
+/// A [HardwareConfigMessage] sent to a piglet, which replies to it with a
+/// [HardwareConfigMessage::Ack] or [HardwareConfigMessage::Failed] with the same id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request {
+    pub id: RequestId,
+    pub message: HardwareConfigMessage,
 }
 
 /// [HardwareDetails] captures a number of specific details about the Hardware we are connected to
@@ -68,6 +161,48 @@ pub struct HardwareDetails {
     pub serial: String,
     /// A Human friendly Hardware Model description
     pub model: String,
+    /// The fields decoded from `revision`, if it is a Raspberry Pi revision code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board: Option<BoardRevision>,
+}
+
+impl HardwareDetails {
+    /// Parse the details from the contents of "/proc/cpuinfo", decoding the revision code.
+    /// Older kernels have no "Model" line, so then the model is taken from the decoded revision
+    #[allow(dead_code)] // for the fake and cdev backends
+    pub fn from_cpuinfo(cpuinfo: &str) -> Self {
+        let mut details = HardwareDetails {
+            hardware: "Unknown".to_string(),
+            revision: "Unknown".to_string(),
+            serial: "Unknown".to_string(),
+            model: "Unknown".to_string(),
+            board: None,
+        };
+
+        for line in cpuinfo.lines() {
+            match line
+                .split_once(':')
+                .map(|(key, value)| (key.trim(), value.trim()))
+            {
+                Some(("Hardware", hw)) => details.hardware = hw.to_string(),
+                Some(("Revision", revision)) => details.revision = revision.to_string(),
+                Some(("Serial", serial)) => details.serial = serial.to_string(),
+                Some(("Model", model)) => details.model = model.to_string(),
+                _ => {}
+            }
+        }
+
+        details.board = u32::from_str_radix(&details.revision, 16)
+            .ok()
+            .and_then(|code| BoardRevision::decode(code).ok());
+        if let Some(board) = &details.board {
+            if details.model == "Unknown" {
+                details.model = board.model();
+            }
+        }
+
+        details
+    }
 }
 
 impl Display for HardwareDetails {
@@ -75,7 +210,11 @@ impl Display for HardwareDetails {
         writeln!(f, "Hardware: {}", self.hardware)?;
         writeln!(f, "Revision: {}", self.revision)?;
         writeln!(f, "Serial: {}", self.serial)?;
-        write!(f, "Model: {}", self.model)
+        write!(f, "Model: {}", self.model)?;
+        if let Some(board) = &self.board {
+            write!(f, "\n{board}")?;
+        }
+        Ok(())
     }
 }
 
@@ -84,6 +223,12 @@ impl Display for HardwareDetails {
 pub struct HardwareDescription {
     pub details: HardwareDetails,
     pub pins: PinDescriptionSet,
+    /// The HAT attached to the board, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hat: Option<HatInfo>,
+    /// What the pins that have not been configured were doing when the description was made
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub probe: HashMap<BCMPinNumber, PinProbe>,
 }
 
 /// LevelChange describes the change in level of an input or Output
@@ -124,6 +269,138 @@ impl Display for InputPull {
     }
 }
 
+/// The mode of the GPIO block a pin was found in by [PinProbe]
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum PinMode {
+    Input,
+    Output,
+    Alt(AltMode),
+    /// A mode that pigg does not know, or the hardware cannot report
+    Unknown,
+}
+
+/// [PinProbe] is the state a pin was found in, read without reconfiguring it
+/// * `mode` - the [PinMode] it is in
+/// * `pull` - the pull-up or pull-down of an input, if the hardware can report it
+/// * `level` - the level of the pin, if it can be read without reconfiguring it
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub struct PinProbe {
+    pub mode: PinMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull: Option<InputPull>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<PinLevel>,
+}
+
+impl Display for PinProbe {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.mode {
+            PinMode::Input => write!(f, "Input")?,
+            PinMode::Output => write!(f, "Output")?,
+            PinMode::Alt(mode) => write!(f, "{mode}")?,
+            PinMode::Unknown => write!(f, "Unknown")?,
+        }
+        if let Some(pull) = self.pull {
+            write!(f, " {pull}")?;
+        }
+        match self.level {
+            Some(true) => write!(f, " (High)"),
+            Some(false) => write!(f, " (Low)"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The edges of an input on which its changes of level are reported
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum InputEdge {
+    Rising,
+    Falling,
+    #[default]
+    Both,
+}
+
+impl InputEdge {
+    #[allow(dead_code)] // for piglet
+    pub const ALL: [InputEdge; 3] = [InputEdge::Both, InputEdge::Rising, InputEdge::Falling];
+}
+
+impl Display for InputEdge {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InputEdge::Rising => write!(f, "Rising"),
+            InputEdge::Falling => write!(f, "Falling"),
+            InputEdge::Both => write!(f, "Both Edges"),
+        }
+    }
+}
+
+/// [InputFilter] limits the changes of level of an input that are reported, to avoid noisy
+/// inputs (such as bouncing buttons) flooding the GUI with level changes
+/// * `edge` - only changes on this edge are reported
+/// * `debounce_ms` - if set, changes within this many milliseconds of the previous change
+///   reported are ignored
+#[derive(Debug, PartialEq, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InputFilter {
+    pub edge: InputEdge,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub debounce_ms: Option<u32>,
+}
+
+/// [LevelFilter] applies an [InputFilter] to the changes of level of an input as they happen
+#[cfg(feature = "hardware")]
+struct LevelFilter {
+    filter: InputFilter,
+    last_reported: Option<Instant>,
+}
+
+#[cfg(feature = "hardware")]
+impl LevelFilter {
+    fn new(filter: Option<InputFilter>) -> Self {
+        Self {
+            filter: filter.unwrap_or_default(),
+            last_reported: None,
+        }
+    }
+
+    /// Return true if the change of level to `level` at time `now` should be reported
+    fn report(&mut self, level: PinLevel, now: Instant) -> bool {
+        let on_edge = match self.filter.edge {
+            InputEdge::Rising => level,
+            InputEdge::Falling => !level,
+            InputEdge::Both => true,
+        };
+        if !on_edge {
+            return false;
+        }
+
+        if let (Some(debounce_ms), Some(last_reported)) =
+            (self.filter.debounce_ms, self.last_reported)
+        {
+            if now.duration_since(last_reported) < Duration::from_millis(debounce_ms as u64) {
+                return false;
+            }
+        }
+
+        self.last_reported = Some(now);
+        true
+    }
+}
+
+/// Check that PWM settings can be generated: frequency must be positive and duty cycle a
+/// fraction between 0.0 and 1.0
+#[cfg(any(feature = "pi_hw", feature = "fake_hw"))]
+fn check_pwm(frequency: f64, duty_cycle: f64) -> io::Result<()> {
+    if frequency <= 0.0 || !(0.0..=1.0).contains(&duty_cycle) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid PWM settings: frequency {frequency}Hz, duty cycle {duty_cycle}"),
+        ));
+    }
+    Ok(())
+}
+
 /// [`Hardware`] is a trait to be implemented depending on the hardware we are running on, to
 /// interact with any possible GPIO hardware on the device to set config and get state
 #[cfg(feature = "hardware")]
@@ -133,7 +410,8 @@ pub trait Hardware {
     /// * [PinDescriptionSet] describing all the pins
     fn description(&self) -> io::Result<HardwareDescription>;
 
-    /// This takes the GPIOConfig struct and configures all the pins in it
+    /// This takes the GPIOConfig struct and configures all the pins in it, and the SPI buses.
+    /// The UART is opened separately using [Hardware::apply_uart_config]
     fn apply_config<C>(&mut self, config: &HardwareConfig, callback: C) -> io::Result<()>
     where
         C: FnMut(BCMPinNumber, PinLevel) + Send + Sync + Clone + 'static,
@@ -143,6 +421,10 @@ pub trait Hardware {
             self.apply_pin_config(*bcm_pin_number, pin_function, callback.clone())?;
         }
 
+        for (bus, spi_config) in &config.spi {
+            self.apply_spi_config(*bus, spi_config)?;
+        }
+
         Ok(())
     }
 
@@ -156,6 +438,14 @@ pub trait Hardware {
     where
         C: FnMut(BCMPinNumber, PinLevel) + Send + Sync + Clone + 'static;
 
+    /// Put a pin into one of the alternate function modes (ALT0 - ALT5) of the SoC, connecting it
+    /// to whichever peripheral the SoC has on that mode for the pin
+    fn apply_alt_mode(&mut self, bcm_pin_number: BCMPinNumber, mode: AltMode) -> io::Result<()>;
+
+    /// Find the [PinProbe] state of each GPIO pin that has not been configured using this
+    /// [Hardware], without reconfiguring it, to show what the pins are already doing
+    fn probe_pins(&self) -> io::Result<HashMap<BCMPinNumber, PinProbe>>;
+
     /// Read the input level of an input using its [BCMPinNumber]
     #[allow(dead_code)] // for piglet
     fn get_input_level(&self, bcm_pin_number: BCMPinNumber) -> io::Result<PinLevel>;
@@ -164,16 +454,248 @@ pub trait Hardware {
     #[allow(dead_code)] // for piglet
     fn set_output_level(&mut self, bcm_pin_number: BCMPinNumber, level: PinLevel)
         -> io::Result<()>;
+
+    /// Scan the I2C bus, returning the addresses of the devices that respond
+    fn i2c_scan(&mut self) -> io::Result<Vec<I2CAddress>>;
+
+    /// Read `length` bytes from the device at `address` on the I2C bus
+    fn i2c_read(&mut self, address: I2CAddress, length: usize) -> io::Result<Vec<u8>>;
+
+    /// Write `data` to the device at `address` on the I2C bus
+    fn i2c_write(&mut self, address: I2CAddress, data: &[u8]) -> io::Result<()>;
+
+    /// Read `length` bytes from the device at `address` on the I2C bus, starting at `register`
+    fn i2c_read_register(
+        &mut self,
+        address: I2CAddress,
+        register: u8,
+        length: usize,
+    ) -> io::Result<Vec<u8>> {
+        self.i2c_write(address, &[register])?;
+        self.i2c_read(address, length)
+    }
+
+    /// Write `data` to the device at `address` on the I2C bus, starting at `register`
+    fn i2c_write_register(
+        &mut self,
+        address: I2CAddress,
+        register: u8,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let mut buffer = vec![register];
+        buffer.extend_from_slice(data);
+        self.i2c_write(address, &buffer)
+    }
+
+    /// Make an [I2CRequest] on the I2C bus and return the [I2CResponse] to it
+    fn i2c_request(&mut self, request: &I2CRequest) -> I2CResponse {
+        let result = match request {
+            I2CRequest::Scan => self.i2c_scan().map(I2CResponse::Devices),
+            I2CRequest::Read { address, length } => {
+                self.i2c_read(*address, *length)
+                    .map(|data| I2CResponse::Data {
+                        address: *address,
+                        register: None,
+                        data,
+                    })
+            }
+            I2CRequest::Write { address, data } => {
+                self.i2c_write(*address, data)
+                    .map(|_| I2CResponse::Written {
+                        address: *address,
+                        register: None,
+                        length: data.len(),
+                    })
+            }
+            I2CRequest::ReadRegister {
+                address,
+                register,
+                length,
+            } => self
+                .i2c_read_register(*address, *register, *length)
+                .map(|data| I2CResponse::Data {
+                    address: *address,
+                    register: Some(*register),
+                    data,
+                }),
+            I2CRequest::WriteRegister {
+                address,
+                register,
+                data,
+            } => self
+                .i2c_write_register(*address, *register, data)
+                .map(|_| I2CResponse::Written {
+                    address: *address,
+                    register: Some(*register),
+                    length: data.len(),
+                }),
+        };
+
+        result.unwrap_or_else(|e| I2CResponse::Error(e.to_string()))
+    }
+
+    /// Apply new settings to an SPI bus, to be used for subsequent transfers on it
+    fn apply_spi_config(&mut self, bus: SPIBus, config: &SPIConfig) -> io::Result<()>;
+
+    /// Make a full-duplex transfer on an SPI bus using `chip_select`, writing `data` and
+    /// returning the same number of bytes read
+    fn spi_transfer(&mut self, bus: SPIBus, chip_select: u8, data: &[u8]) -> io::Result<Vec<u8>>;
+
+    /// Make an [SPITransfer] and return the [SPIResponse] to it
+    fn spi_request(&mut self, transfer: &SPITransfer) -> SPIResponse {
+        match self.spi_transfer(transfer.bus, transfer.chip_select, &transfer.data) {
+            Ok(data) => SPIResponse::Data {
+                bus: transfer.bus,
+                chip_select: transfer.chip_select,
+                data,
+            },
+            Err(e) => SPIResponse::Error(e.to_string()),
+        }
+    }
+
+    /// Open the UART with new settings, closing it first if already open, and call `callback`
+    /// with the bytes received on it
+    fn apply_uart_config<D>(&mut self, config: &UARTConfig, callback: D) -> io::Result<()>
+    where
+        D: FnMut(Vec<u8>) + Send + 'static;
+
+    /// Send `data` on the UART
+    fn uart_write(&mut self, data: &[u8]) -> io::Result<()>;
+}
+
+/// Check that the UART settings can be used: baud rate must be non-zero and 1 or 2 stop bits
+#[cfg(any(feature = "pi_hw", feature = "fake_hw"))]
+fn check_uart_config(config: &UARTConfig) -> io::Result<()> {
+    if config.baud_rate == 0 || !(1..=2).contains(&config.stop_bits) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid UART settings: {config}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that `pin_function` is one of the functions of the pin in `pins`
+#[cfg(feature = "hardware")]
+fn check_supported(
+    pins: &PinDescriptionSet,
+    bcm_pin_number: BCMPinNumber,
+    pin_function: &PinFunction,
+) -> io::Result<()> {
+    let supported = pins
+        .get_by_bcm(bcm_pin_number)
+        .map(|pin| pin.supports(pin_function))
+        .unwrap_or(false);
+    if !supported {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{pin_function} is not available on BCM pin #{bcm_pin_number}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that a General Purpose Clock can be output on a pin: the clock must be one of the
+/// functions of the pin in `pins`, and the frequency must be positive
+#[cfg(any(feature = "pi_hw", feature = "fake_hw"))]
+fn check_gpclk(
+    pins: &PinDescriptionSet,
+    bcm_pin_number: BCMPinNumber,
+    pin_function: &PinFunction,
+) -> io::Result<()> {
+    check_supported(pins, bcm_pin_number, pin_function)?;
+    let frequency = pin_function
+        .gpclk()
+        .map(|(_, frequency)| frequency)
+        .unwrap_or(0.0);
+    if frequency <= 0.0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid {pin_function} frequency {frequency}Hz"),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that the settings for an SPI bus can be used: the clock speed must be non-zero
+#[cfg(any(feature = "pi_hw", feature = "fake_hw"))]
+fn check_spi_config(bus: SPIBus, config: &SPIConfig) -> io::Result<()> {
+    if config.clock_speed == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid clock speed of 0Hz for {bus}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Check that `chip_select` is one of the chip select lines of the SPI `bus`
+#[cfg(any(feature = "pi_hw", feature = "fake_hw"))]
+fn check_chip_select(bus: SPIBus, chip_select: u8) -> io::Result<()> {
+    if chip_select as usize >= bus.chip_select_functions().len() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{bus} has no chip select CE{chip_select}"),
+        ));
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod test {
-    use crate::hw;
-    use crate::hw::Hardware;
+    #[cfg(any(feature = "pi_hw", feature = "fake_hw"))]
+    use crate::hw::backend::Backend;
+    use crate::hw::pin_function::AltMode;
+    #[cfg(any(feature = "pi_hw", feature = "fake_hw"))]
+    use crate::hw::{self, Hardware};
+    use crate::hw::{HardwareDetails, InputPull, PinMode, PinProbe};
+    #[cfg(feature = "hardware")]
+    use crate::hw::{InputEdge, InputFilter, LevelFilter};
+    use std::path::PathBuf;
+    #[cfg(feature = "hardware")]
+    use std::time::{Duration, Instant};
+
+    #[test]
+    #[cfg(feature = "hardware")]
+    fn level_filter_edge() {
+        let mut filter = LevelFilter::new(Some(InputFilter {
+            edge: InputEdge::Falling,
+            debounce_ms: None,
+        }));
+        let now = Instant::now();
+        assert!(!filter.report(true, now));
+        assert!(filter.report(false, now));
+    }
+
+    #[test]
+    #[cfg(feature = "hardware")]
+    fn level_filter_debounce() {
+        let mut filter = LevelFilter::new(Some(InputFilter {
+            edge: InputEdge::Both,
+            debounce_ms: Some(10),
+        }));
+        let start = Instant::now();
+        assert!(filter.report(false, start));
+        assert!(!filter.report(true, start + Duration::from_millis(2)));
+        assert!(!filter.report(false, start + Duration::from_millis(5)));
+        assert!(filter.report(true, start + Duration::from_millis(12)));
+    }
+
+    #[test]
+    #[cfg(feature = "hardware")]
+    fn level_filter_default_reports_all() {
+        let mut filter = LevelFilter::new(None);
+        let now = Instant::now();
+        assert!(filter.report(true, now));
+        assert!(filter.report(false, now));
+        assert!(filter.report(true, now));
+    }
 
     #[test]
+    // The cdev backend describes whatever GPIO chip the host has, which may be none
+    #[cfg(any(feature = "pi_hw", feature = "fake_hw"))]
     fn hw_can_be_got() {
-        let hw = hw::get();
+        let hw = hw::get(Backend::default());
         assert!(hw.description().is_ok());
         println!(
             "{:?}",
@@ -183,8 +705,9 @@ mod test {
     }
 
     #[test]
+    #[cfg(any(feature = "pi_hw", feature = "fake_hw"))]
     fn forty_board_pins() {
-        let hw = hw::get();
+        let hw = hw::get(Backend::default());
         let pin_set = hw
             .description()
             .expect("Could not get Hardware Description")
@@ -193,10 +716,10 @@ mod test {
     }
 
     #[test]
-    #[cfg(feature = "gui")]
+    #[cfg(all(feature = "gui", any(feature = "pi_hw", feature = "fake_hw")))]
     fn bcm_pins_sort_in_order() {
         // 0-27, not counting the gpio0 and gpio1 pins with no options
-        let hw = hw::get();
+        let hw = hw::get(Backend::default());
         let pin_set = hw
             .description()
             .expect("Could not get Hardware Description")
@@ -209,4 +732,69 @@ mod test {
             previous = pin.bcm.expect("Could not get BCM pin number");
         }
     }
+
+    fn cpuinfo_details(filename: &str) -> HardwareDetails {
+        let root = std::env::var("CARGO_MANIFEST_DIR").expect("Could not get manifest dir");
+        let path = PathBuf::from(root).join("test_data/cpuinfo").join(filename);
+        let cpuinfo = std::fs::read_to_string(path).expect("Could not read cpuinfo file");
+        HardwareDetails::from_cpuinfo(&cpuinfo)
+    }
+
+    #[test]
+    fn pi_4_cpuinfo() {
+        let details = cpuinfo_details("pi4b.txt");
+        assert_eq!(details.hardware, "Unknown");
+        assert_eq!(details.revision, "c03114");
+        assert_eq!(details.serial, "10000000a1b2c3d4");
+        assert_eq!(details.model, "Raspberry Pi 4 Model B Rev 1.4");
+        let board = details.board.expect("Could not decode revision");
+        assert_eq!(board.board_type, "4B");
+        assert_eq!(board.memory_mb, 4096);
+        assert_eq!(board.soc, "BCM2711");
+    }
+
+    #[test]
+    fn pi_3_cpuinfo() {
+        let details = cpuinfo_details("pi3b.txt");
+        assert_eq!(details.hardware, "BCM2835");
+        assert!(details.to_string().contains("Memory: 1GB"));
+        let board = details.board.expect("Could not decode revision");
+        assert_eq!(board.board_type, "3B");
+        assert_eq!(board.memory_mb, 1024);
+        assert_eq!(board.manufacturer, "Sony UK");
+        assert_eq!(board.pcb_revision, "1.2");
+    }
+
+    #[test]
+    fn old_style_cpuinfo_without_model() {
+        let details = cpuinfo_details("pi1b.txt");
+        assert_eq!(details.model, "Raspberry Pi B Rev 2.0");
+        let board = details.board.expect("Could not decode revision");
+        assert_eq!(board.memory_mb, 512);
+        assert!(board.warranty_voided);
+    }
+
+    #[test]
+    fn unknown_revision_not_decoded() {
+        let details = HardwareDetails::from_cpuinfo("Revision\t: xyz\n");
+        assert_eq!(details.revision, "xyz");
+        assert!(details.board.is_none());
+        assert_eq!(details.model, "Unknown");
+    }
+
+    #[test]
+    fn pin_probe_display() {
+        let probe = PinProbe {
+            mode: PinMode::Input,
+            pull: Some(InputPull::PullUp),
+            level: Some(true),
+        };
+        assert_eq!(probe.to_string(), "Input Pull Up (High)");
+        let probe = PinProbe {
+            mode: PinMode::Alt(AltMode::Alt0),
+            pull: None,
+            level: None,
+        };
+        assert_eq!(probe.to_string(), "ALT0");
+    }
 }
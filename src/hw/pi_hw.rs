@@ -1,70 +1,274 @@
 use std::collections::HashMap;
-use std::fs;
-use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::{fs, io, thread};
 
 use rppal::gpio::Gpio;
 use rppal::gpio::OutputPin;
 /// Implementation of GPIO for raspberry pi - uses rrpal
-use rppal::gpio::{InputPin, Level, Trigger};
+use rppal::gpio::{InputPin, IoPin, Level, Mode, Trigger};
+use rppal::i2c::I2c;
+use rppal::pwm::{Channel, Polarity, Pwm};
+use rppal::spi::{Bus, SlaveSelect, Spi};
+use rppal::system::{DeviceInfo, Model};
+use rppal::uart::{Parity, Uart};
 
+use crate::hw::gpclk::ClockManager;
+use crate::hw::gpio_pulls::read_pulls;
+use crate::hw::hat::{HatInfo, HAT_DEVICE_TREE};
+use crate::hw::i2c::{I2CAddress, I2C_SCAN_RANGE};
 use crate::hw::pin_description::PinDescriptionSet;
-use crate::hw::pin_descriptions::*;
-use crate::hw::{BCMPinNumber, PinLevel};
-use crate::hw::{InputPull, PinFunction};
+use crate::hw::pin_function::AltMode;
+use crate::hw::pin_headers::PinHeader;
+use crate::hw::spi::{SPIBus, SPIConfig, SPIMode};
+use crate::hw::uart::{UARTConfig, UARTParity};
+use crate::hw::{BCMPinNumber, PinLevel, PinMode, PinProbe};
+use crate::hw::{InputEdge, InputPull, PinFunction};
 
-use super::Hardware;
+use super::{
+    check_chip_select, check_gpclk, check_pwm, check_spi_config, check_supported,
+    check_uart_config, Hardware, LevelFilter,
+};
 use super::{HardwareDescription, HardwareDetails};
 
-/// Model the 40 pin GPIO connections - including Ground, 3.3V and 5V outputs
-/// For now, we will use the same descriptions for all hardware
-//noinspection DuplicatedCode
-const GPIO_PIN_DESCRIPTIONS: PinDescriptionSet = PinDescriptionSet::new([
-    PIN_1, PIN_2, PIN_3, PIN_4, PIN_5, PIN_6, PIN_7, PIN_8, PIN_9, PIN_10, PIN_11, PIN_12, PIN_13,
-    PIN_14, PIN_15, PIN_16, PIN_17, PIN_18, PIN_19, PIN_20, PIN_21, PIN_22, PIN_23, PIN_24, PIN_25,
-    PIN_26, PIN_27, PIN_28, PIN_29, PIN_30, PIN_31, PIN_32, PIN_33, PIN_34, PIN_35, PIN_36, PIN_37,
-    PIN_38, PIN_39, PIN_40,
-]);
-
 enum Pin {
     // Cache the input level and only report REAL edge changes
     Input(InputPin),
     Output(OutputPin),
+    // PWM generated by one of the hardware PWM channels
+    Pwm(Pwm),
+    // PWM generated in software by toggling an output
+    SoftPwm(OutputPin),
+    // Pin connected to a peripheral (such as a bus) by selecting an alternate function mode.
+    // The IoPin is held so the mode is kept until the pin is reconfigured
+    #[allow(dead_code)]
+    Alt(IoPin),
+    // Pin outputting one of the General Purpose Clocks, by number
+    #[allow(dead_code)]
+    Gpclk(IoPin, u8),
+}
+
+/// The UART on GPIO14 and GPIO15 that rppal opens, a link to the device of the PL011 or mini UART
+const SERIAL0: &str = "/dev/serial0";
+
+/// How long the UART reading thread waits before checking again when no bytes were received
+const UART_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The UART, shared with the thread that reads from it. The thread stops when this is dropped
+struct UartPort {
+    uart: Arc<Mutex<Uart>>,
+    running: Arc<AtomicBool>,
 }
 
-struct PiHW {
+impl Drop for UartPort {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+}
+
+pub struct PiHW {
+    /// The pins of the model of Pi this is running on
+    pin_descriptions: PinDescriptionSet,
     configured_pins: HashMap<BCMPinNumber, Pin>,
+    /// I2C bus #1 - opened when first used
+    i2c: Option<I2c>,
+    /// Settings for each SPI bus, used when a chip select on it is opened
+    spi_configs: HashMap<SPIBus, SPIConfig>,
+    /// SPI devices that have been opened, by bus and chip select
+    spi_devices: HashMap<(SPIBus, u8), Spi>,
+    /// UART on GPIO14/15 - opened when its config is applied
+    uart: Option<UartPort>,
+    /// Clock Manager registers, mapped when a General Purpose Clock is first used
+    clock_manager: Option<ClockManager>,
+    /// The state the pins were found in when started, before any were configured
+    probe: HashMap<BCMPinNumber, PinProbe>,
+}
+
+/// Is this running on a Raspberry Pi model that rppal can identify
+pub fn is_available() -> bool {
+    DeviceInfo::new().is_ok()
 }
 
 /// This method is used to get a "handle" onto the Hardware implementation
-pub fn get() -> impl Hardware {
+pub fn get() -> PiHW {
+    let pin_descriptions = PiHW::pin_header().pin_descriptions();
     PiHW {
+        probe: PiHW::probe(&pin_descriptions).unwrap_or_default(),
+        pin_descriptions,
         configured_pins: Default::default(),
+        i2c: None,
+        spi_configs: Default::default(),
+        spi_devices: Default::default(),
+        uart: None,
+        clock_manager: None,
     }
 }
 
 impl PiHW {
-    fn get_details() -> io::Result<HardwareDetails> {
-        let mut details = HardwareDetails {
-            hardware: "Unknown".to_string(),
-            revision: "Unknown".to_string(),
-            serial: "Unknown".to_string(),
-            model: "Unknown".to_string(),
-        };
+    /// Find the [PinHeader] of the model of Pi this is running on. If it cannot be identified,
+    /// assume it is a recent model with a 40-pin header
+    fn pin_header() -> PinHeader {
+        match DeviceInfo::new().map(|info| info.model()) {
+            Ok(Model::RaspberryPiBRev1) => PinHeader::Rev1,
+            Ok(Model::RaspberryPiA | Model::RaspberryPiBRev2) => PinHeader::Rev2,
+            Ok(Model::RaspberryPi4B | Model::RaspberryPi400 | Model::RaspberryPiComputeModule4) => {
+                PinHeader::Bcm2711
+            }
+            Ok(Model::RaspberryPi5) => PinHeader::Rp1,
+            Ok(
+                Model::RaspberryPiComputeModule
+                | Model::RaspberryPiComputeModule3
+                | Model::RaspberryPiComputeModule3Plus,
+            ) => PinHeader::ComputeModule,
+            Ok(Model::RaspberryPiComputeModule4S) => PinHeader::ComputeModule4S,
+            Ok(_) => PinHeader::Bcm283x,
+            Err(_) => PinHeader::default(),
+        }
+    }
 
-        for line in fs::read_to_string("/proc/cpuinfo")?.lines() {
-            match line
-                .split_once(':')
-                .map(|(key, value)| (key.trim(), value.trim()))
-            {
-                Some(("Hardware", hw)) => details.hardware = hw.to_string(),
-                Some(("Revision", revision)) => details.revision = revision.to_string(),
-                Some(("Serial", serial)) => details.serial = serial.to_string(),
-                Some(("Model", model)) => details.model = model.to_string(),
-                _ => {}
+    /// Read the mode and level of the pins, without changing their mode. The pull of an input
+    /// is only read on the BCM2711, as earlier SoCs cannot report it. Pins that cannot be got
+    /// are skipped
+    fn probe(pin_descriptions: &PinDescriptionSet) -> io::Result<HashMap<BCMPinNumber, PinProbe>> {
+        let gpio = Gpio::new().map_err(io::Error::other)?;
+        let pulls = read_pulls().unwrap_or_default();
+        let mut probe = HashMap::new();
+        for bcm_pin_number in pin_descriptions.pins().iter().filter_map(|pin| pin.bcm) {
+            if let Ok(pin) = gpio.get(bcm_pin_number) {
+                let mode = Self::pin_mode(pin.mode());
+                let pull = match mode {
+                    PinMode::Input => pulls.get(bcm_pin_number as usize).copied(),
+                    _ => None,
+                };
+                probe.insert(
+                    bcm_pin_number,
+                    PinProbe {
+                        mode,
+                        pull,
+                        level: Some(pin.read() == Level::High),
+                    },
+                );
             }
         }
+        Ok(probe)
+    }
+
+    fn get_details() -> io::Result<HardwareDetails> {
+        Ok(HardwareDetails::from_cpuinfo(&fs::read_to_string(
+            "/proc/cpuinfo",
+        )?))
+    }
+
+    /// Return the hardware PWM [Channel] a pin is connected to, if the pin has been put into the
+    /// alternate function mode for PWM (e.g. by "dtoverlay=pwm-2chan" in config.txt)
+    fn pwm_channel(bcm_pin_number: BCMPinNumber, mode: Mode) -> Option<Channel> {
+        match (bcm_pin_number, mode) {
+            (12, Mode::Alt0) | (18, Mode::Alt5) => Some(Channel::Pwm0),
+            (13, Mode::Alt0) | (19, Mode::Alt5) => Some(Channel::Pwm1),
+            _ => None,
+        }
+    }
+
+    /// The [PinMode] of a rppal [Mode]. The Pi 5 has alternate modes beyond ALT5, which
+    /// are not known
+    fn pin_mode(mode: Mode) -> PinMode {
+        match mode {
+            Mode::Input => PinMode::Input,
+            Mode::Output => PinMode::Output,
+            Mode::Alt0 => PinMode::Alt(AltMode::Alt0),
+            Mode::Alt1 => PinMode::Alt(AltMode::Alt1),
+            Mode::Alt2 => PinMode::Alt(AltMode::Alt2),
+            Mode::Alt3 => PinMode::Alt(AltMode::Alt3),
+            Mode::Alt4 => PinMode::Alt(AltMode::Alt4),
+            Mode::Alt5 => PinMode::Alt(AltMode::Alt5),
+            _ => PinMode::Unknown,
+        }
+    }
+
+    /// The mode that connects GPIO14 and GPIO15 to the UART `device` that "/dev/serial0" links
+    /// to. On models with Bluetooth the firmware uses the PL011 UART (ttyAMA0) for Bluetooth by
+    /// default, and serial0 is the mini UART (ttyS0), which is ALT5 on these pins
+    fn uart_alt_mode(device: &Path) -> AltMode {
+        let is_mini_uart = device
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| name.starts_with("ttyS"))
+            .unwrap_or(false);
+        if is_mini_uart {
+            AltMode::Alt5
+        } else {
+            AltMode::Alt0
+        }
+    }
+
+    /// Get the [ClockManager], mapping its registers if not already done
+    fn clock_manager(&mut self) -> io::Result<&mut ClockManager> {
+        if self.clock_manager.is_none() {
+            self.clock_manager = Some(ClockManager::open()?);
+        }
+        self.clock_manager
+            .as_mut()
+            .ok_or_else(|| io::Error::other("Could not open Clock Manager"))
+    }
+
+    /// Get the I2C bus #1, opening it if it is not already open.
+    /// This requires the I2C interface to be enabled with "dtparam=i2c_arm=on" in config.txt
+    fn i2c(&mut self) -> io::Result<&mut I2c> {
+        if self.i2c.is_none() {
+            self.i2c = Some(I2c::with_bus(1).map_err(io::Error::other)?);
+        }
+        self.i2c
+            .as_mut()
+            .ok_or_else(|| io::Error::other("Could not open I2C bus"))
+    }
+
+    /// Get the I2C bus #1 with the device at `address` selected
+    fn i2c_device(&mut self, address: I2CAddress) -> io::Result<&mut I2c> {
+        let i2c = self.i2c()?;
+        i2c.set_slave_address(address).map_err(io::Error::other)?;
+        Ok(i2c)
+    }
 
-        Ok(details)
+    /// Convert an [SPIMode] into the equivalent rppal mode
+    fn spi_mode(mode: SPIMode) -> rppal::spi::Mode {
+        match mode {
+            SPIMode::Mode0 => rppal::spi::Mode::Mode0,
+            SPIMode::Mode1 => rppal::spi::Mode::Mode1,
+            SPIMode::Mode2 => rppal::spi::Mode::Mode2,
+            SPIMode::Mode3 => rppal::spi::Mode::Mode3,
+        }
+    }
+
+    /// Get the SPI device for `chip_select` on `bus`, opening it with the bus's settings if it
+    /// is not already open. This requires the bus to be enabled in config.txt, with
+    /// "dtparam=spi=on" for SPI0 and "dtoverlay=spi1-3cs" for SPI1
+    fn spi_device(&mut self, bus: SPIBus, chip_select: u8) -> io::Result<&Spi> {
+        check_chip_select(bus, chip_select)?;
+        if !self.spi_devices.contains_key(&(bus, chip_select)) {
+            let config = self.spi_configs.get(&bus).copied().unwrap_or_default();
+            let rppal_bus = match bus {
+                SPIBus::SPI0 => Bus::Spi0,
+                SPIBus::SPI1 => Bus::Spi1,
+            };
+            let slave_select = match chip_select {
+                0 => SlaveSelect::Ss0,
+                1 => SlaveSelect::Ss1,
+                _ => SlaveSelect::Ss2,
+            };
+            let spi = Spi::new(
+                rppal_bus,
+                slave_select,
+                config.clock_speed,
+                Self::spi_mode(config.mode),
+            )
+            .map_err(io::Error::other)?;
+            self.spi_devices.insert((bus, chip_select), spi);
+        }
+        self.spi_devices
+            .get(&(bus, chip_select))
+            .ok_or_else(|| io::Error::other(format!("Could not open {bus} CE{chip_select}")))
     }
 }
 
@@ -75,7 +279,9 @@ impl Hardware for PiHW {
     fn description(&self) -> io::Result<HardwareDescription> {
         Ok(HardwareDescription {
             details: Self::get_details()?,
-            pins: GPIO_PIN_DESCRIPTIONS,
+            pins: self.pin_descriptions.clone(),
+            hat: HatInfo::read(Path::new(HAT_DEVICE_TREE)).unwrap_or(None),
+            probe: self.probe_pins().unwrap_or_default(),
         })
     }
 
@@ -89,34 +295,78 @@ impl Hardware for PiHW {
     where
         C: FnMut(BCMPinNumber, PinLevel) + Send + Sync + Clone + 'static,
     {
-        // If it was already configured, remove it
-        self.configured_pins.remove(&bcm_pin_number);
+        // A pin already generating PWM can have its settings changed without re-configuring it
+        if let PinFunction::Pwm {
+            frequency,
+            duty_cycle,
+        } = pin_function
+        {
+            check_pwm(*frequency, *duty_cycle)?;
+            match self.configured_pins.get_mut(&bcm_pin_number) {
+                Some(Pin::Pwm(pwm)) => {
+                    return pwm
+                        .set_frequency(*frequency, *duty_cycle)
+                        .map_err(io::Error::other);
+                }
+                Some(Pin::SoftPwm(output_pin)) => {
+                    return output_pin
+                        .set_pwm_frequency(*frequency, *duty_cycle)
+                        .map_err(io::Error::other);
+                }
+                _ => {}
+            }
+        }
+
+        // A pin already outputting a clock can have its frequency changed without re-configuring it
+        if let Some((clock, frequency)) = pin_function.gpclk() {
+            check_gpclk(&self.pin_descriptions, bcm_pin_number, pin_function)?;
+            if let Some(Pin::Gpclk(_, current_clock)) = self.configured_pins.get(&bcm_pin_number) {
+                if *current_clock == clock {
+                    return self.clock_manager()?.start(clock, frequency);
+                }
+            }
+        }
+
+        // If it was already configured, remove it, stopping any clock it was outputting
+        if let Some(Pin::Gpclk(_, clock)) = self.configured_pins.remove(&bcm_pin_number) {
+            self.clock_manager()?.stop(clock)?;
+        }
 
         match pin_function {
-            PinFunction::Input(pull) => {
+            PinFunction::Input(pull, filter) => {
                 let pin = Gpio::new()
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+                    .map_err(io::Error::other)?
                     .get(bcm_pin_number)
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                    .map_err(io::Error::other)?;
 
                 let mut input = match pull {
                     None | Some(InputPull::None) => pin.into_input(),
                     Some(InputPull::PullUp) => pin.into_input_pullup(),
                     Some(InputPull::PullDown) => pin.into_input_pulldown(),
                 };
+                // Only interrupt on the edges wanted, and debounce the changes reported
+                let trigger = match filter.map(|filter| filter.edge) {
+                    Some(InputEdge::Rising) => Trigger::RisingEdge,
+                    Some(InputEdge::Falling) => Trigger::FallingEdge,
+                    Some(InputEdge::Both) | None => Trigger::Both,
+                };
+                let mut level_filter = LevelFilter::new(*filter);
                 input
-                    .set_async_interrupt(Trigger::Both, move |level| {
-                        callback(bcm_pin_number, level == Level::High);
+                    .set_async_interrupt(trigger, move |level| {
+                        let level = level == Level::High;
+                        if level_filter.report(level, Instant::now()) {
+                            callback(bcm_pin_number, level);
+                        }
                     })
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+                    .map_err(|e| io::Error::other(e.to_string()))?;
                 self.configured_pins
                     .insert(bcm_pin_number, Pin::Input(input));
             }
             PinFunction::Output(value) => {
                 let pin = Gpio::new()
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+                    .map_err(io::Error::other)?
                     .get(bcm_pin_number)
-                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+                    .map_err(io::Error::other)?;
                 let output_pin = match value {
                     Some(true) => pin.into_output_high(),
                     Some(false) => pin.into_output_low(),
@@ -125,18 +375,113 @@ impl Hardware for PiHW {
                 self.configured_pins
                     .insert(bcm_pin_number, Pin::Output(output_pin));
             }
+            PinFunction::Pwm {
+                frequency,
+                duty_cycle,
+            } => {
+                let pin = Gpio::new()
+                    .map_err(io::Error::other)?
+                    .get(bcm_pin_number)
+                    .map_err(io::Error::other)?;
+                let pwm_pin = match Self::pwm_channel(bcm_pin_number, pin.mode()) {
+                    Some(channel) => Pin::Pwm(
+                        Pwm::with_frequency(
+                            channel,
+                            *frequency,
+                            *duty_cycle,
+                            Polarity::Normal,
+                            true,
+                        )
+                        .map_err(io::Error::other)?,
+                    ),
+                    None => {
+                        let mut output_pin = pin.into_output_low();
+                        output_pin
+                            .set_pwm_frequency(*frequency, *duty_cycle)
+                            .map_err(io::Error::other)?;
+                        Pin::SoftPwm(output_pin)
+                    }
+                };
+                self.configured_pins.insert(bcm_pin_number, pwm_pin);
+            }
+
+            // I2C bus #1 is connected to GPIO2 and GPIO3 using ALT0
+            PinFunction::I2C1_SDA | PinFunction::I2C1_SCL => {
+                check_supported(&self.pin_descriptions, bcm_pin_number, pin_function)?;
+                self.apply_alt_mode(bcm_pin_number, AltMode::Alt0)?;
+            }
+
+            // The UART opened is connected to GPIO14 and GPIO15 using the mode of whichever UART
+            // "/dev/serial0" is
+            PinFunction::UART0_TXD | PinFunction::UART0_RXD => {
+                check_supported(&self.pin_descriptions, bcm_pin_number, pin_function)?;
+                let device = fs::read_link(SERIAL0).unwrap_or_default();
+                self.apply_alt_mode(bcm_pin_number, Self::uart_alt_mode(&device))?;
+            }
+
+            // SPI0 is connected to GPIO7-11 using ALT0
+            PinFunction::SPI0_MOSI
+            | PinFunction::SPI0_MISO
+            | PinFunction::SPI0_SCLK
+            | PinFunction::SPI0_CE0_N
+            | PinFunction::SPI0_CE1_N => {
+                check_supported(&self.pin_descriptions, bcm_pin_number, pin_function)?;
+                self.apply_alt_mode(bcm_pin_number, AltMode::Alt0)?;
+            }
+
+            // SPI1 is connected to GPIO16-21 using ALT4
+            PinFunction::SPI1_MOSI
+            | PinFunction::SPI1_MISO
+            | PinFunction::SPI1_SCLK
+            | PinFunction::SPI1_CE0_N
+            | PinFunction::SPI1_CE1_N
+            | PinFunction::SPI1_CE2_N => {
+                check_supported(&self.pin_descriptions, bcm_pin_number, pin_function)?;
+                self.apply_alt_mode(bcm_pin_number, AltMode::Alt4)?;
+            }
+
+            // Any other alternate function the pin has
+            PinFunction::Alt { mode, .. } => {
+                check_supported(&self.pin_descriptions, bcm_pin_number, pin_function)?;
+                self.apply_alt_mode(bcm_pin_number, *mode)?;
+            }
+
+            // General Purpose Clocks are on GPIO4-6 using ALT0, and GPCLK0/1 also on GPIO20/21
+            // using ALT5
+            PinFunction::GPCLK0 { frequency }
+            | PinFunction::GPCLK1 { frequency }
+            | PinFunction::GPCLK2 { frequency } => {
+                let clock = pin_function.gpclk().map(|(clock, _)| clock).unwrap_or(0);
+                let mode = if bcm_pin_number >= 20 {
+                    Mode::Alt5
+                } else {
+                    Mode::Alt0
+                };
+                // Take the pin before starting the clock, so a pin that cannot be used does not
+                // leave a clock running. The pin goes back to its previous mode if dropped
+                let pin = Gpio::new()
+                    .map_err(io::Error::other)?
+                    .get(bcm_pin_number)
+                    .map_err(io::Error::other)?
+                    .into_io(mode);
+                let clock_manager = self.clock_manager()?;
+                if let Err(e) = clock_manager.start(clock, *frequency) {
+                    let _ = clock_manager.stop(clock);
+                    return Err(e);
+                }
+                self.configured_pins
+                    .insert(bcm_pin_number, Pin::Gpclk(pin, clock));
+            }
 
             // HAT EEPROM ID functions, only used at boot and not configurable
             PinFunction::I2C_EEPROM_ID_SD | PinFunction::I2C_EEPROM_ID_SC => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
+                return Err(io::Error::other(
                     "I2C_EEPROM_ID_SD and SC pins cannot be configured",
                 ));
             }
 
             PinFunction::Ground | PinFunction::Power3V3 | PinFunction::Power5V => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
+                return Err(io::Error::other(
                     "Ground, 3V3 or 5V pins cannot be configured",
                 ));
             }
@@ -147,14 +492,41 @@ impl Hardware for PiHW {
         Ok(())
     }
 
+    /// Put the pin into the rppal [Mode] for `mode`, holding it so the mode is kept until the
+    /// pin is reconfigured
+    fn apply_alt_mode(&mut self, bcm_pin_number: BCMPinNumber, mode: AltMode) -> io::Result<()> {
+        let pin = Gpio::new()
+            .map_err(io::Error::other)?
+            .get(bcm_pin_number)
+            .map_err(io::Error::other)?;
+        let mode = match mode {
+            AltMode::Alt0 => Mode::Alt0,
+            AltMode::Alt1 => Mode::Alt1,
+            AltMode::Alt2 => Mode::Alt2,
+            AltMode::Alt3 => Mode::Alt3,
+            AltMode::Alt4 => Mode::Alt4,
+            AltMode::Alt5 => Mode::Alt5,
+        };
+        self.configured_pins
+            .insert(bcm_pin_number, Pin::Alt(pin.into_io(mode)));
+        Ok(())
+    }
+
+    /// The state the pins not configured were found in when started
+    fn probe_pins(&self) -> io::Result<HashMap<BCMPinNumber, PinProbe>> {
+        Ok(self
+            .probe
+            .iter()
+            .filter(|(bcm_pin_number, _)| !self.configured_pins.contains_key(bcm_pin_number))
+            .map(|(bcm_pin_number, probe)| (*bcm_pin_number, *probe))
+            .collect())
+    }
+
     /// Read the input level of an input using the bcm pin number
     fn get_input_level(&self, bcm_pin_number: BCMPinNumber) -> io::Result<bool> {
         match self.configured_pins.get(&bcm_pin_number) {
             Some(Pin::Input(input_pin)) => Ok(input_pin.read() == Level::High),
-            _ => Err(io::Error::new(
-                io::ErrorKind::Other,
-                "Could not find a configured input pin",
-            )),
+            _ => Err(io::Error::other("Could not find a configured input pin")),
         }
     }
 
@@ -169,20 +541,140 @@ impl Hardware for PiHW {
                 true => output_pin.write(Level::High),
                 false => output_pin.write(Level::Low),
             },
-            _ => {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    "Could not find a configured output pin",
-                ))
+            _ => return Err(io::Error::other("Could not find a configured output pin")),
+        }
+        Ok(())
+    }
+
+    /// Scan I2C bus #1 for devices, by attempting to read a byte from each address
+    fn i2c_scan(&mut self) -> io::Result<Vec<I2CAddress>> {
+        let mut devices = vec![];
+        let mut buffer = [0u8; 1];
+        for address in I2C_SCAN_RANGE {
+            if self.i2c_device(address)?.read(&mut buffer).is_ok() {
+                devices.push(address);
+            }
+        }
+        Ok(devices)
+    }
+
+    fn i2c_read(&mut self, address: I2CAddress, length: usize) -> io::Result<Vec<u8>> {
+        let mut buffer = vec![0u8; length];
+        let count = self
+            .i2c_device(address)?
+            .read(&mut buffer)
+            .map_err(io::Error::other)?;
+        buffer.truncate(count);
+        Ok(buffer)
+    }
+
+    fn i2c_write(&mut self, address: I2CAddress, data: &[u8]) -> io::Result<()> {
+        self.i2c_device(address)?
+            .write(data)
+            .map_err(io::Error::other)?;
+        Ok(())
+    }
+
+    /// Write the register number and read the data back using a repeated start condition
+    fn i2c_read_register(
+        &mut self,
+        address: I2CAddress,
+        register: u8,
+        length: usize,
+    ) -> io::Result<Vec<u8>> {
+        let mut buffer = vec![0u8; length];
+        self.i2c_device(address)?
+            .write_read(&[register], &mut buffer)
+            .map_err(io::Error::other)?;
+        Ok(buffer)
+    }
+
+    /// Store the settings for the bus and apply them to any of its chip selects already open
+    fn apply_spi_config(&mut self, bus: SPIBus, config: &SPIConfig) -> io::Result<()> {
+        check_spi_config(bus, config)?;
+        self.spi_configs.insert(bus, *config);
+        for ((device_bus, _), spi) in &self.spi_devices {
+            if *device_bus == bus {
+                spi.set_mode(Self::spi_mode(config.mode))
+                    .map_err(io::Error::other)?;
+                spi.set_clock_speed(config.clock_speed)
+                    .map_err(io::Error::other)?;
             }
         }
         Ok(())
     }
+
+    fn spi_transfer(&mut self, bus: SPIBus, chip_select: u8, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut buffer = vec![0u8; data.len()];
+        self.spi_device(bus, chip_select)?
+            .transfer(&mut buffer, data)
+            .map_err(io::Error::other)?;
+        Ok(buffer)
+    }
+
+    /// Open the UART connected to GPIO14/15 ("/dev/serial0"), which requires "enable_uart=1" in
+    /// config.txt and the serial console to be disabled. A thread polls it for received bytes
+    fn apply_uart_config<D>(&mut self, config: &UARTConfig, mut callback: D) -> io::Result<()>
+    where
+        D: FnMut(Vec<u8>) + Send + 'static,
+    {
+        check_uart_config(config)?;
+        // Close any UART already open, stopping its reading thread
+        self.uart = None;
+
+        let parity = match config.parity {
+            UARTParity::None => Parity::None,
+            UARTParity::Even => Parity::Even,
+            UARTParity::Odd => Parity::Odd,
+        };
+        let mut uart =
+            Uart::new(config.baud_rate, parity, 8, config.stop_bits).map_err(io::Error::other)?;
+        uart.set_write_mode(true).map_err(io::Error::other)?;
+
+        let uart = Arc::new(Mutex::new(uart));
+        let running = Arc::new(AtomicBool::new(true));
+        let reader = uart.clone();
+        let reader_running = running.clone();
+        thread::spawn(move || {
+            let mut buffer = [0u8; 256];
+            while reader_running.load(Ordering::Relaxed) {
+                let count = match reader.lock() {
+                    Ok(mut uart) => uart.read(&mut buffer).unwrap_or(0),
+                    Err(_) => break,
+                };
+                if count > 0 {
+                    callback(buffer[..count].to_vec());
+                } else {
+                    thread::sleep(UART_POLL_INTERVAL);
+                }
+            }
+        });
+
+        self.uart = Some(UartPort { uart, running });
+        Ok(())
+    }
+
+    fn uart_write(&mut self, data: &[u8]) -> io::Result<()> {
+        let port = self
+            .uart
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotConnected, "UART is not open"))?;
+        let mut uart = port
+            .uart
+            .lock()
+            .map_err(|_| io::Error::other("Could not lock UART"))?;
+        let mut written = 0;
+        while written < data.len() {
+            written += uart.write(&data[written..]).map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::hw::Hardware;
+    use crate::hw::{Hardware, PinFunction};
+    use std::io;
 
     #[test]
     fn get_hardware() {
@@ -194,4 +686,87 @@ mod test {
         assert_eq!(pins.len(), 40);
         assert_eq!(pins[0].name, "3V3")
     }
+
+    #[test]
+    fn pwm_channels() {
+        use rppal::gpio::Mode;
+        use rppal::pwm::Channel;
+
+        assert_eq!(
+            super::PiHW::pwm_channel(12, Mode::Alt0),
+            Some(Channel::Pwm0)
+        );
+        assert_eq!(
+            super::PiHW::pwm_channel(19, Mode::Alt5),
+            Some(Channel::Pwm1)
+        );
+        // Not muxed to the PWM channel, so software PWM will be used
+        assert_eq!(super::PiHW::pwm_channel(18, Mode::Output), None);
+        assert_eq!(super::PiHW::pwm_channel(17, Mode::Alt5), None);
+    }
+
+    #[test]
+    fn i2c_on_unsupported_pin() {
+        let mut hw = super::get();
+        // ALT0 of GPIO4 is GPCLK0, not I2C
+        let error = hw
+            .apply_pin_config(4, &PinFunction::I2C1_SDA, |_, _| {})
+            .expect_err("I2C1_SDA was applied to GPIO4");
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn uart_on_unsupported_pin() {
+        let mut hw = super::get();
+        let error = hw
+            .apply_pin_config(4, &PinFunction::UART0_TXD, |_, _| {})
+            .expect_err("UART0_TXD was applied to GPIO4");
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn uart_alt_modes() {
+        use crate::hw::pin_function::AltMode;
+        use std::path::Path;
+
+        assert_eq!(
+            super::PiHW::uart_alt_mode(Path::new("ttyS0")),
+            AltMode::Alt5
+        );
+        assert_eq!(
+            super::PiHW::uart_alt_mode(Path::new("ttyAMA0")),
+            AltMode::Alt0
+        );
+        // serial0 not found, so the PL011 UART is assumed
+        assert_eq!(super::PiHW::uart_alt_mode(Path::new("")), AltMode::Alt0);
+    }
+
+    #[test]
+    fn spi_on_unsupported_pin() {
+        let mut hw = super::get();
+        // ALT4 of GPIO4 is JTAG, not SPI1
+        let error = hw
+            .apply_pin_config(4, &PinFunction::SPI1_MOSI, |_, _| {})
+            .expect_err("SPI1_MOSI was applied to GPIO4");
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+        let error = hw
+            .apply_pin_config(17, &PinFunction::SPI0_MOSI, |_, _| {})
+            .expect_err("SPI0_MOSI was applied to GPIO17");
+        assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn pin_modes() {
+        use crate::hw::pin_function::AltMode;
+        use crate::hw::PinMode;
+        use rppal::gpio::Mode;
+
+        assert_eq!(super::PiHW::pin_mode(Mode::Input), PinMode::Input);
+        assert_eq!(
+            super::PiHW::pin_mode(Mode::Alt4),
+            PinMode::Alt(AltMode::Alt4)
+        );
+        // Only on the Pi 5
+        assert_eq!(super::PiHW::pin_mode(Mode::Alt8), PinMode::Unknown);
+    }
 }
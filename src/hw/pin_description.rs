@@ -20,19 +20,34 @@ pub struct PinDescription {
     pub options: Cow<'static, [PinFunction]>, // The set of functions the pin can have, chosen by user config
 }
 
-/// Struct describing all the pins for the connected hardware.
-/// Array indexed from 0 so, board_pin_number -1, as pin numbering start at 1
+impl PinDescription {
+    /// Return true if `function` is one of the options of this pin, ignoring any settings
+    /// (such as a frequency) that it has. Alternate functions must be in the same mode
+    #[allow(dead_code)] // for piggui
+    pub fn supports(&self, function: &PinFunction) -> bool {
+        self.options.iter().any(|option| match (option, function) {
+            (PinFunction::Alt { mode, .. }, PinFunction::Alt { mode: other, .. }) => mode == other,
+            _ => std::mem::discriminant(option) == std::mem::discriminant(function),
+        })
+    }
+}
+
+/// Struct describing all the pins for the connected hardware, in order of [BoardPinNumber].
+/// The number of pins depends on the hardware: 26 or 40 for the GPIO header of a Pi, 46 for a
+/// Compute Module, or the number of lines of a GPIO chip
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PinDescriptionSet {
-    #[serde(with = "serde_arrays")]
-    pins: [PinDescription; 40],
+    pins: Cow<'static, [PinDescription]>,
 }
 
 /// `PinDescriptionSet` describes a set of Pins on a device, using `PinDescription`s
 impl PinDescriptionSet {
     /// Create a new PinDescriptionSet, from a const array of PinDescriptions
-    pub const fn new(pins: [PinDescription; 40]) -> PinDescriptionSet {
-        PinDescriptionSet { pins }
+    #[allow(dead_code)] // for the cdev backend, which builds its set from the chip's lines
+    pub const fn new(pins: &'static [PinDescription]) -> PinDescriptionSet {
+        PinDescriptionSet {
+            pins: Cow::Borrowed(pins),
+        }
     }
 
     /// Return a slice of PinDescriptions
@@ -41,9 +56,16 @@ impl PinDescriptionSet {
         &self.pins
     }
 
+    /// Return the [PinDescription] of the pin with [BCMPinNumber] `bcm_pin_number`, if any
+    #[allow(dead_code)] // for piggui
+    pub fn get_by_bcm(&self, bcm_pin_number: BCMPinNumber) -> Option<&PinDescription> {
+        self.pins.iter().find(|pin| pin.bcm == Some(bcm_pin_number))
+    }
+
     /// Return a set of PinDescriptions *only** for pins that have BCM pin numbering, sorted in
     /// ascending order of [BCMPinNumber]
     #[cfg(any(feature = "gui", test))]
+    #[allow(dead_code)] // for piglet
     pub fn bcm_pins_sorted(&self) -> Vec<&PinDescription> {
         let mut pins = self
             .pins
@@ -56,6 +78,15 @@ impl PinDescriptionSet {
     }
 }
 
+impl From<Vec<PinDescription>> for PinDescriptionSet {
+    /// Create a new PinDescriptionSet from PinDescriptions discovered at run time
+    fn from(pins: Vec<PinDescription>) -> Self {
+        PinDescriptionSet {
+            pins: Cow::Owned(pins),
+        }
+    }
+}
+
 impl Display for PinDescription {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         writeln!(f, "Board Pin #: {}", self.bpn)?;
@@ -68,9 +99,51 @@ impl Display for PinDescription {
 #[cfg(test)]
 mod test {
     use crate::hw::pin_description::{PinDescription, PinDescriptionSet};
-    use crate::hw::pin_function::PinFunction;
+    use crate::hw::pin_function::{AltMode, PinFunction};
     use std::borrow::Cow;
 
+    #[test]
+    fn supports_ignores_settings() {
+        let pin = PinDescription {
+            bpn: 7,
+            bcm: Some(4),
+            name: Cow::from("GPIO4"),
+            options: Cow::from(vec![
+                PinFunction::Input(None, None),
+                PinFunction::GPCLK0 {
+                    frequency: 1_000_000.0,
+                },
+            ]),
+        };
+
+        assert!(pin.supports(&PinFunction::GPCLK0 { frequency: 25.0 }));
+        assert!(!pin.supports(&PinFunction::GPCLK1 {
+            frequency: 1_000_000.0
+        }));
+    }
+
+    #[test]
+    fn supports_alt_mode() {
+        let pin = PinDescription {
+            bpn: 3,
+            bcm: Some(2),
+            name: Cow::from("GPIO2"),
+            options: Cow::from(vec![PinFunction::Alt {
+                mode: AltMode::Alt3,
+                name: Cow::Borrowed("SPI3_MOSI"),
+            }]),
+        };
+
+        assert!(pin.supports(&PinFunction::Alt {
+            mode: AltMode::Alt3,
+            name: Cow::Borrowed("SPI3_MOSI"),
+        }));
+        assert!(!pin.supports(&PinFunction::Alt {
+            mode: AltMode::Alt4,
+            name: Cow::Borrowed("CTS2"),
+        }));
+    }
+
     #[test]
     fn display_pin_description() {
         let pin = PinDescription {
@@ -89,14 +162,20 @@ mod test {
             bpn: 7,
             bcm: Some(11),
             name: Cow::from("Fake Pin"),
-            options: Cow::from(vec![PinFunction::Input(None), PinFunction::Output(None)]),
+            options: Cow::from(vec![
+                PinFunction::Input(None, None),
+                PinFunction::Output(None),
+            ]),
         };
 
         let pin8 = PinDescription {
             bpn: 8,
             bcm: Some(1),
             name: Cow::from("Fake Pin"),
-            options: Cow::from(vec![PinFunction::Input(None), PinFunction::Output(None)]),
+            options: Cow::from(vec![
+                PinFunction::Input(None, None),
+                PinFunction::Output(None),
+            ]),
         };
 
         let pins = [
@@ -141,7 +220,7 @@ mod test {
             pin7.clone(),
             pin7.clone(),
         ];
-        let pin_set = PinDescriptionSet::new(pins);
+        let pin_set = PinDescriptionSet::from(pins.to_vec());
         assert_eq!(
             pin_set
                 .pins
@@ -1,27 +1,48 @@
+//! This module codifies the descriptions if the Raspberry Pi GPIO hardware
+//! exposed pins, including multiple options (functions) available for some pins
+//! via software configuration.
+//!
+//! In general, it has been harvested from the
+//! [official Raspberry Pi docs](https://www.raspberrypi.com/documentation/computers/raspberry-pi.html#gpio-and-the-40-pin-header)
+//! , although sometimes augmented with other sources.
+//!
+//! The default Pullup/Pulldown settings are taking from the "BCM2711 ARM Peripherals" document
+//! as are the alternate functions (ALT0 - ALT5) of each pin
+//!
+//! These pin descriptions are valid for Raspberry Pi Models B+, 2B, Zero, 3B, 3B+,
+//! 4B, Zero W, Zero2 W, 5. The differences of other models are in [crate::hw::pin_headers]
+//!
+//! For SPI interface description, see [here](https://www.raspberrypi.com/documentation/computers/raspberry-pi.html#serial-peripheral-interface-spi)
+//! "Raspberry Pi Zero, 1, 2 and 3 have three SPI controllers:"
+//!
+//! "Raspberry Pi 4, 400 and Compute Module 4 there are four additional SPI buses: SPI3 to SPI6,
+//! each with two hardware chip selects. These extra SPI buses are available via alternate function
+//! assignments on certain GPIO pins. For more information, see the BCM2711 Arm peripherals
+//! datasheet."
+
 use crate::hw::pin_description::PinDescription;
+use crate::hw::pin_function::AltMode;
 use crate::hw::{InputPull, PinFunction};
 use std::borrow::Cow;
 
-/// This module codifies the descriptions if the Raspberry Pi GPIO hardware
-/// exposed pins, including multiple options (functions) available for some pins
-/// via software configuration.
-///
-/// In general, it has been harvested from the
-/// [official Raspberry Pi docs](https://www.raspberrypi.com/documentation/computers/raspberry-pi.html#gpio-and-the-40-pin-header)
-/// , although sometimes augmented with other sources.
-///
-/// The default Pullup/Pulldown settings are taking from the "BCM2711 ARM Peripherals" document
-///
-/// These pin descriptions are valid for Raspberry Pi Models B+, 2B, Zero, 3B, 3B+,
-/// 4B, Zero W, Zero2 W, 5
-///
-/// For SPI interface description, see [here](https://www.raspberrypi.com/documentation/computers/raspberry-pi.html#serial-peripheral-interface-spi)
-/// "Raspberry Pi Zero, 1, 2 and 3 have three SPI controllers:"
-///
-/// "Raspberry Pi 4, 400 and Compute Module 4 there are four additional SPI buses: SPI3 to SPI6,
-/// each with two hardware chip selects. These extra SPI buses are available via alternate function
-/// assignments on certain GPIO pins. For more information, see the BCM2711 Arm peripherals
-/// datasheet."
+/// PWM can be generated (in software if not in hardware) on any GPIO pin, this is the initial
+/// setting used when it is selected for a pin
+pub(crate) const PWM: PinFunction = PinFunction::Pwm {
+    frequency: 1000.0,
+    duty_cycle: 0.5,
+};
+
+/// Initial frequency of a General Purpose Clock when it is selected for a pin
+const GPCLK_FREQUENCY: f64 = 1_000_000.0;
+
+/// An alternate function of a pin, named as in the "Alternative Function Assignments" table of
+/// the BCM2711 ARM Peripherals document, for the modes that pigg has no specific support for
+pub(crate) const fn alt(mode: AltMode, name: &'static str) -> PinFunction {
+    PinFunction::Alt {
+        mode,
+        name: Cow::Borrowed(name),
+    }
+}
 
 pub(crate) const PIN_1: PinDescription = PinDescription {
     bpn: 1,
@@ -43,14 +64,15 @@ pub(crate) const PIN_3: PinDescription = PinDescription {
     bcm: Some(2),
     name: Cow::Borrowed("GPIO2"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullUp)),
+        PinFunction::Input(Some(InputPull::PullUp), None),
         PinFunction::Output(None),
-        // TODO ALT0::I2C1_SDA / SDA1
-        // TODO ALT1: SMI SA3
-        // TODO ALT2: DPI VSYNC / LCD_VSYNC
-        // TODO ALT3: SPI3_MOSI / AVEOUT_VSYNC
-        // TODO ALT4: CTS2 / AVEIN_VSYNC
-        // TODO ALT5: I2C3_SDA / SDA3
+        PWM,
+        PinFunction::I2C1_SDA,
+        alt(AltMode::Alt1, "SMI_SA3"),
+        alt(AltMode::Alt2, "DPI_VSYNC"),
+        alt(AltMode::Alt3, "SPI3_MOSI"),
+        alt(AltMode::Alt4, "UART2_CTS"),
+        alt(AltMode::Alt5, "I2C3_SDA"),
     ]),
 };
 
@@ -67,14 +89,15 @@ pub(crate) const PIN_5: PinDescription = PinDescription {
     bcm: Some(3),
     name: Cow::Borrowed("GPIO3"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullUp)),
+        PinFunction::Input(Some(InputPull::PullUp), None),
         PinFunction::Output(None),
-        // TODO ALT0::I2C1_SCL / SCL1
-        // TODO ALT1: SMI SA2
-        // TODO ALT2: DPI_HSYNC / LCD_HSYNC
-        // TODO ALT3: SPI3_SCLK / AVEOUT_HSYNC
-        // TODO ALT4: RTS2 / AVEIN_HSYNC
-        // TODO ALT5: I2C3_SCL / SCL3
+        PWM,
+        PinFunction::I2C1_SCL,
+        alt(AltMode::Alt1, "SMI_SA2"),
+        alt(AltMode::Alt2, "DPI_HSYNC"),
+        alt(AltMode::Alt3, "SPI3_SCLK"),
+        alt(AltMode::Alt4, "UART2_RTS"),
+        alt(AltMode::Alt5, "I2C3_SCL"),
     ]),
 };
 
@@ -90,14 +113,17 @@ pub(crate) const PIN_7: PinDescription = PinDescription {
     bcm: Some(4),
     name: Cow::Borrowed("GPIO4"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullUp)),
+        PinFunction::Input(Some(InputPull::PullUp), None),
         PinFunction::Output(None),
-        // TODO ALT0::GPCLK0,
-        // TODO ALT1: SMI SA1 / SA1
-        // TODO ALT2: DPI_D0
-        // TODO ALT3: SPI4_CE0_N / AVEOUT_VID0
-        // TODO ALT4: TXD3 / AVEIN_VID0
-        // TODO ALT5: SDA3 / JTAG_TDI
+        PWM,
+        PinFunction::GPCLK0 {
+            frequency: GPCLK_FREQUENCY,
+        },
+        alt(AltMode::Alt1, "SMI_SA1"),
+        alt(AltMode::Alt2, "DPI_D0"),
+        alt(AltMode::Alt3, "SPI4_CE0_N"),
+        alt(AltMode::Alt4, "UART3_TXD"),
+        alt(AltMode::Alt5, "I2C3_SDA"),
     ]),
 };
 
@@ -106,14 +132,15 @@ pub(crate) const PIN_8: PinDescription = PinDescription {
     bcm: Some(14),
     name: Cow::Borrowed("GPIO14"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: UART0_TXD / TXD0
-        // TODO ALT1: SMI SD6 / SD6
-        // TODO ALT2: DSI_D10
-        // TODO ALT3: SPI5_MOSI / AVEOUT_VID10
-        // TODO ALT4: CTS5 / AVEIN_VID10
-        // TODO ALT5: TXD1 / UART1_TXD
+        PWM,
+        PinFunction::UART0_TXD,
+        alt(AltMode::Alt1, "SMI_SD6"),
+        alt(AltMode::Alt2, "DPI_D10"),
+        alt(AltMode::Alt3, "SPI5_MOSI"),
+        alt(AltMode::Alt4, "UART5_CTS"),
+        alt(AltMode::Alt5, "UART1_TXD"),
     ]),
 };
 
@@ -129,14 +156,15 @@ pub(crate) const PIN_10: PinDescription = PinDescription {
     bcm: Some(15),
     name: Cow::Borrowed("GPIO15"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: UART0_RXD / RXD0
-        // TODO ALT1: SMI SD7
-        // TODO ALT2: DPI_D11
-        // TODO ALT3: SPI5_SCLK / AVEOUT VID11
-        // TODO ALT4: RTS5 / AVEIN VID11
-        // TODO ALT5: RXD1 / UART1_RXD
+        PWM,
+        PinFunction::UART0_RXD,
+        alt(AltMode::Alt1, "SMI_SD7"),
+        alt(AltMode::Alt2, "DPI_D11"),
+        alt(AltMode::Alt3, "SPI5_SCLK"),
+        alt(AltMode::Alt4, "UART5_RTS"),
+        alt(AltMode::Alt5, "UART1_RXD"),
     ]),
 };
 
@@ -145,14 +173,14 @@ pub(crate) const PIN_11: PinDescription = PinDescription {
     bcm: Some(17),
     name: Cow::Borrowed("GPIO17"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: <reserved>
-        // TODO ALT1: SMI SD9
-        // TODO ALT2: DPI_D13
-        // TODO ALT3: UART0 RTS / RTS0
-        // TODO ALT4: SPI1_CE1_N
-        // TODO ALT5: UART1 RTS / RTS1
+        PWM,
+        alt(AltMode::Alt1, "SMI_SD9"),
+        alt(AltMode::Alt2, "DPI_D13"),
+        alt(AltMode::Alt3, "UART0_RTS"),
+        PinFunction::SPI1_CE1_N,
+        alt(AltMode::Alt5, "UART1_RTS"),
     ]),
 };
 
@@ -161,14 +189,15 @@ pub(crate) const PIN_12: PinDescription = PinDescription {
     bcm: Some(18),
     name: Cow::Borrowed("GPIO18"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: PCM_CLK
-        // TODO ALT1: SMI SD10
-        // TODO ALT2: DPI_D14
-        // TODO ALT3: SPI6_CE0_N
-        // TODO ALT4: SPI1_CE0_N
-        // TODO ALT5: PWM0_0
+        PWM,
+        alt(AltMode::Alt0, "PCM_CLK"),
+        alt(AltMode::Alt1, "SMI_SD10"),
+        alt(AltMode::Alt2, "DPI_D14"),
+        alt(AltMode::Alt3, "SPI6_CE0_N"),
+        PinFunction::SPI1_CE0_N,
+        alt(AltMode::Alt5, "PWM0_0"),
     ]),
 };
 
@@ -177,14 +206,14 @@ pub(crate) const PIN_13: PinDescription = PinDescription {
     bcm: Some(27),
     name: Cow::Borrowed("GPIO27"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: SD0_DAT3
-        // TODO ALT1: <reserved>
-        // TODO ALT2: DPI_D23
-        // TODO ALT3: SD1_DAT3
-        // TODO ALT4: ARM_TMS / JTA TMS
-        // TODO ALT5:
+        PWM,
+        alt(AltMode::Alt0, "SD0_DAT3"),
+        alt(AltMode::Alt2, "DPI_D23"),
+        alt(AltMode::Alt3, "SD1_DAT3"),
+        alt(AltMode::Alt4, "ARM_TMS"),
+        alt(AltMode::Alt5, "SPI6_CE1_N"),
     ]),
 };
 
@@ -200,14 +229,15 @@ pub(crate) const PIN_15: PinDescription = PinDescription {
     bcm: Some(22),
     name: Cow::Borrowed("GPIO22"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: SD0_CLK
-        // TODO ALT1: SMI SD14 / SD14
-        // TODO ALT2: DPI_D18
-        // TODO ALT3: SD1_CLK
-        // TODO ALT4: ARM_TRST / JTA TRST
-        // TODO ALT5: SDA6
+        PWM,
+        alt(AltMode::Alt0, "SD0_CLK"),
+        alt(AltMode::Alt1, "SMI_SD14"),
+        alt(AltMode::Alt2, "DPI_D18"),
+        alt(AltMode::Alt3, "SD1_CLK"),
+        alt(AltMode::Alt4, "ARM_TRST"),
+        alt(AltMode::Alt5, "I2C6_SDA"),
     ]),
 };
 
@@ -216,14 +246,15 @@ pub(crate) const PIN_16: PinDescription = PinDescription {
     bcm: Some(23),
     name: Cow::Borrowed("GPIO23"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: SD0 CMD
-        // TODO ALT1: SMI SD15 / SD15
-        // TODO ALT2: DPI_D19
-        // TODO ALT3: SD1_CMD
-        // TODO ALT4: ARM_RTCK / JTA RTCK
-        // TODO ALT5: SCL6
+        PWM,
+        alt(AltMode::Alt0, "SD0_CMD"),
+        alt(AltMode::Alt1, "SMI_SD15"),
+        alt(AltMode::Alt2, "DPI_D19"),
+        alt(AltMode::Alt3, "SD1_CMD"),
+        alt(AltMode::Alt4, "ARM_RTCK"),
+        alt(AltMode::Alt5, "I2C6_SCL"),
     ]),
 };
 
@@ -239,14 +270,15 @@ pub(crate) const PIN_18: PinDescription = PinDescription {
     bcm: Some(24),
     name: Cow::Borrowed("GPIO24"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: SD0_DAT0
-        // TODO ALT1: SMI SD16 / SD16
-        // TODO ALT2: DPI_D20
-        // TODO ALT3: SD1_DAT0
-        // TODO ALT4: ARM_TDO / JTA TDO
-        // TODO ALT5: SPI3_CE1_N
+        PWM,
+        alt(AltMode::Alt0, "SD0_DAT0"),
+        alt(AltMode::Alt1, "SMI_SD16"),
+        alt(AltMode::Alt2, "DPI_D20"),
+        alt(AltMode::Alt3, "SD1_DAT0"),
+        alt(AltMode::Alt4, "ARM_TDO"),
+        alt(AltMode::Alt5, "SPI3_CE1_N"),
     ]),
 };
 
@@ -256,14 +288,15 @@ pub(crate) const PIN_19: PinDescription = PinDescription {
     bcm: Some(10),
     name: Cow::Borrowed("GPIO10"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: SPI0_MOSI
-        // TODO ALT1: SMI SD2
-        // TODO ALT2: DPI_D6
-        // TODO ALT3: BSCSL SDA / MOSI / AVEOUT VID6
-        // TODO ALT4: CTS4 / AVEIN VID6
-        // TODO ALT5: SDA5
+        PWM,
+        PinFunction::SPI0_MOSI,
+        alt(AltMode::Alt1, "SMI_SD2"),
+        alt(AltMode::Alt2, "DPI_D6"),
+        alt(AltMode::Alt3, "BSCSL_SDA"),
+        alt(AltMode::Alt4, "UART4_CTS"),
+        alt(AltMode::Alt5, "I2C5_SDA"),
     ]),
 };
 
@@ -280,14 +313,15 @@ pub(crate) const PIN_21: PinDescription = PinDescription {
     bcm: Some(9),
     name: Cow::Borrowed("GPIO9"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0:SPI0_MISO
-        // TODO ALT1: SMI SD1
-        // TODO ALT2: DPI_D5
-        // TODO ALT3: BSCSL / MISO / AVEOUT VID5
-        // TODO ALT4: RXD4 / AVEIN VID5
-        // TODO ALT5: SCL4
+        PWM,
+        PinFunction::SPI0_MISO,
+        alt(AltMode::Alt1, "SMI_SD1"),
+        alt(AltMode::Alt2, "DPI_D5"),
+        alt(AltMode::Alt3, "BSCSL_MISO"),
+        alt(AltMode::Alt4, "UART4_RXD"),
+        alt(AltMode::Alt5, "I2C4_SCL"),
     ]),
 };
 
@@ -296,14 +330,15 @@ pub(crate) const PIN_22: PinDescription = PinDescription {
     bcm: Some(25),
     name: Cow::Borrowed("GPIO25"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: SD0_DAT1
-        // TODO ALT1: SMI_SD17 / SD17
-        // TODO ALT2: DPI_D21
-        // TODO ALT3: SD1_DAT1
-        // TODO ALT4: ARM_TCK / JTAG TCK
-        // TODO ALT5: SPI4_CE1_N
+        PWM,
+        alt(AltMode::Alt0, "SD0_DAT1"),
+        alt(AltMode::Alt1, "SMI_SD17"),
+        alt(AltMode::Alt2, "DPI_D21"),
+        alt(AltMode::Alt3, "SD1_DAT1"),
+        alt(AltMode::Alt4, "ARM_TCK"),
+        alt(AltMode::Alt5, "SPI4_CE1_N"),
     ]),
 };
 
@@ -313,14 +348,15 @@ pub(crate) const PIN_23: PinDescription = PinDescription {
     bcm: Some(11),
     name: Cow::Borrowed("GPIO11"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: SPI0_SCLK
-        // TODO ALT1: SMI SD3
-        // TODO ALT2: DPI_D7
-        // TODO ALT3: BSCSL SCL / SCLK / AVEOUT VID7
-        // TODO ALT4: RTS4 / AVEIN VID7
-        // TODO ALT5: SCL5
+        PWM,
+        PinFunction::SPI0_SCLK,
+        alt(AltMode::Alt1, "SMI_SD3"),
+        alt(AltMode::Alt2, "DPI_D7"),
+        alt(AltMode::Alt3, "BSCSL_SCL"),
+        alt(AltMode::Alt4, "UART4_RTS"),
+        alt(AltMode::Alt5, "I2C5_SCL"),
     ]),
 };
 
@@ -330,14 +366,15 @@ pub(crate) const PIN_24: PinDescription = PinDescription {
     bcm: Some(8),
     name: Cow::Borrowed("GPIO8"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullUp)),
+        PinFunction::Input(Some(InputPull::PullUp), None),
         PinFunction::Output(None),
-        // TODO ALT0: SPI0_CE0_N
-        // TODO ALT1: SMI SD0 / SD0
-        // TODO ALT2: DPI_D4
-        // TODO ALT3: BSCSL / CE_N / AVEOUT VID4
-        // TODO ALT4: TXD4 / AVEIN VID4
-        // TODO ALT5: SDA4
+        PWM,
+        PinFunction::SPI0_CE0_N,
+        alt(AltMode::Alt1, "SMI_SD0"),
+        alt(AltMode::Alt2, "DPI_D4"),
+        alt(AltMode::Alt3, "BSCSL_CE_N"),
+        alt(AltMode::Alt4, "UART4_TXD"),
+        alt(AltMode::Alt5, "I2C4_SDA"),
     ]),
 };
 
@@ -354,14 +391,15 @@ pub(crate) const PIN_26: PinDescription = PinDescription {
     bcm: Some(7),
     name: Cow::Borrowed("GPIO7"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullUp)),
+        PinFunction::Input(Some(InputPull::PullUp), None),
         PinFunction::Output(None),
-        // TODO ALT0: SPI0_CE1_N
-        // TODO ALT1: SMI SWE_N / SRW_N
-        // TODO ALT2: DPI_D3
-        // TODO ALT3: SPI4_SCLK / AVEOUT VID3
-        // TODO ALT4: RTS3 / AVEIN VID3
-        // TODO ALT5: SCL4
+        PWM,
+        PinFunction::SPI0_CE1_N,
+        alt(AltMode::Alt1, "SMI_SWE_N"),
+        alt(AltMode::Alt2, "DPI_D3"),
+        alt(AltMode::Alt3, "SPI4_SCLK"),
+        alt(AltMode::Alt4, "UART3_RTS"),
+        alt(AltMode::Alt5, "I2C4_SCL"),
     ]),
 };
 
@@ -370,12 +408,7 @@ pub(crate) const PIN_27: PinDescription = PinDescription {
     bcm: None,
     name: Cow::Borrowed("GPIO0"), // EEPROM ID_SD for HAT identification
     options: Cow::Borrowed(&[
-        // TODO ALT0: I2C0 SDA / SDA0
-        // TODO ALT1: SMI SA5
-        // TODO ALT2: DPI CLK / PCLK
-        // TODO ALT3: SPI3_CE0_N / AVEOUT VCLK
-        // TODO ALT4: TXD2 / AVEIN VCLK
-        // TODO ALT5: SDA6
+        // Alternate functions are not offered, as the pin is reserved for the HAT ID EEPROM
     ]),
 };
 
@@ -384,12 +417,7 @@ pub(crate) const PIN_28: PinDescription = PinDescription {
     bcm: None,
     name: Cow::Borrowed("GPIO1"), // EEPROM ID_SCL for HAT identification
     options: Cow::Borrowed(&[
-        // TODO ALT0: I2C0 SDL / SCL0
-        // TODO ALT1: SMI SA4
-        // TODO ALT2: DPI DEN / DE
-        // TODO ALT3: SPI3_MISO / AVEOUT DSYNC?
-        // TODO ALT4: RXD2 / AVEIN DSYNC?
-        // TODO ALT5: SCL6
+        // Alternate functions are not offered, as the pin is reserved for the HAT ID EEPROM
     ]),
 };
 
@@ -398,14 +426,17 @@ pub(crate) const PIN_29: PinDescription = PinDescription {
     bcm: Some(5),
     name: Cow::Borrowed("GPIO5"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullUp)),
+        PinFunction::Input(Some(InputPull::PullUp), None),
         PinFunction::Output(None),
-        // TODO ALT0::GPCLK1
-        // TODO ALT1: SMI_SA0 / SA0
-        // TODO ALT2: DPI_D1
-        // TODO ALT3: SPI4_MISO / AVEOUT VID1
-        // TODO ALT4: RXD3 / AVEIN VID1
-        // TODO ALT5: SCL3 / JTA TDO
+        PWM,
+        PinFunction::GPCLK1 {
+            frequency: GPCLK_FREQUENCY,
+        },
+        alt(AltMode::Alt1, "SMI_SA0"),
+        alt(AltMode::Alt2, "DPI_D1"),
+        alt(AltMode::Alt3, "SPI4_MISO"),
+        alt(AltMode::Alt4, "UART3_RXD"),
+        alt(AltMode::Alt5, "I2C3_SCL"),
     ]),
 };
 
@@ -421,14 +452,17 @@ pub(crate) const PIN_31: PinDescription = PinDescription {
     bcm: Some(6),
     name: Cow::Borrowed("GPIO6"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullUp)),
+        PinFunction::Input(Some(InputPull::PullUp), None),
         PinFunction::Output(None),
-        // TODO ALT0::GPCLK2
-        // TODO ALT1: SMI SOE_N / SOE_N / SE
-        // TODO ALT2: DPI_D2
-        // TODO ALT3: SPI4_MOSI / AVEOUT VID2
-        // TODO ALT4: CTS3 / AVEIN VID2
-        // TODO ALT5: SDA4 / JTA RTCK
+        PWM,
+        PinFunction::GPCLK2 {
+            frequency: GPCLK_FREQUENCY,
+        },
+        alt(AltMode::Alt1, "SMI_SOE_N"),
+        alt(AltMode::Alt2, "DPI_D2"),
+        alt(AltMode::Alt3, "SPI4_MOSI"),
+        alt(AltMode::Alt4, "UART3_CTS"),
+        alt(AltMode::Alt5, "I2C4_SDA"),
     ]),
 };
 
@@ -437,14 +471,15 @@ pub(crate) const PIN_32: PinDescription = PinDescription {
     bcm: Some(12),
     name: Cow::Borrowed("GPIO12"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: PWM0 / PWM0_0
-        // TODO ALT1: SMI SD4
-        // TODO ALT2: DPI_D8
-        // TODO ALT3: SPI5_CE0_N / AVEOUT VID8
-        // TODO ALT4: TXD5 / AVEIN VID8
-        // TODO ALT5: SDA5 / JTA TMS
+        PWM,
+        alt(AltMode::Alt0, "PWM0_0"),
+        alt(AltMode::Alt1, "SMI_SD4"),
+        alt(AltMode::Alt2, "DPI_D8"),
+        alt(AltMode::Alt3, "SPI5_CE0_N"),
+        alt(AltMode::Alt4, "UART5_TXD"),
+        alt(AltMode::Alt5, "I2C5_SDA"),
     ]),
 };
 
@@ -453,14 +488,15 @@ pub(crate) const PIN_33: PinDescription = PinDescription {
     bcm: Some(13),
     name: Cow::Borrowed("GPIO13"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: PWM1 / PWM0_1
-        // TODO ALT1: SMI SD5
-        // TODO ALT2: DPI_D9
-        // TODO ALT3: SPI5_MISO / AVEOUT VID9
-        // TODO ALT4: RXD5 / AVEIN VID9
-        // TODO ALT5: SCL5 / JTA TCK
+        PWM,
+        alt(AltMode::Alt0, "PWM0_1"),
+        alt(AltMode::Alt1, "SMI_SD5"),
+        alt(AltMode::Alt2, "DPI_D9"),
+        alt(AltMode::Alt3, "SPI5_MISO"),
+        alt(AltMode::Alt4, "UART5_RXD"),
+        alt(AltMode::Alt5, "I2C5_SCL"),
     ]),
 };
 
@@ -476,14 +512,15 @@ pub(crate) const PIN_35: PinDescription = PinDescription {
     bcm: Some(19),
     name: Cow::Borrowed("GPIO19"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: PCM_FS
-        // TODO ALT1: SMI SD11
-        // TODO ALT2: DPI_D15
-        // TODO ALT3: SPI6_MISO
-        // TODO ALT4: SPI1_MISO
-        // TODO ALT5: PWM0_1
+        PWM,
+        alt(AltMode::Alt0, "PCM_FS"),
+        alt(AltMode::Alt1, "SMI_SD11"),
+        alt(AltMode::Alt2, "DPI_D15"),
+        alt(AltMode::Alt3, "SPI6_MISO"),
+        PinFunction::SPI1_MISO,
+        alt(AltMode::Alt5, "PWM0_1"),
     ]),
 };
 
@@ -492,14 +529,14 @@ pub(crate) const PIN_36: PinDescription = PinDescription {
     bcm: Some(16),
     name: Cow::Borrowed("GPIO16"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: <reserved>
-        // TODO ALT1: SMI SD8
-        // TODO ALT2: DPI_D12
-        // TODO ALT3: UART0 CTS / CTS0
-        // TODO ALT4: SPI1_CE2_N
-        // TODO ALT5: UART1 CTS
+        PWM,
+        alt(AltMode::Alt1, "SMI_SD8"),
+        alt(AltMode::Alt2, "DPI_D12"),
+        alt(AltMode::Alt3, "UART0_CTS"),
+        PinFunction::SPI1_CE2_N,
+        alt(AltMode::Alt5, "UART1_CTS"),
     ]),
 };
 
@@ -508,14 +545,14 @@ pub(crate) const PIN_37: PinDescription = PinDescription {
     bcm: Some(26),
     name: Cow::Borrowed("GPIO26"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: SD0_DAT2
-        // TODO ALT1: <reserved>>
-        // TODO ALT2: DPI_D22
-        // TODO ALT3: SD1_DAT2
-        // TODO ALT4: ARM_TDI / JTA TDI
-        // TODO ALT5: SPI5_CE1_N
+        PWM,
+        alt(AltMode::Alt0, "SD0_DAT2"),
+        alt(AltMode::Alt2, "DPI_D22"),
+        alt(AltMode::Alt3, "SD1_DAT2"),
+        alt(AltMode::Alt4, "ARM_TDI"),
+        alt(AltMode::Alt5, "SPI5_CE1_N"),
     ]),
 };
 
@@ -524,14 +561,17 @@ pub(crate) const PIN_38: PinDescription = PinDescription {
     bcm: Some(20),
     name: Cow::Borrowed("GPIO20"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: PCM_DIN
-        // TODO ALT1: SMI SD12
-        // TODO ALT2: DPI_D16
-        // TODO ALT3: SPI6_MOSI
-        // TODO ALT4: SPI1_MOSI
-        // TODO ALT5: GPCLK0
+        PWM,
+        alt(AltMode::Alt0, "PCM_DIN"),
+        alt(AltMode::Alt1, "SMI_SD12"),
+        alt(AltMode::Alt2, "DPI_D16"),
+        alt(AltMode::Alt3, "SPI6_MOSI"),
+        PinFunction::SPI1_MOSI,
+        PinFunction::GPCLK0 {
+            frequency: GPCLK_FREQUENCY,
+        },
     ]),
 };
 
@@ -547,13 +587,24 @@ pub(crate) const PIN_40: PinDescription = PinDescription {
     bcm: Some(21),
     name: Cow::Borrowed("GPIO21"),
     options: Cow::Borrowed(&[
-        PinFunction::Input(Some(InputPull::PullDown)),
+        PinFunction::Input(Some(InputPull::PullDown), None),
         PinFunction::Output(None),
-        // TODO ALT0: PCM_DOUT
-        // TODO ALT1: SMI SD13
-        // TODO ALT2: DPI_D17
-        // TODO ALT3: SPI6_SCLK
-        // TODO ALT4: SPI1_SCLK
-        // TODO ALT5: GPCLK1
+        PWM,
+        alt(AltMode::Alt0, "PCM_DOUT"),
+        alt(AltMode::Alt1, "SMI_SD13"),
+        alt(AltMode::Alt2, "DPI_D17"),
+        alt(AltMode::Alt3, "SPI6_SCLK"),
+        PinFunction::SPI1_SCLK,
+        PinFunction::GPCLK1 {
+            frequency: GPCLK_FREQUENCY,
+        },
     ]),
 };
+
+/// The pins of the 40-pin GPIO header, in order of board pin number
+pub(crate) const HEADER_40_PINS: &[PinDescription] = &[
+    PIN_1, PIN_2, PIN_3, PIN_4, PIN_5, PIN_6, PIN_7, PIN_8, PIN_9, PIN_10, PIN_11, PIN_12, PIN_13,
+    PIN_14, PIN_15, PIN_16, PIN_17, PIN_18, PIN_19, PIN_20, PIN_21, PIN_22, PIN_23, PIN_24, PIN_25,
+    PIN_26, PIN_27, PIN_28, PIN_29, PIN_30, PIN_31, PIN_32, PIN_33, PIN_34, PIN_35, PIN_36, PIN_37,
+    PIN_38, PIN_39, PIN_40,
+];
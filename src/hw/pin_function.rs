@@ -1,5 +1,6 @@
-use crate::hw::{InputPull, PinLevel};
+use crate::hw::{InputFilter, InputPull, PinLevel};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 
@@ -19,7 +20,8 @@ use std::fmt::{Display, Formatter};
 /// * SCLK - serial clock
 /// * CE   - chip enable (often called chip select)
 /// * MOMI - master out master in
-#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 #[allow(non_camel_case_types)]
 #[allow(clippy::upper_case_acronyms)]
 pub enum PinFunction {
@@ -35,55 +37,81 @@ pub enum PinFunction {
     Ground,
 
     /// GPIO functions
-    Input(Option<InputPull>),
+    /// An Input has an optional pull-up or pull-down, and an optional [InputFilter] on the
+    /// changes of level that are reported. Without a filter it is saved as in earlier versions
+    #[serde(with = "input_settings")]
+    Input(Option<InputPull>, Option<InputFilter>),
     Output(Option<PinLevel>),
-    /*
-    /// General Purpose Clock functions (from https://pinout.xyz/pinout/gpclk)
-    GPCLK0,
-    GPCLK1,
-    GPCLK2,
 
-    /// I2C bus functions
+    /// Pulse Width Modulated output, generated in hardware on the pins that are connected to
+    /// a PWM channel (GPIO12, GPIO13, GPIO18, GPIO19) and in software on other pins
+    /// * `frequency` - in Hz
+    /// * `duty_cycle` - fraction (0.0 - 1.0) of each period that the output is high
+    Pwm {
+        frequency: f64,
+        duty_cycle: f64,
+    },
+
+    /// I2C bus #1 functions
     I2C1_SDA,
     I2C1_SCL,
-    I2C3_SDA,
-    I2C3_SCL,
-    I2C4_SDA,
-    I2C4_SCL,
-    I2C5_SDA,
-    I2C5_SCL,
-    I2C6_SDA,
-    I2C6_SCL,
 
-    /// SPI Interface #0
+    /// SPI Interface #0 - standard mode
     SPI0_MOSI,
-    /// Bi-directional mode
-    SPI0_MOMI,
     SPI0_MISO,
     SPI0_SCLK,
     SPI0_CE0_N,
     SPI0_CE1_N,
 
-    // SPI Interface #0
+    /// SPI Interface #1 - standard mode
     SPI1_MOSI,
-    /// Bi-directional mode
-    SPI1_MOMI,
     SPI1_MISO,
     SPI1_SCLK,
     SPI1_CE0_N,
     SPI1_CE1_N,
     SPI1_CE2_N,
 
-    /// PWM functions - two pins each use these
-    PWM0,
-    PWM1,
-
     /// UART functions
     /// UART0 - Transmit
     UART0_TXD,
     /// UART0 - Receive
     UART0_RXD,
 
+    /// General Purpose Clock functions (from https://pinout.xyz/pinout/gpclk), outputting a
+    /// clock of `frequency` Hz
+    GPCLK0 {
+        frequency: f64,
+    },
+    GPCLK1 {
+        frequency: f64,
+    },
+    GPCLK2 {
+        frequency: f64,
+    },
+
+    /// One of the alternate functions of a pin that has no specific support, selected by
+    /// putting the pin into `mode`. `name` is the name of the signal in the SoC datasheet
+    Alt {
+        mode: AltMode,
+        name: Cow<'static, str>,
+    },
+    /*
+    /// I2C bus functions
+    I2C3_SDA,
+    I2C3_SCL,
+    I2C4_SDA,
+    I2C4_SCL,
+    I2C5_SDA,
+    I2C5_SCL,
+    I2C6_SDA,
+    I2C6_SCL,
+
+    /// SPI Interface #0 - Bi-directional mode
+    SPI0_MOMI,
+
+    /// SPI Interface #1 - Bi-directional mode
+    SPI1_MOMI,
+
     /// PCM functions - how uncompressed digital audio is encoded
     PCM_FS,
     /// PCM Data In
@@ -95,18 +123,111 @@ pub enum PinFunction {
      */
 }
 
+/// [AltMode] is one of the six alternate function modes (ALT0 - ALT5) that the GPIO block of
+/// the SoC can select for a pin, each connecting it to a different peripheral
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum AltMode {
+    Alt0,
+    Alt1,
+    Alt2,
+    Alt3,
+    Alt4,
+    Alt5,
+}
+
+impl Display for AltMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format!("{:?}", self).to_uppercase())
+    }
+}
+
+impl PinFunction {
+    /// If this is a General Purpose Clock function, return the number of the clock and the
+    /// frequency it outputs
+    #[allow(dead_code)] // for piglet with the cdev backend, which has no clocks
+    pub fn gpclk(&self) -> Option<(u8, f64)> {
+        match self {
+            PinFunction::GPCLK0 { frequency } => Some((0, *frequency)),
+            PinFunction::GPCLK1 { frequency } => Some((1, *frequency)),
+            PinFunction::GPCLK2 { frequency } => Some((2, *frequency)),
+            _ => None,
+        }
+    }
+
+    /// Create the General Purpose Clock function for `clock` (0-2) with `frequency`
+    #[allow(dead_code)] // for piglet
+    pub fn new_gpclk(clock: u8, frequency: f64) -> Option<PinFunction> {
+        match clock {
+            0 => Some(PinFunction::GPCLK0 { frequency }),
+            1 => Some(PinFunction::GPCLK1 { frequency }),
+            2 => Some(PinFunction::GPCLK2 { frequency }),
+            _ => None,
+        }
+    }
+}
+
+/// Serialization of the settings of an Input: just the pull when there is no filter, so that
+/// config files are unchanged unless a filter is used and files saved earlier can be loaded
+mod input_settings {
+    use crate::hw::{InputFilter, InputPull};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(untagged)]
+    enum InputSettings {
+        Pull(Option<InputPull>),
+        Filtered(FilteredInput),
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct FilteredInput {
+        pull: Option<InputPull>,
+        filter: InputFilter,
+    }
+
+    pub fn serialize<S: Serializer>(
+        pull: &Option<InputPull>,
+        filter: &Option<InputFilter>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match filter {
+            None => InputSettings::Pull(*pull),
+            Some(filter) => InputSettings::Filtered(FilteredInput {
+                pull: *pull,
+                filter: *filter,
+            }),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<(Option<InputPull>, Option<InputFilter>), D::Error> {
+        Ok(match InputSettings::deserialize(deserializer)? {
+            InputSettings::Pull(pull) => (pull, None),
+            InputSettings::Filtered(FilteredInput { pull, filter }) => (pull, Some(filter)),
+        })
+    }
+}
+
 impl Display for PinFunction {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        // Remove anything after the first opening bracket of debug representation
+        if let PinFunction::Alt { mode, name } = self {
+            return write!(f, "{mode} {name}");
+        }
+
+        // Remove anything after the first opening bracket or space of debug representation
         let full = format!("{:?}", self);
-        write!(f, "{}", full.split_once('(').unwrap_or((&full, "")).0)
+        write!(f, "{}", full.split(['(', ' ']).next().unwrap_or(&full))
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::hw::pin_function::PinFunction;
+    use crate::hw::pin_function::{AltMode, PinFunction};
     use crate::hw::InputPull::{PullDown, PullUp};
+    use std::borrow::Cow;
 
     #[test]
     fn display_pin_function() {
@@ -117,17 +238,39 @@ mod test {
             PinFunction::Output(None),
             PinFunction::Output(Some(true)),
             PinFunction::Output(Some(false)),
-            PinFunction::Input(None),
-            PinFunction::Input(Some(PullUp)),
-            PinFunction::Input(Some(PullDown)),
+            PinFunction::Input(None, None),
+            PinFunction::Input(Some(PullUp), None),
+            PinFunction::Input(Some(PullDown), None),
             PinFunction::I2C_EEPROM_ID_SC,
             PinFunction::I2C_EEPROM_ID_SD,
             PinFunction::Power3V3,
             PinFunction::Power5V,
+            PinFunction::Pwm {
+                frequency: 1000.0,
+                duty_cycle: 0.5,
+            },
         ];
 
         for function in functions {
             println!("{}", function);
         }
     }
+
+    #[test]
+    fn display_pwm_without_settings() {
+        let pwm = PinFunction::Pwm {
+            frequency: 50.0,
+            duty_cycle: 0.25,
+        };
+        assert_eq!(pwm.to_string(), "Pwm");
+    }
+
+    #[test]
+    fn display_alt_function() {
+        let alt = PinFunction::Alt {
+            mode: AltMode::Alt3,
+            name: Cow::Borrowed("SPI3_MOSI"),
+        };
+        assert_eq!(alt.to_string(), "ALT3 SPI3_MOSI");
+    }
 }
@@ -0,0 +1,289 @@
+//! The GPIO pins that each model of Raspberry Pi has, built from the pin descriptions of the
+//! 40-pin header. Older models have a 26-pin header, Compute Modules have no header but more
+//! GPIOs, and the Pi 5 has its GPIOs connected to the RP1 I/O controller instead of the SoC
+
+use crate::hw::pin_description::{PinDescription, PinDescriptionSet};
+use crate::hw::pin_descriptions::*;
+use crate::hw::pin_function::AltMode;
+use crate::hw::{BCMPinNumber, InputPull, PinFunction};
+use std::borrow::Cow;
+
+/// On the revision 1 Model B, pin 3 of the 26-pin header is GPIO0, for I2C bus 0
+const REV1_PIN_3: PinDescription = PinDescription {
+    bpn: 3,
+    bcm: Some(0),
+    name: Cow::Borrowed("GPIO0"),
+    options: Cow::Borrowed(&[
+        PinFunction::Input(Some(InputPull::PullUp), None),
+        PinFunction::Output(None),
+        PWM,
+        alt(AltMode::Alt0, "I2C0_SDA"),
+    ]),
+};
+
+/// On the revision 1 Model B, pin 5 of the 26-pin header is GPIO1, for I2C bus 0
+const REV1_PIN_5: PinDescription = PinDescription {
+    bpn: 5,
+    bcm: Some(1),
+    name: Cow::Borrowed("GPIO1"),
+    options: Cow::Borrowed(&[
+        PinFunction::Input(Some(InputPull::PullUp), None),
+        PinFunction::Output(None),
+        PWM,
+        alt(AltMode::Alt0, "I2C0_SCL"),
+    ]),
+};
+
+/// On the revision 1 Model B, pin 13 of the 26-pin header is GPIO21
+const REV1_PIN_13: PinDescription = PinDescription {
+    bpn: 13,
+    bcm: Some(21),
+    name: Cow::Borrowed("GPIO21"),
+    options: Cow::Borrowed(&[
+        PinFunction::Input(Some(InputPull::PullDown), None),
+        PinFunction::Output(None),
+        PWM,
+        alt(AltMode::Alt0, "PCM_DOUT"),
+    ]),
+};
+
+/// The pins of the 26-pin GPIO header of the revision 1 Model B
+const REV1_HEADER_PINS: &[PinDescription] = &[
+    PIN_1,
+    PIN_2,
+    REV1_PIN_3,
+    PIN_4,
+    REV1_PIN_5,
+    PIN_6,
+    PIN_7,
+    PIN_8,
+    PIN_9,
+    PIN_10,
+    PIN_11,
+    PIN_12,
+    REV1_PIN_13,
+    PIN_14,
+    PIN_15,
+    PIN_16,
+    PIN_17,
+    PIN_18,
+    PIN_19,
+    PIN_20,
+    PIN_21,
+    PIN_22,
+    PIN_23,
+    PIN_24,
+    PIN_25,
+    PIN_26,
+];
+
+/// The functions of the Compute Module GPIOs above GPIO27, that are not on any header
+const COMPUTE_MODULE_OPTIONS: &[PinFunction] = &[
+    PinFunction::Input(None, None),
+    PinFunction::Output(None),
+    PWM,
+];
+
+/// The highest GPIO of the BCM2835/6/7 and BCM2711 available on a Compute Module
+const COMPUTE_MODULE_MAX_GPIO: BCMPinNumber = 45;
+
+/// Names of the buses that were added by the BCM2711 of the Pi 4, 400 and Compute Module 4,
+/// which the BCM2835/6/7 of earlier models do not have
+const BCM2711_BUSES: &[&str] = &[
+    "SPI3", "SPI4", "SPI5", "SPI6", "I2C3", "I2C4", "I2C5", "I2C6", "UART2", "UART3", "UART4",
+    "UART5",
+];
+
+/// The set of GPIO pins a Raspberry Pi model has and the functions they have, which depend on
+/// the header the board has and the SoC or I/O controller that the pins are connected to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PinHeader {
+    /// The 26-pin header of the revision 1 Model B, with GPIO0, GPIO1 and GPIO21
+    Rev1,
+    /// The 26-pin header of the Model A and revision 2 Model B: the first 26 pins of the
+    /// 40-pin header
+    Rev2,
+    /// The 40-pin header connected to a BCM2835/6/7: B+, A+, 2B, 3B, 3B+, 3A+ and the Zeros
+    Bcm283x,
+    /// The 40-pin header connected to a BCM2711, with its extra SPI, I2C and UART buses: 4B, 400
+    /// and the Compute Module 4 IO board
+    #[default]
+    Bcm2711,
+    /// The 40-pin header of the Pi 5, connected to the RP1 I/O controller. The alternate
+    /// functions and clocks of the RP1 are not those of the Broadcom SoCs, so are not offered
+    Rp1,
+    /// The GPIO0 - GPIO45 of a Compute Module 1, 3 or 3+, on its SODIMM connector
+    ComputeModule,
+    /// The GPIO0 - GPIO45 of a Compute Module 4S, on its SODIMM connector, with the extra buses
+    /// of its BCM2711
+    ComputeModule4S,
+}
+
+impl PinHeader {
+    /// The [PinDescriptionSet] of the pins of this header
+    pub fn pin_descriptions(&self) -> PinDescriptionSet {
+        match self {
+            PinHeader::Rev1 => PinDescriptionSet::from(without_bcm2711_buses(REV1_HEADER_PINS)),
+            PinHeader::Rev2 => {
+                PinDescriptionSet::from(without_bcm2711_buses(&HEADER_40_PINS[..26]))
+            }
+            PinHeader::Bcm283x => PinDescriptionSet::from(without_bcm2711_buses(HEADER_40_PINS)),
+            PinHeader::Bcm2711 => PinDescriptionSet::new(HEADER_40_PINS),
+            PinHeader::Rp1 => PinDescriptionSet::from(without_options(HEADER_40_PINS, |f| {
+                matches!(f, PinFunction::Alt { .. }) || f.gpclk().is_some()
+            })),
+            PinHeader::ComputeModule => {
+                PinDescriptionSet::from(without_bcm2711_buses(&compute_module_pins()))
+            }
+            PinHeader::ComputeModule4S => PinDescriptionSet::from(compute_module_pins()),
+        }
+    }
+}
+
+/// Copy `pins`, removing the alternate functions of the buses only the BCM2711 has, for the
+/// models with an earlier SoC
+fn without_bcm2711_buses(pins: &[PinDescription]) -> Vec<PinDescription> {
+    without_options(pins, |f| {
+        matches!(f, PinFunction::Alt { name, .. }
+            if BCM2711_BUSES.iter().any(|bus| name.starts_with(bus)))
+    })
+}
+
+/// Copy `pins`, removing the options that `remove` returns true for
+fn without_options(
+    pins: &[PinDescription],
+    remove: impl Fn(&PinFunction) -> bool,
+) -> Vec<PinDescription> {
+    pins.iter()
+        .map(|pin| PinDescription {
+            options: pin
+                .options
+                .iter()
+                .filter(|option| !remove(option))
+                .cloned()
+                .collect::<Vec<PinFunction>>()
+                .into(),
+            ..pin.clone()
+        })
+        .collect()
+}
+
+/// The GPIOs of a Compute Module, in order of [BCMPinNumber]. With no header, the board pin
+/// numbers are just one more than the [BCMPinNumber]. GPIO0 - GPIO27 have the functions they
+/// have on the 40-pin header, the others can only be inputs or outputs
+fn compute_module_pins() -> Vec<PinDescription> {
+    (0..=COMPUTE_MODULE_MAX_GPIO)
+        .map(|bcm| {
+            let options = HEADER_40_PINS
+                .iter()
+                .find(|pin| pin.bcm == Some(bcm))
+                .map(|pin| pin.options.clone())
+                .unwrap_or(Cow::Borrowed(COMPUTE_MODULE_OPTIONS));
+            PinDescription {
+                bpn: bcm + 1,
+                bcm: Some(bcm),
+                name: Cow::Owned(format!("GPIO{bcm}")),
+                options,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::pin_function::{AltMode, PinFunction};
+    use crate::hw::pin_headers::PinHeader;
+    use std::borrow::Cow;
+
+    #[test]
+    fn header_pin_counts() {
+        assert_eq!(PinHeader::Rev1.pin_descriptions().pins().len(), 26);
+        assert_eq!(PinHeader::Rev2.pin_descriptions().pins().len(), 26);
+        assert_eq!(PinHeader::Bcm283x.pin_descriptions().pins().len(), 40);
+        assert_eq!(PinHeader::Bcm2711.pin_descriptions().pins().len(), 40);
+        assert_eq!(PinHeader::Rp1.pin_descriptions().pins().len(), 40);
+        assert_eq!(PinHeader::ComputeModule.pin_descriptions().pins().len(), 46);
+        assert_eq!(
+            PinHeader::ComputeModule4S.pin_descriptions().pins().len(),
+            46
+        );
+    }
+
+    #[test]
+    fn rev1_has_gpio0() {
+        let pins = PinHeader::Rev1.pin_descriptions();
+        assert_eq!(pins.pins()[2].bcm, Some(0));
+        assert_eq!(pins.pins()[12].bcm, Some(21));
+        assert_eq!(PinHeader::Rev2.pin_descriptions().pins()[12].bcm, Some(27));
+    }
+
+    #[test]
+    fn bcm283x_has_no_bcm2711_buses() {
+        let spi3 = PinFunction::Alt {
+            mode: AltMode::Alt3,
+            name: Cow::Borrowed("SPI3_MOSI"),
+        };
+        let bcm2711 = PinHeader::Bcm2711.pin_descriptions();
+        let bcm283x = PinHeader::Bcm283x.pin_descriptions();
+        assert!(bcm2711.pins()[2].options.contains(&spi3));
+        assert!(!bcm283x.pins()[2].options.contains(&spi3));
+        assert!(bcm283x.pins()[2].options.contains(&PinFunction::I2C1_SDA));
+    }
+
+    #[test]
+    fn only_bcm2711_models_have_its_buses() {
+        let has_bcm2711_bus = |header: PinHeader| {
+            header.pin_descriptions().pins().iter().any(|pin| {
+                pin.options.iter().any(|option| {
+                    matches!(option, PinFunction::Alt { name, .. }
+                        if ["SPI3", "I2C6", "UART5"].iter().any(|bus| name.starts_with(bus)))
+                })
+            })
+        };
+        assert!(!has_bcm2711_bus(PinHeader::Rev1));
+        assert!(!has_bcm2711_bus(PinHeader::Rev2));
+        assert!(!has_bcm2711_bus(PinHeader::Bcm283x));
+        assert!(!has_bcm2711_bus(PinHeader::ComputeModule));
+        assert!(has_bcm2711_bus(PinHeader::Bcm2711));
+        assert!(has_bcm2711_bus(PinHeader::ComputeModule4S));
+    }
+
+    #[test]
+    fn rp1_has_no_alt_or_clock() {
+        let rp1 = PinHeader::Rp1.pin_descriptions();
+        assert!(rp1
+            .pins()
+            .iter()
+            .all(|pin| pin
+                .options
+                .iter()
+                .all(
+                    |option| !matches!(option, PinFunction::Alt { .. }) && option.gpclk().is_none()
+                )));
+    }
+
+    #[test]
+    fn compute_module_gpios() {
+        let cm = PinHeader::ComputeModule.pin_descriptions();
+        let gpio45 = cm.get_by_bcm(45).expect("Could not find GPIO45");
+        assert_eq!(gpio45.name, "GPIO45");
+        assert_eq!(gpio45.bpn, 46);
+        assert_eq!(
+            cm.get_by_bcm(2).map(|pin| pin.options.len()),
+            PinHeader::Bcm283x
+                .pin_descriptions()
+                .get_by_bcm(2)
+                .map(|pin| pin.options.len())
+        );
+        assert_eq!(
+            PinHeader::ComputeModule4S
+                .pin_descriptions()
+                .get_by_bcm(2)
+                .map(|pin| pin.options.len()),
+            PinHeader::Bcm2711
+                .pin_descriptions()
+                .get_by_bcm(2)
+                .map(|pin| pin.options.len())
+        );
+    }
+}
@@ -0,0 +1,251 @@
+/// Decoding of the revision codes that identify each Raspberry Pi board, as documented in
+/// [Raspberry Pi revision codes](https://www.raspberrypi.com/documentation/computers/raspberry-pi.html#raspberry-pi-revision-codes)
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+/// Bit set in new-style revision codes, which are made of the fields below
+const NEW_STYLE_FLAG: u32 = 1 << 23;
+/// Bit set in old-style revision codes when the board has been over-volted
+const OLD_STYLE_WARRANTY_VOIDED: u32 = 1 << 24;
+
+/// Names of the board types in new-style revision codes, indexed by type
+const BOARD_TYPES: &[&str] = &[
+    "A", "B", "A+", "B+", "2B", "Alpha", "CM1", "Unknown", "3B", "Zero", "CM3", "Unknown",
+    "Zero W", "3B+", "3A+", "Internal", "CM3+", "4B", "Zero 2 W", "400", "CM4", "CM4S", "Internal",
+    "5", "CM5", "500", "CM5 Lite",
+];
+
+/// Names of the manufacturers in new-style revision codes, indexed by manufacturer
+const MANUFACTURERS: &[&str] = &[
+    "Sony UK",
+    "Egoman",
+    "Embest",
+    "Sony Japan",
+    "Embest",
+    "Stadium",
+];
+
+/// Names of the SoCs in new-style revision codes, indexed by processor
+const SOCS: &[&str] = &["BCM2835", "BCM2836", "BCM2837", "BCM2711", "BCM2712"];
+
+/// The old-style revision codes of the first boards, all of which have a BCM2835:
+/// (code, board type, PCB revision, memory in MB, manufacturer)
+const OLD_STYLE_CODES: &[(u32, &str, &str, u32, &str)] = &[
+    (0x0002, "B", "1.0", 256, "Egoman"),
+    (0x0003, "B", "1.0", 256, "Egoman"),
+    (0x0004, "B", "2.0", 256, "Sony UK"),
+    (0x0005, "B", "2.0", 256, "Qisda"),
+    (0x0006, "B", "2.0", 256, "Egoman"),
+    (0x0007, "A", "2.0", 256, "Egoman"),
+    (0x0008, "A", "2.0", 256, "Sony UK"),
+    (0x0009, "A", "2.0", 256, "Qisda"),
+    (0x000d, "B", "2.0", 512, "Egoman"),
+    (0x000e, "B", "2.0", 512, "Sony UK"),
+    (0x000f, "B", "2.0", 512, "Egoman"),
+    (0x0010, "B+", "1.2", 512, "Sony UK"),
+    (0x0011, "CM1", "1.0", 512, "Sony UK"),
+    (0x0012, "A+", "1.1", 256, "Sony UK"),
+    (0x0013, "B+", "1.2", 512, "Embest"),
+    (0x0014, "CM1", "1.0", 512, "Embest"),
+    (0x0015, "A+", "1.1", 256, "Embest"),
+];
+
+/// [BoardRevision] has the fields decoded from the revision code of a Raspberry Pi
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BoardRevision {
+    /// The revision code this was decoded from
+    pub code: u32,
+    /// The type of board, such as "4B" or "Zero 2 W"
+    pub board_type: String,
+    /// Size of the RAM in MB
+    pub memory_mb: u32,
+    pub manufacturer: String,
+    pub soc: String,
+    /// Revision of the printed circuit board, such as "1.4"
+    pub pcb_revision: String,
+    /// Could the board be over-volted. Old-style codes do not say, so it is assumed it can be
+    pub overvoltage_allowed: bool,
+    /// Could the One Time Programmable memory be programmed, or read
+    pub otp_program_allowed: bool,
+    pub otp_read_allowed: bool,
+    /// Has the warranty been voided, by over-volting the board
+    pub warranty_voided: bool,
+}
+
+impl BoardRevision {
+    /// Decode a revision code, as found in "/proc/cpuinfo" in hexadecimal
+    pub fn decode(code: u32) -> io::Result<BoardRevision> {
+        if code & NEW_STYLE_FLAG != 0 {
+            Self::decode_new_style(code)
+        } else {
+            Self::decode_old_style(code)
+        }
+    }
+
+    fn decode_new_style(code: u32) -> io::Result<BoardRevision> {
+        let field = |shift: u32, bits: u32| ((code >> shift) & ((1 << bits) - 1)) as usize;
+        let name = |names: &[&str], index: usize, what: &str| {
+            names
+                .get(index)
+                .map(|name| name.to_string())
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Unknown {what} {index} in revision code {code:x}"),
+                    )
+                })
+        };
+
+        Ok(BoardRevision {
+            code,
+            board_type: name(BOARD_TYPES, field(4, 8), "board type")?,
+            memory_mb: 256 << field(20, 3),
+            manufacturer: name(MANUFACTURERS, field(16, 4), "manufacturer")?,
+            soc: name(SOCS, field(12, 4), "processor")?,
+            pcb_revision: format!("1.{}", field(0, 4)),
+            overvoltage_allowed: field(31, 1) == 0,
+            otp_program_allowed: field(30, 1) == 0,
+            otp_read_allowed: field(29, 1) == 0,
+            warranty_voided: field(25, 1) == 1,
+        })
+    }
+
+    fn decode_old_style(code: u32) -> io::Result<BoardRevision> {
+        let (_, board_type, pcb_revision, memory_mb, manufacturer) = OLD_STYLE_CODES
+            .iter()
+            .find(|(old_code, ..)| *old_code == code & !OLD_STYLE_WARRANTY_VOIDED)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unknown revision code {code:x}"),
+                )
+            })?;
+
+        Ok(BoardRevision {
+            code,
+            board_type: board_type.to_string(),
+            memory_mb: *memory_mb,
+            manufacturer: manufacturer.to_string(),
+            soc: "BCM2835".to_string(),
+            pcb_revision: pcb_revision.to_string(),
+            overvoltage_allowed: true,
+            otp_program_allowed: true,
+            otp_read_allowed: true,
+            warranty_voided: code & OLD_STYLE_WARRANTY_VOIDED != 0,
+        })
+    }
+
+    /// A Human friendly name of the model of the board
+    pub fn model(&self) -> String {
+        format!("Raspberry Pi {} Rev {}", self.board_type, self.pcb_revision)
+    }
+}
+
+impl Display for BoardRevision {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Board: {}", self.board_type)?;
+        writeln!(f, "PCB Revision: {}", self.pcb_revision)?;
+        writeln!(f, "SoC: {}", self.soc)?;
+        writeln!(f, "Memory: {}", memory(self.memory_mb))?;
+        writeln!(f, "Manufacturer: {}", self.manufacturer)?;
+        writeln!(f, "Overvoltage: {}", allowed(self.overvoltage_allowed))?;
+        writeln!(
+            f,
+            "OTP Programming: {}, Reading: {}",
+            allowed(self.otp_program_allowed),
+            allowed(self.otp_read_allowed)
+        )?;
+        write!(
+            f,
+            "Warranty: {}",
+            if self.warranty_voided {
+                "Voided"
+            } else {
+                "Intact"
+            }
+        )
+    }
+}
+
+/// Describe a memory size in MB in the largest units it is a whole number of
+fn memory(memory_mb: u32) -> String {
+    if memory_mb >= 1024 {
+        format!("{}GB", memory_mb / 1024)
+    } else {
+        format!("{memory_mb}MB")
+    }
+}
+
+fn allowed(allowed: bool) -> &'static str {
+    if allowed {
+        "Allowed"
+    } else {
+        "Disallowed"
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::revision::BoardRevision;
+
+    #[test]
+    fn decode_pi_4() {
+        let revision = BoardRevision::decode(0xc03114).expect("Could not decode revision");
+        assert_eq!(revision.board_type, "4B");
+        assert_eq!(revision.memory_mb, 4096);
+        assert_eq!(revision.manufacturer, "Sony UK");
+        assert_eq!(revision.soc, "BCM2711");
+        assert_eq!(revision.pcb_revision, "1.4");
+        assert!(revision.overvoltage_allowed);
+        assert!(!revision.warranty_voided);
+        assert_eq!(revision.model(), "Raspberry Pi 4B Rev 1.4");
+    }
+
+    #[test]
+    fn decode_zero_2_w() {
+        let revision = BoardRevision::decode(0x902120).expect("Could not decode revision");
+        assert_eq!(revision.board_type, "Zero 2 W");
+        assert_eq!(revision.memory_mb, 512);
+        assert_eq!(revision.soc, "BCM2837");
+        assert_eq!(revision.pcb_revision, "1.0");
+    }
+
+    #[test]
+    fn decode_flags() {
+        // A 3B+ with overvoltage and OTP programming disallowed and the warranty voided
+        let revision = BoardRevision::decode(0xc2a020d3).expect("Could not decode revision");
+        assert_eq!(revision.board_type, "3B+");
+        assert!(!revision.overvoltage_allowed);
+        assert!(!revision.otp_program_allowed);
+        assert!(revision.otp_read_allowed);
+        assert!(revision.warranty_voided);
+    }
+
+    #[test]
+    fn decode_old_style() {
+        let revision = BoardRevision::decode(0x000e).expect("Could not decode revision");
+        assert_eq!(revision.board_type, "B");
+        assert_eq!(revision.pcb_revision, "2.0");
+        assert_eq!(revision.memory_mb, 512);
+        assert_eq!(revision.manufacturer, "Sony UK");
+        assert_eq!(revision.soc, "BCM2835");
+        assert!(!revision.warranty_voided);
+    }
+
+    #[test]
+    fn decode_old_style_warranty_voided() {
+        let revision = BoardRevision::decode(0x1000002).expect("Could not decode revision");
+        assert_eq!(revision.board_type, "B");
+        assert!(revision.warranty_voided);
+    }
+
+    #[test]
+    fn unknown_codes() {
+        assert!(BoardRevision::decode(0x0001).is_err());
+        // New-style with a board type that is not known
+        assert!(BoardRevision::decode(0xa03ff0).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use crate::hw::pin_function::PinFunction;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// [SPIBus] identifies one of the SPI buses available on the GPIO header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum SPIBus {
+    SPI0,
+    SPI1,
+}
+
+impl SPIBus {
+    #[allow(dead_code)] // for piglet
+    pub const ALL: [SPIBus; 2] = [SPIBus::SPI0, SPIBus::SPI1];
+
+    /// The [PinFunction]s of the pins used for data and clock on this bus: MOSI, MISO and SCLK
+    #[allow(dead_code)] // for piglet
+    pub fn data_functions(&self) -> [PinFunction; 3] {
+        match self {
+            SPIBus::SPI0 => [
+                PinFunction::SPI0_MOSI,
+                PinFunction::SPI0_MISO,
+                PinFunction::SPI0_SCLK,
+            ],
+            SPIBus::SPI1 => [
+                PinFunction::SPI1_MOSI,
+                PinFunction::SPI1_MISO,
+                PinFunction::SPI1_SCLK,
+            ],
+        }
+    }
+
+    /// The [PinFunction]s of the chip select lines of this bus, indexed by chip select number
+    #[allow(dead_code)] // for piglet with the cdev backend, which has no SPI
+    pub fn chip_select_functions(&self) -> &'static [PinFunction] {
+        match self {
+            SPIBus::SPI0 => &[PinFunction::SPI0_CE0_N, PinFunction::SPI0_CE1_N],
+            SPIBus::SPI1 => &[
+                PinFunction::SPI1_CE0_N,
+                PinFunction::SPI1_CE1_N,
+                PinFunction::SPI1_CE2_N,
+            ],
+        }
+    }
+}
+
+impl Display for SPIBus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// [SPIMode] is the clock polarity (CPOL) and phase (CPHA) combination used on the bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SPIMode {
+    /// CPOL 0, CPHA 0
+    #[default]
+    Mode0,
+    /// CPOL 0, CPHA 1
+    Mode1,
+    /// CPOL 1, CPHA 0
+    Mode2,
+    /// CPOL 1, CPHA 1
+    Mode3,
+}
+
+impl SPIMode {
+    #[allow(dead_code)] // for piglet
+    pub const ALL: [SPIMode; 4] = [
+        SPIMode::Mode0,
+        SPIMode::Mode1,
+        SPIMode::Mode2,
+        SPIMode::Mode3,
+    ];
+}
+
+impl Display for SPIMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// [SPIConfig] holds the settings used for transfers on an SPI bus. The chip selects that can
+/// be used on the bus are those whose pins are configured with the bus's CEn_N function
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SPIConfig {
+    pub mode: SPIMode,
+    /// Clock speed in Hz
+    pub clock_speed: u32,
+}
+
+impl Default for SPIConfig {
+    fn default() -> Self {
+        Self {
+            mode: SPIMode::Mode0,
+            clock_speed: 1_000_000,
+        }
+    }
+}
+
+/// [SPITransfer] is a full-duplex transfer to be made on an SPI bus: `data` is clocked out on
+/// MOSI while the same number of bytes are clocked in on MISO
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SPITransfer {
+    pub bus: SPIBus,
+    pub chip_select: u8,
+    pub data: Vec<u8>,
+}
+
+/// [SPIResponse] is the result of an [SPITransfer]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SPIResponse {
+    /// The bytes read during the transfer
+    Data {
+        bus: SPIBus,
+        chip_select: u8,
+        data: Vec<u8>,
+    },
+    /// The transfer failed
+    Error(String),
+}
+
+impl Display for SPIResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SPIResponse::Data {
+                bus,
+                chip_select,
+                data,
+            } => {
+                write!(f, "{bus} CE{chip_select} read:")?;
+                for byte in data {
+                    write!(f, " {byte:02x}")?;
+                }
+                Ok(())
+            }
+            SPIResponse::Error(message) => write!(f, "Error: {message}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::spi::{SPIBus, SPIResponse};
+
+    #[test]
+    fn display_transfer_data() {
+        let response = SPIResponse::Data {
+            bus: SPIBus::SPI1,
+            chip_select: 2,
+            data: vec![0xA5, 0x00],
+        };
+        assert_eq!(response.to_string(), "SPI1 CE2 read: a5 00");
+    }
+
+    #[test]
+    fn chip_selects_per_bus() {
+        assert_eq!(SPIBus::SPI0.chip_select_functions().len(), 2);
+        assert_eq!(SPIBus::SPI1.chip_select_functions().len(), 3);
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::hw::{BCMPinNumber, PinLevel};
+
+/// A [Stimulus] describes how the level of inputs changes over time when using the fake
+/// hardware backend, so that demos and tests behave the same way every time they are run
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct Stimulus {
+    pub pins: HashMap<BCMPinNumber, PinStimulus>,
+}
+
+/// The [Waveform] applied to one input and whether it starts again when it reaches the end
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PinStimulus {
+    pub waveform: Waveform,
+    /// Loop back to the start of the waveform at the end, instead of holding the last level
+    #[serde(default)]
+    pub repeat: bool,
+}
+
+/// One level in a [Waveform::Sequence], held for `ms` milliseconds
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Step {
+    pub level: PinLevel,
+    pub ms: u64,
+}
+
+/// How the level of an input changes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Waveform {
+    /// A sequence of levels, each held for some time
+    Sequence(Vec<Step>),
+    /// One period of a square wave: high for half of `period_ms` and then low for the other half
+    SquareWave { period_ms: u64 },
+    /// `count` pulses that are high for `width_ms` and start every `period_ms`
+    PulseTrain {
+        width_ms: u64,
+        period_ms: u64,
+        count: u32,
+    },
+    /// The input follows the level of the output with this [BCMPinNumber], as if wired to it
+    Loopback(BCMPinNumber),
+}
+
+impl Waveform {
+    /// The levels of the waveform and how long each is held for, in order. A [Waveform::Loopback]
+    /// has none, as its levels come from the output it is wired to
+    pub fn steps(&self) -> Vec<(PinLevel, Duration)> {
+        match self {
+            Waveform::Sequence(steps) => steps
+                .iter()
+                .map(|step| (step.level, Duration::from_millis(step.ms)))
+                .collect(),
+            Waveform::SquareWave { period_ms } => {
+                let half = Duration::from_millis(*period_ms) / 2;
+                vec![(true, half), (false, half)]
+            }
+            Waveform::PulseTrain {
+                width_ms,
+                period_ms,
+                count,
+            } => {
+                let width = Duration::from_millis(*width_ms);
+                let gap = Duration::from_millis(*period_ms).saturating_sub(width);
+                (0..*count)
+                    .flat_map(|_| [(true, width), (false, gap)])
+                    .collect()
+            }
+            Waveform::Loopback(_) => vec![],
+        }
+    }
+
+    /// Check the waveform can be simulated: a waveform that repeats must take some time, or it
+    /// would change level as fast as possible forever
+    fn check(&self, repeat: bool) -> io::Result<()> {
+        let total: Duration = self.steps().iter().map(|(_, duration)| *duration).sum();
+        if repeat && !matches!(self, Waveform::Loopback(_)) && total.is_zero() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Repeating waveform {self:?} has a duration of zero"),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Stimulus {
+    /// Load a new Stimulus from the file named `filename`
+    pub fn load(filename: &str) -> io::Result<Stimulus> {
+        let file = File::open(filename)?;
+        let reader = BufReader::new(file);
+        let stimulus: Stimulus = serde_json::from_reader(reader)?;
+        for pin_stimulus in stimulus.pins.values() {
+            pin_stimulus.waveform.check(pin_stimulus.repeat)?;
+        }
+        Ok(stimulus)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::stimulus::{PinStimulus, Step, Stimulus, Waveform};
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::time::Duration;
+    use tempfile::tempdir;
+
+    #[test]
+    fn square_wave_steps() {
+        let waveform = Waveform::SquareWave { period_ms: 100 };
+        assert_eq!(
+            waveform.steps(),
+            vec![
+                (true, Duration::from_millis(50)),
+                (false, Duration::from_millis(50))
+            ]
+        );
+    }
+
+    #[test]
+    fn pulse_train_steps() {
+        let waveform = Waveform::PulseTrain {
+            width_ms: 10,
+            period_ms: 100,
+            count: 2,
+        };
+        assert_eq!(
+            waveform.steps(),
+            vec![
+                (true, Duration::from_millis(10)),
+                (false, Duration::from_millis(90)),
+                (true, Duration::from_millis(10)),
+                (false, Duration::from_millis(90))
+            ]
+        );
+    }
+
+    #[test]
+    fn load_stimulus_file() {
+        let root = std::env::var("CARGO_MANIFEST_DIR").expect("Could not get manifest dir");
+        let path = PathBuf::from(root).join("configs/fake_stimulus.json");
+        let stimulus = Stimulus::load(path.to_str().expect("Could not get Path as str"))
+            .expect("Could not load Stimulus from path");
+        assert_eq!(
+            stimulus.pins.get(&26),
+            Some(&PinStimulus {
+                waveform: Waveform::SquareWave { period_ms: 1000 },
+                repeat: true,
+            })
+        );
+        assert_eq!(
+            stimulus.pins.get(&6).map(|pin| &pin.waveform),
+            Some(&Waveform::Loopback(17))
+        );
+    }
+
+    #[test]
+    fn repeating_zero_duration_rejected() {
+        let contents =
+            r#"{"pins":{"5":{"waveform":{"Sequence":[{"level":true,"ms":0}]},"repeat":true}}}"#;
+        let output_dir = tempdir().expect("Could not create a tempdir").into_path();
+        let test_file = output_dir.join("stimulus.json");
+        let mut file = File::create(&test_file).expect("Could not create test file");
+        file.write_all(contents.as_bytes())
+            .expect("Could not write to test file");
+        assert!(Stimulus::load(test_file.to_str().expect("Could not get Path as str")).is_err());
+    }
+
+    #[test]
+    fn sequence_not_repeated_can_be_instant() {
+        let waveform = Waveform::Sequence(vec![Step { level: true, ms: 0 }]);
+        assert!(waveform.check(false).is_ok());
+    }
+}
@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+/// Baud rates offered for the UART, the most commonly used by devices
+#[allow(dead_code)] // for piglet
+pub const UART_BAUD_RATES: [u32; 8] = [1200, 2400, 4800, 9600, 19200, 38400, 57600, 115200];
+
+/// Parity bit used on the UART
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum UARTParity {
+    #[default]
+    None,
+    Even,
+    Odd,
+}
+
+impl UARTParity {
+    #[allow(dead_code)] // for piglet
+    pub const ALL: [UARTParity; 3] = [UARTParity::None, UARTParity::Even, UARTParity::Odd];
+}
+
+impl Display for UARTParity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// [UARTConfig] holds the settings of the UART on GPIO14 (TXD) and GPIO15 (RXD).
+/// Eight data bits are always used
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UARTConfig {
+    pub baud_rate: u32,
+    pub parity: UARTParity,
+    /// Number of stop bits, 1 or 2
+    pub stop_bits: u8,
+}
+
+impl Default for UARTConfig {
+    fn default() -> Self {
+        Self {
+            baud_rate: 115200,
+            parity: UARTParity::None,
+            stop_bits: 1,
+        }
+    }
+}
+
+impl Display for UARTConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let parity = match self.parity {
+            UARTParity::None => 'N',
+            UARTParity::Even => 'E',
+            UARTParity::Odd => 'O',
+        };
+        write!(f, "{} 8{parity}{}", self.baud_rate, self.stop_bits)
+    }
+}
+
+/// [UARTEvent] is sent from the hardware to the GUI when something happens on the UART
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum UARTEvent {
+    /// Bytes were received on RXD
+    Received(Vec<u8>),
+    /// The UART could not be opened, written to or read from
+    Error(String),
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::uart::{UARTConfig, UARTParity};
+
+    #[test]
+    fn display_default_config() {
+        assert_eq!(UARTConfig::default().to_string(), "115200 8N1");
+    }
+
+    #[test]
+    fn display_config() {
+        let config = UARTConfig {
+            baud_rate: 9600,
+            parity: UARTParity::Even,
+            stop_bits: 2,
+        };
+        assert_eq!(config.to_string(), "9600 8E2");
+    }
+}
@@ -0,0 +1,228 @@
+//! Persistence of the iroh-net keys of piglet and piggui, so their nodeids are stable between
+//! runs, and of the allow-list of the clients piglet accepts
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{DirBuilderExt, OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use iroh_net::key::SecretKey;
+use iroh_net::NodeId;
+
+/// The directory where `app_name` keeps state that should survive restarts, such as its key.
+/// This is in the user's state directory, or their local data directory on platforms without one
+pub fn state_dir(app_name: &str) -> Option<PathBuf> {
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join(app_name))
+}
+
+/// Load the secret key that a node's [NodeId] is derived from, from `key_path`, so the nodeid
+/// stays the same across restarts. A new key is generated and saved if there is none yet or if
+/// `regenerate` is set. The key file is only readable by the user, as anyone that can read it
+/// can impersonate the node, and its permissions are restricted if they were more open than that
+pub fn load_or_create_key(key_path: &Path, regenerate: bool) -> io::Result<SecretKey> {
+    if !regenerate && key_path.exists() {
+        restrict_permissions(key_path)?;
+        let contents = fs::read_to_string(key_path)?;
+        return SecretKey::from_str(contents.trim()).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Could not read the key in '{}': {e}", key_path.display()),
+            )
+        });
+    }
+
+    let secret_key = SecretKey::generate();
+    if let Some(dir) = key_path.parent() {
+        let mut builder = fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        builder.mode(0o700);
+        builder.create(dir)?;
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(key_path)?;
+    // an existing file keeps its permissions when opened, so restrict them when regenerating
+    #[cfg(unix)]
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    writeln!(file, "{secret_key}")?;
+    Ok(secret_key)
+}
+
+/// Remove any access to the key file by other users
+fn restrict_permissions(key_path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let permissions = fs::metadata(key_path)?.permissions();
+        if permissions.mode() & 0o077 != 0 {
+            fs::set_permissions(key_path, fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = key_path;
+    Ok(())
+}
+
+/// The [NodeId]s of the clients that are allowed to control a piglet
+#[allow(dead_code)] // for piglet
+#[derive(Debug, Default)]
+pub struct AllowList {
+    clients: HashSet<NodeId>,
+}
+
+#[allow(dead_code)] // for piglet
+impl AllowList {
+    /// Load the allow-list from `path`, a text file with one nodeid per line. Empty lines and
+    /// anything after a '#' are ignored. A file that does not exist is an empty allow-list
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let mut allow_list = AllowList::default();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(allow_list),
+            Err(e) => return Err(e),
+        };
+
+        for (index, line) in contents.lines().enumerate() {
+            let entry = line.split('#').next().unwrap_or_default().trim();
+            if entry.is_empty() {
+                continue;
+            }
+            let nodeid = NodeId::from_str(entry).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Invalid nodeid '{entry}' on line {} of '{}': {e}",
+                        index + 1,
+                        path.display()
+                    ),
+                )
+            })?;
+            allow_list.add(nodeid);
+        }
+
+        Ok(allow_list)
+    }
+
+    /// Allow the client with `nodeid`
+    pub fn add(&mut self, nodeid: NodeId) {
+        self.clients.insert(nodeid);
+    }
+
+    /// True if no clients have been allowed
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    /// The number of clients allowed
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// True if the client with `nodeid` is allowed
+    pub fn contains(&self, nodeid: &NodeId) -> bool {
+        self.clients.contains(nodeid)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+
+    use iroh_net::key::SecretKey;
+    use tempfile::tempdir;
+
+    use crate::identity::{load_or_create_key, AllowList};
+
+    fn temp_path(filename: &str) -> PathBuf {
+        tempdir()
+            .expect("Could not create a tempdir")
+            .into_path()
+            .join(filename)
+    }
+
+    #[test]
+    fn key_kept_between_runs() {
+        let key_path = temp_path("state").join("piglet.key");
+        let first = load_or_create_key(&key_path, false).expect("Could not create key");
+        assert!(key_path.exists(), "Key file was not created");
+        let second = load_or_create_key(&key_path, false).expect("Could not load key");
+        assert_eq!(first.public(), second.public());
+    }
+
+    #[test]
+    fn regenerate_key() {
+        let key_path = temp_path("piglet.key");
+        let first = load_or_create_key(&key_path, false).expect("Could not create key");
+        let second = load_or_create_key(&key_path, true).expect("Could not regenerate key");
+        assert_ne!(first.public(), second.public());
+        let third = load_or_create_key(&key_path, false).expect("Could not load key");
+        assert_eq!(second.public(), third.public());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn key_file_permissions() {
+        let key_path = temp_path("piglet.key");
+        load_or_create_key(&key_path, false).expect("Could not create key");
+        let mode = |path| {
+            fs::metadata(path)
+                .expect("Could not read metadata")
+                .permissions()
+                .mode()
+                & 0o777
+        };
+        assert_eq!(mode(&key_path), 0o600);
+
+        fs::set_permissions(&key_path, fs::Permissions::from_mode(0o644))
+            .expect("Could not set permissions");
+        load_or_create_key(&key_path, false).expect("Could not load key");
+        assert_eq!(mode(&key_path), 0o600);
+    }
+
+    #[test]
+    fn invalid_key_file() {
+        let key_path = temp_path("piglet.key");
+        fs::write(&key_path, "not a key").expect("Could not write key file");
+        assert!(load_or_create_key(&key_path, false).is_err());
+    }
+
+    #[test]
+    fn load_allow_list() {
+        let allowed = SecretKey::generate().public();
+        let other = SecretKey::generate().public();
+        let path = temp_path("piglet.allow");
+        fs::write(
+            &path,
+            format!("# bench laptop\n\n  {allowed}  # Andrew\n# {other}\n"),
+        )
+        .expect("Could not write allow-list");
+        let allow_list = AllowList::load(&path).expect("Could not load allow-list");
+        assert_eq!(allow_list.len(), 1);
+        assert!(allow_list.contains(&allowed));
+        assert!(!allow_list.contains(&other));
+    }
+
+    #[test]
+    fn missing_allow_list_is_empty() {
+        let allow_list =
+            AllowList::load(&temp_path("piglet.allow")).expect("Could not load allow-list");
+        assert!(allow_list.is_empty());
+    }
+
+    #[test]
+    fn invalid_allow_list_entry() {
+        let path = temp_path("piglet.allow");
+        fs::write(&path, "# clients\nnot-a-nodeid\n").expect("Could not write allow-list");
+        let error = AllowList::load(&path).expect_err("Invalid nodeid was accepted");
+        assert!(error.to_string().contains("line 2"), "{error}");
+    }
+}
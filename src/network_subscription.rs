@@ -1,8 +1,13 @@
-use crate::hw::HardwareConfigMessage::IOLevelChanged;
-use crate::hw::{HardwareConfigMessage, HardwareDescription, PIGLET_ALPN};
+use crate::hw::HardwareConfigMessage::{
+    Ack, Control, Failed, I2CResponse, IOLevelChanged, NewConfig, NewPinConfig, NewSPIConfig,
+    NewUARTConfig, SPIResponse, SwitchProfile, UARTEvent,
+};
+use crate::hw::{HardwareConfigMessage, HardwareDescription, Request, RequestId, PIGLET_ALPN};
+use crate::identity;
+use crate::protocol::{Channel, FrameReader, FrameWriter};
 use crate::views::hardware_view::HardwareEventMessage;
 use crate::views::hardware_view::HardwareEventMessage::InputChange;
-use anyhow::{ensure, Context};
+use anyhow::Context;
 use iced::futures::channel::mpsc;
 use iced::futures::channel::mpsc::Receiver;
 use iced::futures::sink::SinkExt;
@@ -13,6 +18,7 @@ use iroh_net::endpoint::Connection;
 use iroh_net::key::SecretKey;
 use iroh_net::relay::{RelayMode, RelayUrl};
 use iroh_net::{Endpoint, NodeAddr, NodeId};
+use std::collections::HashMap;
 use std::io;
 
 /// This enum describes the states of the subscription
@@ -20,7 +26,34 @@ pub enum NetworkState {
     /// Just starting up, we have not yet set up a channel between GUI and Listener
     Disconnected,
     /// The subscription is ready and will listen for config events on the channel contained
-    Connected(Receiver<HardwareConfigMessage>, Connection),
+    Connected(
+        Receiver<HardwareConfigMessage>,
+        Connection,
+        Box<Channel>,
+        PendingRequests,
+    ),
+}
+
+/// The changes sent to piglet that it has not replied to yet, by the id of their [Request]
+#[derive(Debug, Default)]
+pub struct PendingRequests {
+    next_id: RequestId,
+    pending: HashMap<RequestId, HardwareConfigMessage>,
+}
+
+impl PendingRequests {
+    /// Make a [Request] of `message` with a new id, remembering it until piglet replies to it
+    fn request(&mut self, message: HardwareConfigMessage) -> Request {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending.insert(id, message.clone());
+        Request { id, message }
+    }
+
+    /// Forget the request with `id` that piglet has replied to, returning the change it made
+    fn replied(&mut self, id: RequestId) -> Option<HardwareConfigMessage> {
+        self.pending.remove(&id)
+    }
 }
 
 // TODO Emit a "Connected" message when successful that will close dialog
@@ -46,7 +79,7 @@ pub fn subscribe(nodeid: NodeId, relay: Option<RelayUrl>) -> Subscription<Hardwa
                         let (hardware_event_sender, hardware_event_receiver) = mpsc::channel(100);
 
                         match connect(&nodeid, relay.clone()).await {
-                            Ok((hardware_description, connection)) => {
+                            Ok((hardware_description, connection, channel)) => {
                                 // Send the sender back to the GUI
                                 let _ = gui_sender_clone
                                     .send(HardwareEventMessage::Connected(
@@ -56,8 +89,12 @@ pub fn subscribe(nodeid: NodeId, relay: Option<RelayUrl>) -> Subscription<Hardwa
                                     .await;
 
                                 // We are ready to receive messages from the GUI
-                                state =
-                                    NetworkState::Connected(hardware_event_receiver, connection);
+                                state = NetworkState::Connected(
+                                    hardware_event_receiver,
+                                    connection,
+                                    Box::new(channel),
+                                    PendingRequests::default(),
+                                );
                             }
                             Err(e) => {
                                 // TODO surface to the UI somehow
@@ -66,23 +103,56 @@ pub fn subscribe(nodeid: NodeId, relay: Option<RelayUrl>) -> Subscription<Hardwa
                         }
                     }
 
-                    NetworkState::Connected(config_change_receiver, connection) => {
-                        let mut connection_clone = connection.clone();
+                    NetworkState::Connected(
+                        config_change_receiver,
+                        _connection,
+                        channel,
+                        requests,
+                    ) => {
                         let fused_wait_for_remote_message =
-                            wait_for_remote_message(&mut connection_clone).fuse();
+                            wait_for_remote_message(&mut channel.reader).fuse();
                         pin_mut!(fused_wait_for_remote_message);
 
                         futures::select! {
                             // receive a config change from the UI
                             config_change_message = config_change_receiver.select_next_some() => {
-                                send_config_change(connection, config_change_message).await.unwrap()
+                                let request = requests.request(config_change_message);
+                                send_config_change(&mut channel.writer, request).await.unwrap()
                             }
 
                             // receive an input level change from remote hardware
                             remote_event = fused_wait_for_remote_message => {
-                                if let Ok(IOLevelChanged(bcm, level_change)) = remote_event {
-                                     gui_sender_clone.send(InputChange(bcm, level_change)).await.unwrap();
-                                 }
+                                match remote_event {
+                                    Ok(IOLevelChanged(bcm, level_change)) => {
+                                        gui_sender_clone.send(InputChange(bcm, level_change)).await.unwrap();
+                                    }
+                                    Ok(I2CResponse(response)) => {
+                                        gui_sender_clone.send(HardwareEventMessage::I2CResponse(response)).await.unwrap();
+                                    }
+                                    Ok(SPIResponse(response)) => {
+                                        gui_sender_clone.send(HardwareEventMessage::SPIResponse(response)).await.unwrap();
+                                    }
+                                    Ok(UARTEvent(event)) => {
+                                        gui_sender_clone.send(HardwareEventMessage::UARTEvent(event)).await.unwrap();
+                                    }
+                                    Ok(Control(has_control)) => {
+                                        gui_sender_clone.send(HardwareEventMessage::Control(has_control)).await.unwrap();
+                                    }
+                                    Ok(Ack(id)) => {
+                                        requests.replied(id);
+                                    }
+                                    // piglet follows this with the actual state of what the change tried to change
+                                    Ok(Failed(id, error)) => {
+                                        if let Some(change) = requests.replied(id) {
+                                            gui_sender_clone.send(HardwareEventMessage::ChangeFailed(change, error)).await.unwrap();
+                                        }
+                                    }
+                                    // changes made by another client in control of the hardware
+                                    Ok(change @ (NewConfig(_) | SwitchProfile(..) | NewPinConfig(..) | NewSPIConfig(..) | NewUARTConfig(_))) => {
+                                        gui_sender_clone.send(HardwareEventMessage::ConfigChanged(change)).await.unwrap();
+                                    }
+                                    _ => {}
+                                }
                             }
                         }
                     }
@@ -94,41 +164,40 @@ pub fn subscribe(nodeid: NodeId, relay: Option<RelayUrl>) -> Subscription<Hardwa
 
 /// Wait until we receive a message from remote hardware
 async fn wait_for_remote_message(
-    connection: &mut Connection,
+    reader: &mut FrameReader,
 ) -> Result<HardwareConfigMessage, anyhow::Error> {
-    let mut config_receiver = connection.accept_uni().await?;
-    let message = config_receiver.read_to_end(4096).await?;
-    ensure!(
-        !message.is_empty(),
-        io::Error::new(io::ErrorKind::BrokenPipe, "Connection closed")
-    );
-
-    let content = String::from_utf8_lossy(&message);
-    Ok(serde_json::from_str(&content)?)
+    let message = reader.receive().await?;
+    Ok(message.ok_or(io::Error::new(
+        io::ErrorKind::BrokenPipe,
+        "Connection closed",
+    ))?)
 }
 
-/// Send config change received form the GUI to the remote hardware
-async fn send_config_change(
-    connection: &mut Connection,
-    config_change_message: HardwareConfigMessage,
-) -> anyhow::Result<()> {
-    // open a quick stream to the connected hardware
-    let mut config_sender = connection.open_uni().await?;
-    // serialize the message
-    let content = serde_json::to_string(&config_change_message)?;
-    // send it to the remotely connected hardware
-    config_sender.write_all(content.as_bytes()).await?;
-    // close and flush the stream to ensure the message is sent
-    config_sender.finish().await?;
+/// Send a request with a config change received form the GUI to the remote hardware
+async fn send_config_change(writer: &mut FrameWriter, request: Request) -> anyhow::Result<()> {
+    writer.send(&request).await?;
     Ok(())
 }
 
+/// The key piggui connects to piglets with, kept between runs so that its nodeid only needs to be
+/// added to the allow-list of a piglet once
+pub fn client_key() -> io::Result<SecretKey> {
+    let state_dir = identity::state_dir("piggui").ok_or(io::Error::new(
+        io::ErrorKind::NotFound,
+        "Could not find a directory to keep piggui's key in",
+    ))?;
+    identity::load_or_create_key(&state_dir.join("piggui.key"), false)
+}
+
 //noinspection SpellCheckingInspection
 async fn connect(
     nodeid: &NodeId,
     relay: Option<RelayUrl>,
-) -> anyhow::Result<(HardwareDescription, Connection)> {
-    let secret_key = SecretKey::generate();
+) -> anyhow::Result<(HardwareDescription, Connection, Channel)> {
+    let secret_key = client_key().unwrap_or_else(|e| {
+        eprintln!("Could not load piggui's key, connecting with a new one: {e}");
+        SecretKey::generate()
+    });
 
     // Build a `Endpoint`, which uses PublicKeys as node identifiers
     let endpoint = Endpoint::builder()
@@ -151,10 +220,11 @@ async fn connect(
 
     // find my closest relay - maybe set this as a default in the UI but allow used to
     // override it in a text entry box. Leave black for user if fails to fetch it.
-    let relay_url = relay.unwrap_or(endpoint.home_relay().ok_or(io::Error::new(
-        io::ErrorKind::Other,
-        "Could not get home relay",
-    ))?);
+    let relay_url = relay.unwrap_or(
+        endpoint
+            .home_relay()
+            .ok_or(io::Error::other("Could not get home relay"))?,
+    );
 
     // Build a `NodeAddr` from the node_id, relay url, and UDP addresses.
     let addr = NodeAddr::from_parts(*nodeid, Some(relay_url), vec![]);
@@ -162,11 +232,42 @@ async fn connect(
     // Attempt to connect, over the given ALPN, returns a Quinn connection.
     let connection = endpoint.connect(addr, PIGLET_ALPN).await?;
 
-    // create a uni receiver to receive the hardware description on
-    let mut gui_receiver = connection.accept_uni().await?;
-    let message = gui_receiver.read_to_end(4096).await?;
-    let message = String::from_utf8(message)?;
-    let desc = serde_json::from_str(&message)?;
+    // agree how to talk to piglet, then it sends the hardware description first
+    let mut channel = Channel::connect(&connection).await?;
+    let desc = channel
+        .reader
+        .receive()
+        .await?
+        .context("Connection closed before the hardware description was received")?;
+
+    Ok((desc, connection, channel))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::pin_function::PinFunction;
+    use crate::hw::HardwareConfigMessage::NewPinConfig;
+    use crate::network_subscription::PendingRequests;
+
+    #[test]
+    fn requests_have_unique_ids() {
+        let mut requests = PendingRequests::default();
+        let first = requests.request(NewPinConfig(4, PinFunction::Output(None)));
+        let second = requests.request(NewPinConfig(4, PinFunction::None));
+        assert_ne!(first.id, second.id);
+    }
 
-    Ok((desc, connection))
+    #[test]
+    fn reply_matched_with_request() {
+        let mut requests = PendingRequests::default();
+        let request = requests.request(NewPinConfig(4, PinFunction::Output(None)));
+        assert!(matches!(
+            requests.replied(request.id),
+            Some(NewPinConfig(4, PinFunction::Output(None)))
+        ));
+        assert!(
+            requests.replied(request.id).is_none(),
+            "Request was replied to twice"
+        );
+    }
 }
@@ -1,7 +1,12 @@
 use crate::connect_dialog_handler::ConnectDialogMessage::HideConnectDialog;
 use crate::connect_dialog_handler::{ConnectDialog, ConnectDialogMessage};
 use crate::file_helper::{maybe_load_no_picker, pick_and_load, save};
-use crate::hw::config::HardwareConfig;
+#[cfg(feature = "hardware")]
+use crate::hw::backend::Backend;
+use crate::hw::config::{BoardMetadata, HardwareConfig};
+#[cfg(feature = "hardware")]
+use crate::hw::stimulus::Stimulus;
+use crate::network_subscription::client_key;
 use crate::toast_handler::{ToastHandler, ToastMessage};
 use crate::views::hardware_view::{HardwareTarget, HardwareView, HardwareViewMessage};
 use crate::views::info_row::InfoRow;
@@ -11,6 +16,8 @@ use crate::views::message_row::MessageMessage::Info;
 use crate::views::message_row::{MessageMessage, MessageRowMessage};
 use crate::widgets::modal::Modal;
 use crate::Message::*;
+#[cfg(feature = "hardware")]
+use clap::builder::PossibleValuesParser;
 use clap::{Arg, ArgMatches};
 use iced::widget::{container, Column};
 use iced::{
@@ -26,7 +33,9 @@ mod file_helper;
 #[cfg(feature = "hardware")]
 pub mod hardware_subscription;
 mod hw;
+mod identity;
 pub mod network_subscription;
+mod protocol;
 mod styles;
 mod toast_handler;
 mod views;
@@ -36,6 +45,8 @@ mod widgets;
 #[derive(Debug, Clone)]
 pub enum Message {
     ConfigLoaded(String, HardwareConfig),
+    HatPresetSelected(HardwareConfig),
+    ProfileSelected(String),
     ConfigSaved,
     ConfigChangesMade,
     Save,
@@ -74,6 +85,20 @@ impl Piggui {
             ));
     }
 
+    /// Apply a new config, showing the user any problems found with it on the connected hardware.
+    /// The parts of it with problems are removed, so it then has unsaved changes
+    fn new_config(&mut self, config: HardwareConfig) -> Command<Message> {
+        let problems = self.hardware_view.new_config(config);
+        if problems.is_empty() {
+            return Command::none();
+        }
+        self.unsaved_changes = true;
+        self.toast_handler.update(
+            ToastMessage::ConfigProblemsToast(problems),
+            &self.hardware_view,
+        )
+    }
+
     /// Send a message about successful connection to the info bar
     fn info_connected(&mut self, message: String) {
         self.info_row.add_info_message(Info(message));
@@ -111,6 +136,17 @@ impl Application for Piggui {
             .get_one::<String>("config-file")
             .map(|s| s.to_string());
 
+        #[allow(unused_mut)] // the backend is only set when built with local hardware
+        let mut hardware_view = HardwareView::new();
+        #[cfg(feature = "hardware")]
+        hardware_view.set_backend(get_backend(&matches), get_stimulus(&matches));
+
+        let mut connect_dialog = ConnectDialog::new();
+        match client_key() {
+            Ok(key) => connect_dialog.set_client_nodeid(key.public()),
+            Err(e) => eprintln!("Could not load piggui's key: {e}"),
+        }
+
         (
             Self {
                 config_filename: config_filename.clone(),
@@ -118,8 +154,8 @@ impl Application for Piggui {
                 unsaved_changes: false,
                 info_row: InfoRow::new(),
                 toast_handler: ToastHandler::new(),
-                hardware_view: HardwareView::new(),
-                connect_dialog: ConnectDialog::new(),
+                hardware_view,
+                connect_dialog,
                 hardware_target: get_hardware_target(&matches),
             },
             maybe_load_no_picker(config_filename),
@@ -157,7 +193,12 @@ impl Application for Piggui {
             }
 
             Save => {
-                return save(self.hardware_view.get_config());
+                let mut config = self.hardware_view.get_config();
+                config.board = self
+                    .hardware_view
+                    .hw_model()
+                    .map(|model| BoardMetadata { model });
+                return save(config);
             }
 
             ConfigSaved => {
@@ -191,7 +232,16 @@ impl Application for Piggui {
             }
 
             Hardware(msg) => {
-                return self.hardware_view.update(msg);
+                let command = self.hardware_view.update(msg);
+                // Fit the window to the number of pins of the hardware, if that has changed
+                if let Some(size) = self
+                    .hardware_view
+                    .pin_descriptions()
+                    .and_then(|pins| self.layout_selector.set_pin_descriptions(pins))
+                {
+                    return Command::batch(vec![command, window::resize(window::Id::MAIN, size)]);
+                }
+                return command;
             }
 
             ConfigChangesMade => {
@@ -201,7 +251,25 @@ impl Application for Piggui {
             ConfigLoaded(filename, config) => {
                 self.config_filename = Some(filename);
                 self.unsaved_changes = false;
-                self.hardware_view.new_config(config);
+                return self.new_config(config);
+            }
+
+            HatPresetSelected(config) => {
+                self.unsaved_changes = true;
+                return self.new_config(config);
+            }
+
+            ProfileSelected(name) => {
+                let problems = self.hardware_view.switch_profile(&name);
+                self.info_row
+                    .add_info_message(Info(format!("Switched to profile '{name}'")));
+                if !problems.is_empty() {
+                    self.unsaved_changes = true;
+                    return self.toast_handler.update(
+                        ToastMessage::ConfigProblemsToast(problems),
+                        &self.hardware_view,
+                    );
+                }
             }
 
             ConnectRequest(new_target) => {
@@ -247,7 +315,7 @@ impl Application for Piggui {
        |  +---------------------------------------------------------------------------------+ |
        +--------------------------------------------------------------------------------------+
     */
-    fn view(&self) -> Element<Message> {
+    fn view(&self) -> Element<'_, Message> {
         let main_col = Column::new()
             .push(main_row::view(
                 &self.hardware_view,
@@ -310,6 +378,34 @@ fn get_hardware_target(matches: &ArgMatches) -> HardwareTarget {
     target
 }
 
+/// Determine the backend to use for local hardware based on command line options
+#[cfg(feature = "hardware")]
+fn get_backend(matches: &ArgMatches) -> Backend {
+    matches
+        .get_one::<String>("backend")
+        .and_then(|name| match Backend::from_str(name) {
+            Ok(backend) => Some(backend),
+            Err(e) => {
+                eprintln!("{e}");
+                None
+            }
+        })
+        .unwrap_or_default()
+}
+
+/// Load any stimulus file for the fake backend specified in command line options
+#[cfg(feature = "hardware")]
+fn get_stimulus(matches: &ArgMatches) -> Option<Stimulus> {
+    let filename = matches.get_one::<String>("stimulus")?;
+    match Stimulus::load(filename) {
+        Ok(stimulus) => Some(stimulus),
+        Err(e) => {
+            eprintln!("Could not load stimulus from '{filename}': {e}");
+            None
+        }
+    }
+}
+
 /// Parse the command line arguments using clap
 fn get_matches() -> ArgMatches {
     let app = clap::Command::new(env!("CARGO_BIN_NAME")).version(env!("CARGO_PKG_VERSION"));
@@ -326,6 +422,29 @@ fn get_matches() -> ArgMatches {
             .help("Node Id of a piglet instance to connect to"),
     );
 
+    #[cfg(feature = "hardware")]
+    let app = app.arg(
+        Arg::new("backend")
+            .short('b')
+            .long("backend")
+            .num_args(1)
+            .number_of_values(1)
+            .value_name("BACKEND")
+            .value_parser(PossibleValuesParser::new(Backend::names()))
+            .help("Backend for local GPIO, 'auto' (default) uses the Pi's GPIO if running on one"),
+    );
+
+    #[cfg(feature = "hardware")]
+    let app = app.arg(
+        Arg::new("stimulus")
+            .short('s')
+            .long("stimulus")
+            .num_args(1)
+            .number_of_values(1)
+            .value_name("STIMULUS_FILE")
+            .help("Path of a stimulus file with waveforms to drive the inputs of the fake backend"),
+    );
+
     let app = app.arg(
         Arg::new("config-file")
             .num_args(0..)
@@ -338,6 +457,54 @@ fn get_matches() -> ArgMatches {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::hw::pin_description::{PinDescription, PinDescriptionSet};
+    use crate::hw::pin_function::PinFunction;
+    use crate::hw::{HardwareDescription, HardwareDetails};
+    use crate::views::hardware_view::HardwareEventMessage;
+    use crate::views::hardware_view::HardwareViewMessage::HardwareSubscription;
+    use std::borrow::Cow;
+    use std::collections::HashMap;
+
+    /// Connect `app` to hardware with only GPIO17, which can only be an output
+    fn connect_gpio17(app: &mut Piggui) {
+        let description = HardwareDescription {
+            details: HardwareDetails {
+                hardware: "Test".into(),
+                revision: "Unknown".into(),
+                serial: "Unknown".into(),
+                model: "Test".into(),
+                board: None,
+            },
+            pins: PinDescriptionSet::from(vec![PinDescription {
+                bpn: 11,
+                bcm: Some(17),
+                name: Cow::Borrowed("GPIO17"),
+                options: Cow::Borrowed(&[PinFunction::Output(None)]),
+            }]),
+            hat: None,
+            probe: HashMap::new(),
+        };
+        let (sender, _) = iced::futures::channel::mpsc::channel(10);
+        let _ = app.update(Hardware(HardwareSubscription(
+            HardwareEventMessage::Connected(sender, description),
+        )));
+    }
+
+    #[test]
+    fn config_with_problems_has_unsaved_changes() {
+        let mut app = Piggui::new(()).0;
+        connect_gpio17(&mut app);
+
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(None));
+        let _ = app.update(ConfigLoaded("clean.pigg".into(), config.clone()));
+        assert!(!app.unsaved_changes);
+
+        config.pins.insert(4, PinFunction::Output(None));
+        let _ = app.update(ConfigLoaded("problems.pigg".into(), config));
+        assert!(app.unsaved_changes);
+        assert!(!app.hardware_view.get_config().pins.contains_key(&4));
+    }
 
     #[test]
     fn test_window_close_with_unsaved_changes() {
@@ -6,36 +6,67 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::time::Duration;
-use std::{env, fs, io, process};
+use std::{env, fs, io, process, slice};
 
 use anyhow::Context;
+use clap::builder::PossibleValuesParser;
 use clap::{Arg, ArgMatches, Command};
 use futures_lite::StreamExt;
-use iroh_net::endpoint::Connection;
+use iroh_net::endpoint::{Connection, VarInt};
 use iroh_net::relay::RelayUrl;
 use iroh_net::{key::SecretKey, relay::RelayMode, Endpoint, NodeId};
 use log::error;
-use log::{info, trace};
+use log::{info, trace, warn};
 use service_manager::{
     ServiceInstallCtx, ServiceLabel, ServiceManager, ServiceStartCtx, ServiceStopCtx,
     ServiceUninstallCtx,
 };
 use sysinfo::{Process, System};
+#[cfg(unix)]
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
 use tracing::Level;
 use tracing_subscriber::filter::{Directive, LevelFilter};
 use tracing_subscriber::EnvFilter;
 
-use hw::config::HardwareConfig;
+use hw::config::{validate, HardwareConfig, DEFAULT_PROFILE};
 use hw::Hardware;
 
+use crate::hw::backend::{Backend, HW};
+use crate::hw::pin_description::PinDescriptionSet;
 use crate::hw::pin_function::PinFunction;
-use crate::hw::HardwareConfigMessage::{IOLevelChanged, NewConfig, NewPinConfig};
-use crate::hw::{BCMPinNumber, HardwareConfigMessage, PinLevel};
+use crate::hw::stimulus::Stimulus;
+use crate::hw::uart::UARTConfig;
+use crate::hw::uart::UARTEvent::{Error as UARTError, Received as UARTReceived};
+use crate::hw::HardwareConfigMessage::{
+    Ack, Control, Failed, I2CRequest, I2CResponse, IOLevelChanged, NewConfig, NewPinConfig,
+    NewSPIConfig, NewUARTConfig, SPIResponse, SPITransfer, SwitchProfile, UARTEvent, UARTWrite,
+};
+use crate::hw::{BCMPinNumber, HardwareConfigMessage, PinLevel, Request};
 use crate::hw::{LevelChange, PIGLET_ALPN};
+use crate::identity::AllowList;
+use crate::protocol::{Channel, FrameReader};
 
 mod hw;
+mod identity;
+mod protocol;
 const SERVICE_NAME: &str = "net.mackenzie-serres.pigg.piglet";
+const KEY_FILENAME: &str = "piglet.key";
+const ALLOW_LIST_FILENAME: &str = "piglet.allow";
+
+/// What piglet does with connections from clients that are not in its allow-list
+const UNKNOWN_CLIENTS: [&str; 2] = ["reject", "read-only"];
+
+/// What a connected client is allowed to do
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClientAccess {
+    /// The client can configure and drive the hardware, when it holds the control lease
+    Control,
+    /// The client can observe the hardware, but never gets control of it
+    ReadOnly,
+}
 
 /// Piglet will expose the same functionality from the GPIO Hardware Backend used by the GUI
 /// in Piggy, but without any GUI or related dependencies, loading a config from file and
@@ -48,8 +79,11 @@ async fn main() -> anyhow::Result<()> {
     manage_service(&exec_path, &matches)?;
 
     let info_path = check_unique(&exec_path)?;
+    let state_dir = identity::state_dir("piglet")
+        .or_else(|| exec_path.parent().map(Path::to_path_buf))
+        .unwrap_or_default();
 
-    run_service(&info_path, &matches).await
+    run_service(&info_path, &state_dir, &matches).await
 }
 
 /// Handle any service installation or uninstallation tasks
@@ -71,25 +105,94 @@ fn manage_service(exec_path: &Path, matches: &ArgMatches) -> anyhow::Result<()>
 
 /// Run piglet as a service - this could be interactively by a user in foreground or started
 /// by the system as a user service, in background - use logging for output from here on
-async fn run_service(info_path: &Path, matches: &ArgMatches) -> anyhow::Result<()> {
+async fn run_service(
+    info_path: &Path,
+    state_dir: &Path,
+    matches: &ArgMatches,
+) -> anyhow::Result<()> {
     setup_logging(matches);
 
-    let mut hw = hw::get();
-    info!("\n{}", hw.description()?.details);
+    let key_path = state_dir.join(KEY_FILENAME);
+    let regenerate = matches.get_flag("regenerate-key") || !key_path.exists();
+    let secret_key = identity::load_or_create_key(&key_path, regenerate)?;
+    if regenerate {
+        info!("New key generated and saved to: {key_path:?}");
+    } else {
+        info!("Key loaded from: {key_path:?}");
+    }
+
+    let allow_list = load_allow_list(&state_dir.join(ALLOW_LIST_FILENAME), matches)?;
+    let unknown_access = match matches
+        .get_one::<String>("unknown-clients")
+        .map(String::as_str)
+    {
+        Some("read-only") => Some(ClientAccess::ReadOnly),
+        _ => None,
+    };
+
+    let backend = matches
+        .get_one::<String>("backend")
+        .map(|name| Backend::from_str(name))
+        .transpose()?
+        .unwrap_or_default();
+    let mut hw = hw::get(backend);
+    info!("Using the '{}' hardware backend", hw.backend());
+    if let Some(stimulus_filename) = matches.get_one::<String>("stimulus") {
+        hw.set_stimulus(Stimulus::load(stimulus_filename)?)?;
+        info!("Stimulus loaded from file: {stimulus_filename}");
+    }
+    let description = hw.description()?;
+    info!("\n{}", description.details);
+    if let Some(hat) = description.hat {
+        info!("\n{hat}");
+    }
+    let mut probe: Vec<_> = description.probe.iter().collect();
+    probe.sort_by_key(|(bcm_pin_number, _)| **bcm_pin_number);
+    for (bcm_pin_number, pin_probe) in probe {
+        info!("GPIO{bcm_pin_number}: {pin_probe}");
+    }
 
     // Load any config file specified on the command line
     if let Some(config_filename) = matches.get_one::<String>("config-file") {
-        let config = HardwareConfig::load(config_filename)?;
-        info!("Config loaded from file: {config_filename}");
+        let profile = matches
+            .get_one::<String>("profile")
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_PROFILE);
+        let config = HardwareConfig::load(config_filename)?.profile(profile)?;
+        info!("Config loaded from file: {config_filename}, using profile '{profile}'");
         trace!("{config}");
+        check_config(&config, &description.pins)?;
         hw.apply_config(&config, |bcm_pin_number, level| {
             info!("Pin #{bcm_pin_number} changed level to '{level}'")
         })?;
         trace!("Configuration applied to hardware");
     };
 
-    // Then listen for remote connections and "serve" them
-    listen(info_path, hw).await
+    // Then listen for remote connections and "serve" them, until asked to stop. Dropping the
+    // hardware when stopped puts the pins into their safe state
+    tokio::select! {
+        result = listen(info_path, secret_key, hw, |node_id: &NodeId| {
+            client_access(&allow_list, unknown_access, node_id)
+        }) => result,
+        result = terminated() => {
+            info!("Stopping");
+            Ok(result?)
+        }
+    }
+}
+
+/// Wait until piglet is asked to stop, by SIGTERM (as the service manager does) or Ctrl-C
+async fn terminated() -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let mut sigterm = signal(SignalKind::terminate())?;
+        tokio::select! {
+            _ = sigterm.recv() => Ok(()),
+            result = tokio::signal::ctrl_c() => result,
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await
 }
 
 /// CHeck that this is the only instance of piglet running, both user process or system process
@@ -136,6 +239,40 @@ fn check_unique(exec_path: &Path) -> anyhow::Result<PathBuf> {
     Ok(info_path)
 }
 
+/// Load the allow-list of clients from `allow_path`, adding those given with `--allow`
+fn load_allow_list(allow_path: &Path, matches: &ArgMatches) -> anyhow::Result<AllowList> {
+    let mut allow_list = AllowList::load(allow_path)?;
+    for nodeid in matches.get_many::<String>("allow").unwrap_or_default() {
+        allow_list.add(
+            NodeId::from_str(nodeid.trim())
+                .with_context(|| format!("Invalid nodeid '{nodeid}' to allow"))?,
+        );
+    }
+    if allow_list.is_empty() {
+        warn!("No clients in the allow-list at {allow_path:?}, any client that has the nodeid can control the hardware");
+    } else {
+        info!(
+            "{} client(s) allowed to control the hardware",
+            allow_list.len()
+        );
+    }
+    Ok(allow_list)
+}
+
+/// The access the client with `node_id` has - with no allow-list every client has control,
+/// otherwise clients not in it get `unknown_access`, `None` meaning they are rejected
+fn client_access(
+    allow_list: &AllowList,
+    unknown_access: Option<ClientAccess>,
+    node_id: &NodeId,
+) -> Option<ClientAccess> {
+    if allow_list.is_empty() || allow_list.contains(node_id) {
+        Some(ClientAccess::Control)
+    } else {
+        unknown_access
+    }
+}
+
 /// Setup logging with the requested verbosity level - or default if none was specified
 fn setup_logging(matches: &ArgMatches) {
     let default: Directive = LevelFilter::from_level(Level::ERROR).into();
@@ -175,6 +312,34 @@ fn get_matches() -> ArgMatches {
             .conflicts_with("install"),
     );
 
+    let app = app.arg(
+        Arg::new("regenerate-key")
+            .long("regenerate-key")
+            .action(clap::ArgAction::SetTrue)
+            .help("Generate a new key, and so a new nodeid, replacing the one kept between runs"),
+    );
+
+    let app = app.arg(
+        Arg::new("allow")
+            .short('a')
+            .long("allow")
+            .num_args(1)
+            .number_of_values(1)
+            .value_name("NODEID")
+            .action(clap::ArgAction::Append)
+            .help("Allow the client with NODEID to control the hardware, in addition to those in 'piglet.allow'"),
+    );
+
+    let app = app.arg(
+        Arg::new("unknown-clients")
+            .long("unknown-clients")
+            .num_args(1)
+            .number_of_values(1)
+            .value_name("POLICY")
+            .value_parser(PossibleValuesParser::new(UNKNOWN_CLIENTS))
+            .help("What to do with clients not in the allow-list, 'reject' (default) or give them 'read-only' access"),
+    );
+
     let app = app.arg(
         Arg::new("verbosity")
             .short('v')
@@ -187,21 +352,61 @@ fn get_matches() -> ArgMatches {
             ),
     );
 
+    let app = app.arg(
+        Arg::new("backend")
+            .short('b')
+            .long("backend")
+            .num_args(1)
+            .number_of_values(1)
+            .value_name("BACKEND")
+            .value_parser(PossibleValuesParser::new(Backend::names()))
+            .help("Hardware backend to use, 'auto' (default) uses the Pi's GPIO if running on one"),
+    );
+
+    let app = app.arg(
+        Arg::new("stimulus")
+            .short('s')
+            .long("stimulus")
+            .num_args(1)
+            .number_of_values(1)
+            .value_name("STIMULUS_FILE")
+            .help("Path of a stimulus file with waveforms to drive the inputs of the fake backend"),
+    );
+
+    let app = app.arg(
+        Arg::new("profile")
+            .short('p')
+            .long("profile")
+            .num_args(1)
+            .number_of_values(1)
+            .value_name("PROFILE")
+            .requires("config-file")
+            .help("Name of the profile in the config file to apply, instead of its default"),
+    );
+
     let app = app.arg(
         Arg::new("config-file")
             .num_args(0..)
-            .help("Path of a '.pigg' config file to load"),
+            .help("Path of a '.pigg' (JSON) or '.yaml' config file to load"),
     );
 
     app.get_matches()
 }
 
-/// Listen for an incoming iroh-net connection and apply any config changes received, and
-/// send to GUI over the connection any input level changes.
+/// Listen for incoming iroh-net connections and serve each client concurrently, applying the
+/// config changes received from the client with control of the hardware and sending all of them
+/// any input level changes. Connections from clients that `access` gives no access to are
+/// closed, and read-only clients can only observe the hardware.
 /// This is adapted from the iroh-net example with help from the iroh community
-async fn listen(info_path: &Path, mut hardware: impl Hardware) -> anyhow::Result<()> {
-    let secret_key = SecretKey::generate();
-
+async fn listen<A>(
+    info_path: &Path,
+    secret_key: SecretKey,
+    hardware: HW,
+    access: A,
+) -> anyhow::Result<()>
+where
+    A: Fn(&NodeId) -> Option<ClientAccess>,
+{
     // Build a `Endpoint`, which uses PublicKeys as node identifiers, uses QUIC for directly
     // connecting to other nodes, and uses the relay protocol and relay servers to holepunch direct
     // connections between nodes when there are NATs or firewalls preventing direct connections.
@@ -245,56 +450,350 @@ async fn listen(info_path: &Path, mut hardware: impl Hardware) -> anyhow::Result
     // write the info about the node to the info_path file for use in piggui
     write_info_file(info_path, &nodeid, &local_addrs, &relay_url)?;
 
+    let hardware = Arc::new(tokio::sync::Mutex::new(hardware));
+    let clients = SharedClients::default();
+    let mut next_id = 0;
+
     loop {
         // accept incoming connections, returns a normal QUIC connection
         if let Some(connecting) = endpoint.accept().await {
-            let connection = connecting.await?;
-            let node_id = iroh_net::endpoint::get_remote_node_id(&connection)?;
-            info!("New connection from nodeid: '{node_id}'",);
-
-            let mut gui_sender = connection.open_uni().await?;
-
-            trace!("Sending hardware description");
-            let desc = hardware.description()?;
-            let message = serde_json::to_string(&desc)?;
-            gui_sender.write_all(message.as_bytes()).await?;
-            gui_sender.finish().await?;
-
-            loop {
-                trace!("waiting for connection");
-                match connection.accept_uni().await {
-                    Ok(mut config_receiver) => {
-                        let connection_clone = connection.clone();
-                        trace!("Connected, waiting for message");
-                        let payload = config_receiver.read_to_end(4096).await?;
-
-                        if !payload.is_empty() {
-                            let content = String::from_utf8_lossy(&payload);
-                            if let Ok(config_message) = serde_json::from_str(&content) {
-                                if let Err(e) = apply_config_change(
-                                    &mut hardware,
-                                    config_message,
-                                    connection_clone,
-                                )
-                                .await
-                                {
-                                    error!("Error applying config to hw: {}", e);
-                                }
-                            } else {
-                                error!("Unknown message: {content}");
-                            };
-                        }
-                    }
-                    _ => {
-                        info!("Connection lost");
-                        break;
-                    }
+            let connection = match connecting.await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    error!("Could not accept connection: {e}");
+                    continue;
+                }
+            };
+            let node_id = match iroh_net::endpoint::get_remote_node_id(&connection) {
+                Ok(node_id) => node_id,
+                Err(e) => {
+                    error!("Rejected connection with no nodeid: {e}");
+                    connection.close(VarInt::from_u32(1), b"no nodeid");
+                    continue;
                 }
+            };
+            let Some(client_access) = access(&node_id) else {
+                warn!("Rejected connection from nodeid not in the allow-list: '{node_id}'");
+                connection.close(VarInt::from_u32(1), b"not in the allow-list");
+                continue;
+            };
+            info!("New connection from nodeid: '{node_id}', with {client_access:?} access");
+
+            let id = next_id;
+            next_id += 1;
+            let can_control = client_access == ClientAccess::Control;
+            let hardware = hardware.clone();
+            let clients = clients.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_client(id, can_control, connection, hardware, clients).await {
+                    error!("Error serving client '{node_id}': {e}");
+                }
+            });
+        }
+    }
+}
+
+/// Serve a client connected to piglet until it disconnects: make the protocol handshake, send
+/// it the hardware description, tell it whether it has control of the hardware or observes it,
+/// and apply the changes it sends while it has control, forwarding them to the other clients.
+/// If it had control when it left, the pins are put in their safe state and control passes to
+/// the next client that can have it
+async fn serve_client(
+    id: u64,
+    can_control: bool,
+    connection: Connection,
+    hardware: Arc<tokio::sync::Mutex<HW>>,
+    clients: SharedClients,
+) -> anyhow::Result<()> {
+    let Channel {
+        mut writer,
+        mut reader,
+        welcome,
+    } = Channel::accept(&connection).await?;
+    info!(
+        "Client #{id} talking protocol version {} in {}, with capabilities: {}",
+        welcome.version,
+        welcome.encoding,
+        welcome.capabilities.join(", ")
+    );
+
+    trace!("Sending hardware description");
+    let desc = hardware.lock().await.description()?;
+    writer.send(&desc).await?;
+
+    // Messages for the client are queued, so that they can be sent from any task or thread
+    let (sender, mut outgoing) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        while let Some(message) = outgoing.recv().await {
+            if let Err(e) = writer.send(&message).await {
+                error!("Error sending to client #{id}: {e}");
+                break;
+            }
+        }
+    });
+
+    let has_control = lock(&clients).join(id, can_control, sender.clone());
+    let _ = sender.send(Control(has_control));
+    if !has_control {
+        info!("Client #{id} is observing the hardware");
+        // Show the observer the config another client applied, with the current input levels
+        let hardware = hardware.lock().await;
+        let config = hardware.config().clone();
+        let levels = current_input_levels(&config, &*hardware);
+        let _ = sender.send(NewConfig(config));
+        send_input_levels(slice::from_ref(&sender), &levels);
+    }
+
+    let result = receive_changes(id, &mut reader, &sender, &hardware, &clients).await;
+
+    let had_control = lock(&clients).leave(id);
+    if had_control {
+        info!("Client #{id} with control of the hardware disconnected");
+        if let Err(e) = hardware.lock().await.apply_safe_state() {
+            error!("Error putting pins into their safe state: {e}");
+        }
+        let next_controller = lock(&clients).grant_control();
+        if let Some((next_id, next)) = next_controller {
+            info!("Client #{next_id} now has control of the hardware");
+            let _ = next.send(Control(true));
+        }
+    }
+
+    result
+}
+
+/// Receive the changes sent by client `id` with `reader` until it disconnects, applying them
+/// to the hardware if it has control of it. Each is acknowledged, or the error applying it
+/// returned, followed by the actual state of what it tried to change, using `sender`
+async fn receive_changes(
+    id: u64,
+    reader: &mut FrameReader,
+    sender: &ClientSender,
+    hardware: &tokio::sync::Mutex<HW>,
+    clients: &SharedClients,
+) -> anyhow::Result<()> {
+    loop {
+        trace!("Waiting for message");
+        let request = match reader.receive::<Request>().await {
+            Ok(Some(request)) => request,
+            Ok(None) => {
+                info!("Connection closed");
+                return Ok(());
+            }
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                error!("Unknown message: {e}");
+                continue;
+            }
+            Err(e) => {
+                info!("Connection lost: {e}");
+                return Ok(());
+            }
+        };
+
+        handle_request(id, request, &mut *hardware.lock().await, sender, clients);
+    }
+}
+
+/// Apply the change in `request` from client `id` to the hardware if it has control of it, and
+/// forward it to the other clients. The request is acknowledged using `sender`, or it is told
+/// the error, followed by the actual state of what it tried to change
+fn handle_request(
+    id: u64,
+    request: Request,
+    hardware: &mut HW,
+    sender: &ClientSender,
+    clients: &SharedClients,
+) {
+    let has_control = lock(clients).has_control(id);
+    if !has_control {
+        warn!("Change from client #{id}, which does not have control, refused");
+        let _ = sender.send(Failed(
+            request.id,
+            "Another client has control of the hardware".into(),
+        ));
+        if let Some(actual) = actual_state(&request.message, hardware.config()) {
+            let _ = sender.send(actual);
+        }
+        return;
+    }
+
+    let change = request.message.clone();
+    match apply_config_change(hardware, request.message, sender, clients) {
+        Ok(()) => {
+            let _ = sender.send(Ack(request.id));
+            if is_forwarded(&change) {
+                let levels = match &change {
+                    NewConfig(config) | SwitchProfile(_, config) => {
+                        current_input_levels(config, hardware)
+                    }
+                    _ => vec![],
+                };
+                let others = lock(clients).others(id);
+                forward_change(&others, change, &levels);
+            }
+        }
+        Err(e) => {
+            error!("Error applying config to hw: {e}");
+            let _ = sender.send(Failed(request.id, e.to_string()));
+            if let Some(actual) = actual_state(&change, hardware.config()) {
+                let _ = sender.send(actual);
+            }
+        }
+    }
+}
+
+/// The state of the hardware that a `change` which failed or was refused tried to change, from
+/// the `config` applied to it, for the client that made it to show instead of the change. A bus
+/// that is not configured is only in the whole config, which is sent instead. Requests that do
+/// not change the hardware's state, such as transfers, have none
+fn actual_state(
+    change: &HardwareConfigMessage,
+    config: &HardwareConfig,
+) -> Option<HardwareConfigMessage> {
+    let pin_function = |bcm_pin_number: &BCMPinNumber| {
+        config
+            .pins
+            .get(bcm_pin_number)
+            .cloned()
+            .unwrap_or(PinFunction::None)
+    };
+    match change {
+        NewConfig(_) | SwitchProfile(..) => Some(NewConfig(config.clone())),
+        NewPinConfig(bcm_pin_number, _) => {
+            Some(NewPinConfig(*bcm_pin_number, pin_function(bcm_pin_number)))
+        }
+        IOLevelChanged(bcm_pin_number, _) => match pin_function(bcm_pin_number) {
+            PinFunction::Output(Some(level)) => {
+                Some(IOLevelChanged(*bcm_pin_number, LevelChange::new(level)))
             }
+            // the level is not known, so show the pin as it was configured
+            pin_function => Some(NewPinConfig(*bcm_pin_number, pin_function)),
+        },
+        NewSPIConfig(bus, _) => match config.spi.get(bus) {
+            Some(spi_config) => Some(NewSPIConfig(*bus, *spi_config)),
+            None => Some(NewConfig(config.clone())),
+        },
+        NewUARTConfig(_) => match config.uart {
+            Some(uart_config) => Some(NewUARTConfig(uart_config)),
+            None => Some(NewConfig(config.clone())),
+        },
+        _ => None,
+    }
+}
+
+/// True if `change` is a change to the config of the hardware that the clients other than the
+/// one that made it should be shown
+fn is_forwarded(change: &HardwareConfigMessage) -> bool {
+    matches!(
+        change,
+        NewConfig(_)
+            | SwitchProfile(..)
+            | NewPinConfig(..)
+            | IOLevelChanged(..)
+            | NewSPIConfig(..)
+            | NewUARTConfig(_)
+    )
+}
+
+/// Send a change to the config of the hardware to the clients observing it, followed by the
+/// input `levels` of the pins it configured
+fn forward_change(
+    observers: &[ClientSender],
+    change: HardwareConfigMessage,
+    levels: &[(BCMPinNumber, PinLevel)],
+) {
+    for observer in observers {
+        let _ = observer.send(change.clone());
+    }
+    send_input_levels(observers, levels);
+}
+
+/// The clients connected to piglet, in the order they connected, and which one of them holds
+/// the control lease - the only client whose changes are applied to the hardware
+#[derive(Debug)]
+struct Clients<C = ClientSender> {
+    connected: Vec<(u64, bool, C)>,
+    controller: Option<u64>,
+}
+
+type SharedClients = Arc<Mutex<Clients>>;
+
+/// Queues messages to be sent to a client
+type ClientSender = mpsc::UnboundedSender<HardwareConfigMessage>;
+
+impl<C> Default for Clients<C> {
+    fn default() -> Self {
+        Clients {
+            connected: vec![],
+            controller: None,
         }
     }
 }
 
+impl<C: Clone> Clients<C> {
+    /// Add client `id` that messages are sent to with `sender`, giving it control of the
+    /// hardware if it `can_control` it and no other client has control. Return true if it has
+    /// control
+    fn join(&mut self, id: u64, can_control: bool, sender: C) -> bool {
+        self.connected.push((id, can_control, sender));
+        if can_control && self.controller.is_none() {
+            self.controller = Some(id);
+        }
+        self.has_control(id)
+    }
+
+    /// Remove client `id`, returning true if it had control of the hardware
+    fn leave(&mut self, id: u64) -> bool {
+        self.connected.retain(|(client_id, _, _)| *client_id != id);
+        if self.controller == Some(id) {
+            self.controller = None;
+            return true;
+        }
+        false
+    }
+
+    /// If no client has control, give it to the client that has been connected the longest of
+    /// those that can control the hardware, and return its id and sender
+    fn grant_control(&mut self) -> Option<(u64, C)> {
+        if self.controller.is_some() {
+            return None;
+        }
+        let (id, _, sender) = self
+            .connected
+            .iter()
+            .find(|(_, can_control, _)| *can_control)?;
+        self.controller = Some(*id);
+        Some((*id, sender.clone()))
+    }
+
+    /// True if client `id` has control of the hardware
+    fn has_control(&self, id: u64) -> bool {
+        self.controller == Some(id)
+    }
+
+    /// The senders of all the clients
+    fn senders(&self) -> Vec<C> {
+        self.connected
+            .iter()
+            .map(|(_, _, sender)| sender.clone())
+            .collect()
+    }
+
+    /// The senders of all the clients except `id`
+    fn others(&self, id: u64) -> Vec<C> {
+        self.connected
+            .iter()
+            .filter(|(client_id, _, _)| *client_id != id)
+            .map(|(_, _, sender)| sender.clone())
+            .collect()
+    }
+}
+
+/// Lock the [Clients], even if another thread panicked while holding the lock
+fn lock(clients: &SharedClients) -> MutexGuard<'_, Clients> {
+    clients.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
 /// Write info about the running piglet to the info file
 fn write_info_file(
     info_path: &Path,
@@ -310,98 +809,159 @@ fn write_info_file(
     Ok(())
 }
 
-/// Apply a config change to the hardware
-/// NOTE: Initially the callback to Config/PinConfig change was async, and that compiles and runs
-/// but wasn't working - so this uses a sync callback again to fix that, and an async version of
-/// send_input_level() for use directly from the async context
-async fn apply_config_change(
-    hardware: &mut impl Hardware,
+/// Apply a config change to the hardware, sending any response with `sender` and input level
+/// changes to all the `clients`
+fn apply_config_change(
+    hardware: &mut HW,
     config_change: HardwareConfigMessage,
-    connection: Connection,
+    sender: &ClientSender,
+    clients: &SharedClients,
 ) -> anyhow::Result<()> {
+    let cl = clients.clone();
+    let input_level_changed = move |bcm, level| broadcast_input_level(&cl, bcm, level);
     match config_change {
         NewConfig(config) => {
-            let cc = connection.clone();
+            check_config(&config, &hardware.description()?.pins)?;
             info!("New config applied");
-            hardware.apply_config(&config, move |bcm, level| {
-                let cc = connection.clone();
-                let _ = send_input_level(cc, bcm, level);
-            })?;
+            hardware.apply_config(&config, input_level_changed)?;
+
+            if let Some(uart_config) = &config.uart {
+                if let Err(e) = apply_uart_config(hardware, uart_config, clients) {
+                    let _ = sender.send(UARTEvent(UARTError(e.to_string())));
+                }
+            }
 
-            let _ = send_current_input_states(cc, &config, hardware).await;
+            let levels = current_input_levels(&config, hardware);
+            send_input_levels(slice::from_ref(sender), &levels);
+        }
+        SwitchProfile(name, profile) => {
+            hardware.switch_profile(&profile, input_level_changed)?;
+            info!("Switched to profile '{name}'");
+
+            if let Some(uart_config) = &profile.uart {
+                if let Err(e) = apply_uart_config(hardware, uart_config, clients) {
+                    let _ = sender.send(UARTEvent(UARTError(e.to_string())));
+                }
+            }
+
+            let levels = current_input_levels(&profile, hardware);
+            send_input_levels(slice::from_ref(sender), &levels);
         }
         NewPinConfig(bcm, pin_function) => {
             info!("New pin config for pin #{bcm}: {pin_function}");
-            let _ = hardware.apply_pin_config(bcm, &pin_function, move |bcm, level| {
-                let cc = connection.clone();
-                let _ = send_input_level(cc, bcm, level);
-            });
+            hardware.apply_pin_config(bcm, &pin_function, input_level_changed)?;
         }
         IOLevelChanged(bcm, level_change) => {
             trace!("Pin #{bcm} Output level change: {level_change:?}");
-            let _ = hardware.set_output_level(bcm, level_change.new_level);
+            hardware.set_output_level(bcm, level_change.new_level)?;
+        }
+        I2CRequest(request) => {
+            trace!("I2C request: {request:?}");
+            let response = hardware.i2c_request(&request);
+            trace!("I2C response: {response}");
+            let _ = sender.send(I2CResponse(response));
+        }
+        NewSPIConfig(bus, spi_config) => {
+            info!("New config for {bus}: {spi_config:?}");
+            hardware.apply_spi_config(bus, &spi_config)?;
+        }
+        SPITransfer(transfer) => {
+            trace!("SPI transfer: {transfer:?}");
+            let response = hardware.spi_request(&transfer);
+            trace!("SPI response: {response}");
+            let _ = sender.send(SPIResponse(response));
+        }
+        NewUARTConfig(uart_config) => {
+            info!("New UART config: {uart_config}");
+            apply_uart_config(hardware, &uart_config, clients)?;
+        }
+        UARTWrite(data) => {
+            trace!("UART write: {data:?}");
+            hardware.uart_write(&data)?;
+        }
+        unexpected @ (I2CResponse(_) | SPIResponse(_) | UARTEvent(_) | Control(_) | Ack(_)
+        | Failed(..)) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Unexpected message received from GUI: {unexpected:?}"),
+            )
+            .into());
         }
     }
 
     Ok(())
 }
 
-/// Send the current input state for all inputs configured in the config
-async fn send_current_input_states(
-    connection: Connection,
-    config: &HardwareConfig,
-    hardware: &impl Hardware,
-) -> anyhow::Result<()> {
-    // Send initial levels
-    for (bcm_pin_number, pin_function) in &config.pins {
-        if let PinFunction::Input(_pullup) = pin_function {
-            // Update UI with initial state
-            if let Ok(initial_level) = hardware.get_input_level(*bcm_pin_number) {
-                let _ = send_input_level_async(connection.clone(), *bcm_pin_number, initial_level)
-                    .await;
-            }
-        }
+/// Check that `config` can be applied to the hardware with `pins` before applying it, logging
+/// each of the problems found if it cannot
+fn check_config(config: &HardwareConfig, pins: &PinDescriptionSet) -> io::Result<()> {
+    let problems = validate(config, pins);
+    if problems.is_empty() {
+        return Ok(());
+    }
+    for problem in &problems {
+        error!("{problem}");
     }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!(
+            "Config not applied, it has {} problem(s) with this hardware",
+            problems.len()
+        ),
+    ))
+}
 
-    Ok(())
+/// Open the UART with `uart_config`, streaming the bytes received on it to all the `clients`
+fn apply_uart_config(
+    hardware: &mut impl Hardware,
+    uart_config: &UARTConfig,
+    clients: &SharedClients,
+) -> io::Result<()> {
+    let cl = clients.clone();
+    hardware.apply_uart_config(uart_config, move |data| {
+        trace!("UART received: {data:?}");
+        let senders = lock(&cl).senders();
+        for sender in senders {
+            let _ = sender.send(UARTEvent(UARTReceived(data.clone())));
+        }
+    })
 }
 
-/// Send a detected input level change back to the GUI using `connection` [Connection],
-/// timestamping with the current time in Utc
-async fn send_input_level_async(
-    connection: Connection,
-    bcm: BCMPinNumber,
-    level: PinLevel,
-) -> anyhow::Result<()> {
-    let level_change = LevelChange::new(level);
-    trace!("Pin #{bcm} Input level change: {level_change:?}");
-    let hardware_event = IOLevelChanged(bcm, level_change);
-    let message = serde_json::to_string(&hardware_event)?;
-    send(connection, message).await
+/// The current level of all the inputs configured in the config. These are read before sending
+/// them, as the hardware cannot be shared with the tasks serving other clients
+fn current_input_levels(
+    config: &HardwareConfig,
+    hardware: &impl Hardware,
+) -> Vec<(BCMPinNumber, PinLevel)> {
+    config
+        .pins
+        .iter()
+        .filter(|(_, pin_function)| matches!(pin_function, PinFunction::Input(..)))
+        .filter_map(|(bcm_pin_number, _)| {
+            hardware
+                .get_input_level(*bcm_pin_number)
+                .ok()
+                .map(|level| (*bcm_pin_number, level))
+        })
+        .collect()
 }
 
-/// Send a detected input level change back to the GUI using `connection` [Connection],
-/// timestamping with the current time in Utc
-fn send_input_level(
-    connection: Connection,
-    bcm: BCMPinNumber,
-    level: PinLevel,
-) -> anyhow::Result<()> {
-    let level_change = LevelChange::new(level);
-    trace!("Pin #{bcm} Input level change: {level_change:?}");
-    let hardware_event = IOLevelChanged(bcm, level_change);
-    let message = serde_json::to_string(&hardware_event)?;
-    let rt = tokio::runtime::Builder::new_current_thread()
-        .enable_all()
-        .build()?;
-    rt.block_on(send(connection, message))
-}
-
-async fn send(connection: Connection, message: String) -> anyhow::Result<()> {
-    let mut gui_sender = connection.open_uni().await?;
-    gui_sender.write_all(message.as_bytes()).await?;
-    gui_sender.finish().await?;
-    Ok(())
+/// Send input `levels` with each of `senders`, such as the initial state of the inputs,
+/// timestamping them with the current time in Utc
+fn send_input_levels(senders: &[ClientSender], levels: &[(BCMPinNumber, PinLevel)]) {
+    for (bcm_pin_number, level) in levels {
+        let level_change = LevelChange::new(*level);
+        for sender in senders {
+            let _ = sender.send(IOLevelChanged(*bcm_pin_number, level_change.clone()));
+        }
+    }
+}
+
+/// Send a detected input level change to all the `clients` connected
+fn broadcast_input_level(clients: &SharedClients, bcm: BCMPinNumber, level: PinLevel) {
+    trace!("Pin #{bcm} Input level change: {level}");
+    let senders = lock(clients).senders();
+    send_input_levels(&senders, &[(bcm, level)]);
 }
 
 fn get_service_manager() -> Result<Box<dyn ServiceManager>, io::Error> {
@@ -480,10 +1040,29 @@ mod test {
     use std::path::PathBuf;
     use std::str::FromStr;
 
+    use iroh_net::key::SecretKey;
     use iroh_net::relay::RelayUrl;
     use iroh_net::NodeId;
     use tempfile::tempdir;
 
+    use crate::hw::config::HardwareConfig;
+    use crate::hw::pin_function::PinFunction;
+    use crate::hw::spi::{SPIBus, SPIConfig, SPIMode};
+    use crate::hw::HardwareConfigMessage::{
+        IOLevelChanged, NewConfig, NewPinConfig, NewSPIConfig, UARTWrite,
+    };
+    use crate::hw::LevelChange;
+    use crate::identity::AllowList;
+    use crate::{actual_state, ClientAccess, Clients};
+    #[cfg(feature = "fake_hw")]
+    use crate::{
+        handle_request, hw::backend::Backend, hw::backend::HW, hw::Hardware,
+        hw::HardwareConfigMessage::Ack, hw::HardwareConfigMessage::Failed, hw::Request, lock,
+        SharedClients,
+    };
+    #[cfg(feature = "fake_hw")]
+    use tokio::sync::mpsc;
+
     #[test]
     fn write_info_file() {
         let output_dir = tempdir().expect("Could not create a tempdir").into_path();
@@ -512,4 +1091,148 @@ mod test {
         assert!(super::write_info_file(&test_file, &nodeid, local_addr, &relay_url).is_err());
         assert!(!test_file.exists(), "File was created!");
     }
+
+    #[test]
+    fn client_access() {
+        let allowed = SecretKey::generate().public();
+        let unknown = SecretKey::generate().public();
+        let mut allow_list = AllowList::default();
+        assert_eq!(
+            super::client_access(&allow_list, None, &unknown),
+            Some(ClientAccess::Control)
+        );
+
+        allow_list.add(allowed);
+        assert_eq!(
+            super::client_access(&allow_list, None, &allowed),
+            Some(ClientAccess::Control)
+        );
+        assert_eq!(super::client_access(&allow_list, None, &unknown), None);
+        assert_eq!(
+            super::client_access(&allow_list, Some(ClientAccess::ReadOnly), &unknown),
+            Some(ClientAccess::ReadOnly)
+        );
+    }
+
+    #[test]
+    fn first_client_that_can_control_has_control() {
+        let mut clients: Clients<()> = Clients::default();
+        assert!(!clients.join(0, false, ()));
+        assert!(clients.join(1, true, ()));
+        assert!(!clients.join(2, true, ()));
+        assert!(clients.has_control(1));
+        assert_eq!(clients.others(1).len(), 2);
+        assert_eq!(clients.senders().len(), 3);
+    }
+
+    #[test]
+    fn control_passes_when_controller_leaves() {
+        let mut clients: Clients<()> = Clients::default();
+        clients.join(0, true, ());
+        clients.join(1, false, ());
+        clients.join(2, true, ());
+        clients.join(3, true, ());
+
+        assert!(!clients.leave(2));
+        assert!(clients.grant_control().is_none());
+        assert!(clients.leave(0));
+        assert_eq!(clients.grant_control(), Some((3, ())));
+        assert!(clients.has_control(3));
+
+        assert!(clients.leave(3));
+        assert!(clients.grant_control().is_none());
+        assert!(!clients.has_control(1));
+    }
+
+    #[test]
+    fn actual_state_of_failed_change() {
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+
+        let actual = actual_state(&NewPinConfig(17, PinFunction::None), &config);
+        assert!(matches!(
+            actual,
+            Some(NewPinConfig(17, PinFunction::Output(Some(true))))
+        ));
+        let actual = actual_state(&NewPinConfig(4, PinFunction::Output(None)), &config);
+        assert!(matches!(actual, Some(NewPinConfig(4, PinFunction::None))));
+        let actual = actual_state(&NewConfig(HardwareConfig::default()), &config);
+        assert!(matches!(actual, Some(NewConfig(c)) if c.pins.len() == 1));
+        assert!(actual_state(&UARTWrite(vec![0]), &config).is_none());
+    }
+
+    #[cfg(feature = "fake_hw")]
+    #[test]
+    fn change_from_observer_refused() {
+        let mut hw = HW::new(Backend::Fake);
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+        hw.apply_config(&config, |_, _| {})
+            .expect("Could not apply config");
+
+        let clients = SharedClients::default();
+        let (controller, mut controller_replies) = mpsc::unbounded_channel();
+        let (observer, mut observer_replies) = mpsc::unbounded_channel();
+        lock(&clients).join(0, true, controller.clone());
+        lock(&clients).join(1, false, observer.clone());
+
+        let request = Request {
+            id: 7,
+            message: NewPinConfig(17, PinFunction::None),
+        };
+        handle_request(1, request, &mut hw, &observer, &clients);
+        assert!(matches!(observer_replies.try_recv(), Ok(Failed(7, _))));
+        assert!(matches!(
+            observer_replies.try_recv(),
+            Ok(NewPinConfig(17, PinFunction::Output(Some(true))))
+        ));
+        assert!(controller_replies.try_recv().is_err());
+        assert!(hw.config().pins.contains_key(&17));
+
+        let request = Request {
+            id: 3,
+            message: NewPinConfig(17, PinFunction::None),
+        };
+        handle_request(0, request, &mut hw, &controller, &clients);
+        assert!(matches!(controller_replies.try_recv(), Ok(Ack(3))));
+        assert!(matches!(
+            observer_replies.try_recv(),
+            Ok(NewPinConfig(17, PinFunction::None))
+        ));
+    }
+
+    #[test]
+    fn actual_state_of_failed_level_change() {
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+        config.pins.insert(22, PinFunction::Output(None));
+
+        let actual = actual_state(&IOLevelChanged(17, LevelChange::new(false)), &config);
+        assert!(matches!(actual, Some(IOLevelChanged(17, level)) if level.new_level));
+        // with no level known, the pin is shown as configured
+        let actual = actual_state(&IOLevelChanged(22, LevelChange::new(true)), &config);
+        assert!(matches!(
+            actual,
+            Some(NewPinConfig(22, PinFunction::Output(None)))
+        ));
+        let actual = actual_state(&IOLevelChanged(4, LevelChange::new(true)), &config);
+        assert!(matches!(actual, Some(NewPinConfig(4, PinFunction::None))));
+    }
+
+    #[test]
+    fn actual_state_of_failed_bus_config() {
+        let mut config = HardwareConfig::default();
+        let spi_config = SPIConfig {
+            mode: SPIMode::Mode3,
+            clock_speed: 500_000,
+        };
+        config.spi.insert(SPIBus::SPI0, spi_config);
+
+        let change = NewSPIConfig(SPIBus::SPI0, SPIConfig::default());
+        let actual = actual_state(&change, &config);
+        assert!(matches!(actual, Some(NewSPIConfig(SPIBus::SPI0, c)) if c == spi_config));
+        let change = NewSPIConfig(SPIBus::SPI1, SPIConfig::default());
+        let actual = actual_state(&change, &config);
+        assert!(matches!(actual, Some(NewConfig(c)) if !c.spi.contains_key(&SPIBus::SPI1)));
+    }
 }
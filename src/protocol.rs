@@ -0,0 +1,447 @@
+//! The protocol piggui and piglet talk over an iroh-net connection. The client opens one
+//! bidirectional stream for the whole connection and sends a [Hello] with the protocol versions,
+//! encodings and capabilities it supports, and piglet replies with a [HelloReply] choosing from
+//! them, or refusing the client. After that each message is sent as a frame: the length of the
+//! message as a 4 byte big-endian number, followed by the message in the chosen [Encoding].
+//! The handshake is always in JSON, so that any version of either side can read it.
+//! The first message piglet sends is the [HardwareDescription](crate::hw::HardwareDescription),
+//! then it sends [HardwareConfigMessage](crate::hw::HardwareConfigMessage)s, and the client
+//! sends each change it makes as a [Request](crate::hw::Request) that piglet acknowledges, or
+//! replies to with the error applying it.
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+
+use iroh_net::endpoint::{Connection, RecvStream, SendStream};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// The newest version of the protocol this build supports
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// The versions of the protocol this build can talk
+const SUPPORTED_VERSIONS: [u32; 1] = [PROTOCOL_VERSION];
+
+/// The largest message accepted, so that a bad length cannot make us allocate without limit
+pub const MAX_FRAME_LENGTH: usize = 16 * 1024 * 1024;
+
+/// The size of the length that precedes each message in a frame
+const LENGTH_SIZE: usize = 4;
+
+/// The optional parts of the protocol this build supports
+pub const CAPABILITIES: [&str; 5] = ["i2c", "spi", "uart", "profiles", "control"];
+
+/// How the messages in frames are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encoding {
+    /// Human-readable, as used by the config files
+    Json,
+    /// A compact binary encoding (MessagePack)
+    MessagePack,
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::Json => write!(f, "JSON"),
+            Encoding::MessagePack => write!(f, "MessagePack"),
+        }
+    }
+}
+
+impl Encoding {
+    /// The encodings supported, in order of preference
+    pub const ALL: [Encoding; 2] = [Encoding::MessagePack, Encoding::Json];
+
+    /// Encode `message`
+    pub fn encode<T: Serialize>(&self, message: &T) -> io::Result<Vec<u8>> {
+        match self {
+            Encoding::Json => serde_json::to_vec(message).map_err(io::Error::other),
+            // Fields are encoded with their names, as some are skipped when they have no value
+            Encoding::MessagePack => rmp_serde::to_vec_named(message).map_err(io::Error::other),
+        }
+    }
+
+    /// Decode a message from `bytes`
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> io::Result<T> {
+        match self {
+            Encoding::Json => serde_json::from_slice(bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Encoding::MessagePack => rmp_serde::from_slice(bytes)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+/// What a client supports, sent to piglet to start the handshake
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hello {
+    pub versions: Vec<u32>,
+    /// In order of the client's preference
+    pub encodings: Vec<Encoding>,
+    pub capabilities: Vec<String>,
+}
+
+impl Default for Hello {
+    /// What this build supports
+    fn default() -> Self {
+        Hello {
+            versions: SUPPORTED_VERSIONS.to_vec(),
+            encodings: Encoding::ALL.to_vec(),
+            capabilities: CAPABILITIES.iter().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+impl Hello {
+    /// Choose how to talk to the client that sent this, from what this build supports: the
+    /// newest version both support, the encoding the client prefers and the capabilities both
+    /// have. Return why not if there is no version or encoding in common
+    pub fn negotiate(&self) -> Result<Welcome, String> {
+        let version = self
+            .versions
+            .iter()
+            .filter(|version| SUPPORTED_VERSIONS.contains(version))
+            .max()
+            .ok_or(format!(
+                "No protocol version in common, client supports {:?} and piglet {:?}",
+                self.versions, SUPPORTED_VERSIONS
+            ))?;
+        let encoding = self
+            .encodings
+            .iter()
+            .find(|encoding| Encoding::ALL.contains(encoding))
+            .ok_or(format!(
+                "No encoding in common, client supports {:?}",
+                self.encodings
+            ))?;
+        let capabilities = self
+            .capabilities
+            .iter()
+            .filter(|capability| CAPABILITIES.contains(&capability.as_str()))
+            .cloned()
+            .collect();
+        Ok(Welcome {
+            version: *version,
+            encoding: *encoding,
+            capabilities,
+        })
+    }
+}
+
+/// How piglet and a client talk, as chosen by piglet in the handshake
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Welcome {
+    pub version: u32,
+    pub encoding: Encoding,
+    pub capabilities: Vec<String>,
+}
+
+/// piglet's reply to a [Hello]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HelloReply {
+    Welcome(Welcome),
+    /// The client cannot talk to this piglet, for the reason given
+    Refused(String),
+}
+
+/// Put `message` in a frame, preceded by its length
+pub fn frame(message: &[u8]) -> io::Result<Vec<u8>> {
+    if message.len() > MAX_FRAME_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Message of {} bytes is larger than the maximum of {MAX_FRAME_LENGTH}",
+                message.len()
+            ),
+        ));
+    }
+    let mut frame = Vec::with_capacity(LENGTH_SIZE + message.len());
+    frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    frame.extend_from_slice(message);
+    Ok(frame)
+}
+
+/// The bytes received on a stream, from which complete frames are taken as they arrive
+#[derive(Debug, Default)]
+pub struct FrameBuffer {
+    bytes: Vec<u8>,
+}
+
+impl FrameBuffer {
+    /// Add bytes received
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Take the message of the next frame, if all of it has been received
+    pub fn next_message(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let Some(length) = self.bytes.first_chunk::<LENGTH_SIZE>() else {
+            return Ok(None);
+        };
+        let length = u32::from_be_bytes(*length) as usize;
+        if length > MAX_FRAME_LENGTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Frame of {length} bytes is larger than the maximum of {MAX_FRAME_LENGTH}"),
+            ));
+        }
+        if self.bytes.len() < LENGTH_SIZE + length {
+            return Ok(None);
+        }
+        let message = self.bytes[LENGTH_SIZE..LENGTH_SIZE + length].to_vec();
+        self.bytes.drain(..LENGTH_SIZE + length);
+        Ok(Some(message))
+    }
+}
+
+/// Sends messages as frames on the stream
+pub struct FrameWriter {
+    send: SendStream,
+    encoding: Encoding,
+}
+
+impl FrameWriter {
+    /// Send `message`
+    pub async fn send<T: Serialize>(&mut self, message: &T) -> io::Result<()> {
+        let frame = frame(&self.encoding.encode(message)?)?;
+        self.send.write_all(&frame).await?;
+        Ok(())
+    }
+}
+
+/// Receives messages sent as frames on the stream
+pub struct FrameReader {
+    recv: RecvStream,
+    buffer: FrameBuffer,
+    encoding: Encoding,
+}
+
+impl FrameReader {
+    /// Receive the next message, or `None` if the stream has been closed. A message that cannot
+    /// be decoded is an error of kind [io::ErrorKind::InvalidData], after which the next message
+    /// can still be received. This is cancel safe, so it can be used in `select!`
+    pub async fn receive<T: DeserializeOwned>(&mut self) -> io::Result<Option<T>> {
+        let mut chunk = [0u8; 4096];
+        loop {
+            if let Some(message) = self.buffer.next_message()? {
+                return self.encoding.decode(&message).map(Some);
+            }
+            match self.recv.read(&mut chunk).await? {
+                Some(length) => self.buffer.push(&chunk[..length]),
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+/// The streams piggui and piglet talk on, and how they talk, after the handshake
+pub struct Channel {
+    pub writer: FrameWriter,
+    pub reader: FrameReader,
+    pub welcome: Welcome,
+}
+
+impl Channel {
+    /// Open the stream to piglet on `connection`, and make the handshake with it
+    #[allow(dead_code)] // for piggui
+    pub async fn connect(connection: &Connection) -> io::Result<Self> {
+        let (send, recv) = connection.open_bi().await.map_err(io::Error::other)?;
+        let mut writer = FrameWriter {
+            send,
+            encoding: Encoding::Json,
+        };
+        let mut reader = FrameReader {
+            recv,
+            buffer: FrameBuffer::default(),
+            encoding: Encoding::Json,
+        };
+
+        writer.send(&Hello::default()).await?;
+        match reader.receive().await? {
+            Some(HelloReply::Welcome(welcome)) => {
+                writer.encoding = welcome.encoding;
+                reader.encoding = welcome.encoding;
+                Ok(Channel {
+                    writer,
+                    reader,
+                    welcome,
+                })
+            }
+            Some(HelloReply::Refused(reason)) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("piglet refused the connection: {reason}"),
+            )),
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Connection closed during the handshake",
+            )),
+        }
+    }
+
+    /// Accept the stream a client opens on `connection`, and make the handshake with it
+    #[allow(dead_code)] // for piglet
+    pub async fn accept(connection: &Connection) -> io::Result<Self> {
+        let (send, recv) = connection.accept_bi().await.map_err(io::Error::other)?;
+        let mut writer = FrameWriter {
+            send,
+            encoding: Encoding::Json,
+        };
+        let mut reader = FrameReader {
+            recv,
+            buffer: FrameBuffer::default(),
+            encoding: Encoding::Json,
+        };
+
+        let hello: Hello = reader.receive().await?.ok_or(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Connection closed during the handshake",
+        ))?;
+        match hello.negotiate() {
+            Ok(welcome) => {
+                writer.send(&HelloReply::Welcome(welcome.clone())).await?;
+                writer.encoding = welcome.encoding;
+                reader.encoding = welcome.encoding;
+                Ok(Channel {
+                    writer,
+                    reader,
+                    welcome,
+                })
+            }
+            Err(reason) => {
+                writer.send(&HelloReply::Refused(reason.clone())).await?;
+                Err(io::Error::new(io::ErrorKind::Unsupported, reason))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::config::{HardwareConfig, PinAnnotation};
+    use crate::hw::pin_function::PinFunction;
+    use crate::hw::HardwareConfigMessage;
+    use crate::protocol::{
+        frame, Encoding, FrameBuffer, Hello, CAPABILITIES, MAX_FRAME_LENGTH, PROTOCOL_VERSION,
+    };
+
+    /// A config larger than the 4 KiB messages used to be limited to
+    fn large_config() -> HardwareConfig {
+        let mut config = HardwareConfig::default();
+        for bcm_pin_number in 0..28 {
+            config
+                .pins
+                .insert(bcm_pin_number, PinFunction::Output(Some(true)));
+            config.annotations.insert(
+                bcm_pin_number,
+                PinAnnotation {
+                    label: format!("GPIO{bcm_pin_number}"),
+                    notes: "x".repeat(200),
+                    colour: None,
+                },
+            );
+        }
+        config
+    }
+
+    #[test]
+    fn encodings_round_trip() {
+        let message = HardwareConfigMessage::NewConfig(large_config());
+        for encoding in Encoding::ALL {
+            let bytes = encoding.encode(&message).expect("Could not encode");
+            assert!(bytes.len() > 4096);
+            let decoded: HardwareConfigMessage = encoding.decode(&bytes).expect("Could not decode");
+            // compared as JSON, in which the pins are in a stable order
+            assert_eq!(
+                Encoding::Json.encode(&decoded).expect("Could not encode"),
+                Encoding::Json.encode(&message).expect("Could not encode")
+            );
+        }
+    }
+
+    #[test]
+    fn message_pack_is_compact() {
+        let message = HardwareConfigMessage::NewConfig(large_config());
+        let json = Encoding::Json.encode(&message).expect("Could not encode");
+        let message_pack = Encoding::MessagePack
+            .encode(&message)
+            .expect("Could not encode");
+        assert!(message_pack.len() < json.len());
+    }
+
+    #[test]
+    fn invalid_message() {
+        let error = Encoding::Json
+            .decode::<HardwareConfigMessage>(b"{\"Unknown\":1}")
+            .expect_err("Invalid message was decoded");
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn frames_received_in_pieces() {
+        let mut bytes = frame(b"first").expect("Could not frame");
+        bytes.extend(frame(b"").expect("Could not frame"));
+        bytes.extend(frame(b"second").expect("Could not frame"));
+
+        let mut buffer = FrameBuffer::default();
+        let mut messages = vec![];
+        for byte in bytes {
+            buffer.push(&[byte]);
+            while let Some(message) = buffer.next_message().expect("Invalid frame") {
+                messages.push(message);
+            }
+        }
+        assert_eq!(
+            messages,
+            vec![b"first".to_vec(), vec![], b"second".to_vec()]
+        );
+    }
+
+    #[test]
+    fn frame_too_large() {
+        assert!(frame(&vec![0; MAX_FRAME_LENGTH + 1]).is_err());
+
+        let mut buffer = FrameBuffer::default();
+        buffer.push(&(MAX_FRAME_LENGTH as u32 + 1).to_be_bytes());
+        assert!(buffer.next_message().is_err());
+    }
+
+    #[test]
+    fn negotiate() {
+        let hello = Hello {
+            versions: vec![0, PROTOCOL_VERSION, PROTOCOL_VERSION + 1],
+            encodings: vec![Encoding::Json, Encoding::MessagePack],
+            capabilities: vec!["uart".into(), "teleport".into()],
+        };
+        let welcome = hello.negotiate().expect("Could not negotiate");
+        assert_eq!(welcome.version, PROTOCOL_VERSION);
+        assert_eq!(welcome.encoding, Encoding::Json);
+        assert_eq!(welcome.capabilities, vec!["uart".to_string()]);
+    }
+
+    #[test]
+    fn default_hello_negotiates_preferred() {
+        let welcome = Hello::default().negotiate().expect("Could not negotiate");
+        assert_eq!(welcome.version, PROTOCOL_VERSION);
+        assert_eq!(welcome.encoding, Encoding::MessagePack);
+        assert_eq!(welcome.capabilities.len(), CAPABILITIES.len());
+    }
+
+    #[test]
+    fn no_common_version() {
+        let hello = Hello {
+            versions: vec![PROTOCOL_VERSION + 1],
+            ..Default::default()
+        };
+        assert!(hello.negotiate().is_err());
+    }
+
+    #[test]
+    fn no_common_encoding() {
+        let hello = Hello {
+            encodings: vec![],
+            ..Default::default()
+        };
+        assert!(hello.negotiate().is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::hw::config::ConfigProblem;
 use crate::views::hardware_view::HardwareView;
 use crate::widgets::toast::{Manager, Status, Toast};
 use crate::Message;
@@ -9,6 +10,7 @@ pub enum ToastMessage {
     HardwareDetailsToast,
     UnsavedChangesToast,
     UnsavedChangesExitToast,
+    ConfigProblemsToast(Vec<ConfigProblem>),
     Close(usize),
     CloseLastToast,
     Timeout(f64),
@@ -61,6 +63,21 @@ impl ToastHandler {
                 self.set_pending_load(true);
             }
 
+            ToastMessage::ConfigProblemsToast(problems) => {
+                self.clear_toasts();
+                let problems: Vec<String> = problems.iter().map(ToString::to_string).collect();
+                self.push_toast(Toast {
+                    title: "Config Problems".into(),
+                    body: format!(
+                        "These parts of the config are not supported by the connected hardware \
+                        and were skipped:\n{}",
+                        problems.join("\n")
+                    ),
+                    status: Status::Danger,
+                });
+                self.showing_toast = true;
+            }
+
             ToastMessage::VersionToast => {
                 self.clear_toasts();
                 self.push_toast(Toast {
@@ -202,6 +219,24 @@ mod tests {
         assert!(toast_handler.get_toasts().is_empty());
     }
 
+    #[test]
+    fn test_config_problems_toast() {
+        let mut toast_handler = ToastHandler::new();
+
+        let problems = vec![ConfigProblem::UnknownPin(40)];
+        let _ = toast_handler.update(
+            ToastMessage::ConfigProblemsToast(problems),
+            &HardwareView::new(),
+        );
+
+        assert_eq!(toast_handler.get_toasts().len(), 1);
+        let toast = &toast_handler.get_toasts()[0];
+        assert_eq!(toast.title, "Config Problems");
+        assert!(toast
+            .body
+            .contains("There is no BCM pin #40 on this hardware"));
+    }
+
     #[test]
     fn test_toast_timeout() {
         let mut toast_handler = ToastHandler::new();
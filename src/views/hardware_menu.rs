@@ -74,6 +74,17 @@ pub fn item<'a>(
             .style(MENU_BUTTON_STYLE.get_button_style()),
     ));
 
+    if let Some(hat) = hardware_view.hat() {
+        if let Some(preset) = hat.preset() {
+            menu_items.push(Item::new(
+                Button::new(Text::new(format!("Use {} pin config", hat.product)))
+                    .on_press(Message::HatPresetSelected(preset))
+                    .width(Length::Fill)
+                    .style(MENU_BUTTON_STYLE.get_button_style()),
+            ));
+        }
+    }
+
     menu_items.push(Item::new(
         Button::new(Text::new("Show Hardware Details"))
             .on_press(Message::Toast(ToastMessage::HardwareDetailsToast))
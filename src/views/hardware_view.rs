@@ -4,33 +4,56 @@ use iced::alignment::Horizontal;
 use iced::futures::channel::mpsc::Sender;
 use iced::widget::tooltip::Position;
 use iced::widget::Tooltip;
-use iced::widget::{button, horizontal_space, pick_list, toggler, Column, Row, Text};
+use iced::widget::{
+    button, horizontal_space, pick_list, slider, text_input, toggler, Column, Row, Text,
+};
 use iced::{Alignment, Color, Command, Element, Length};
 use iced_futures::Subscription;
 use iroh_net::relay::RelayUrl;
 use iroh_net::NodeId;
 use std::cmp::PartialEq;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::time::Duration;
 
 #[cfg(feature = "hardware")]
 use crate::hardware_subscription;
-use crate::hw::config::HardwareConfig;
+#[cfg(feature = "hardware")]
+use crate::hw::backend::Backend;
+use crate::hw::config::{
+    validate, ConfigProblem, HardwareConfig, LabelColour, PinAnnotation, DEFAULT_PROFILE,
+};
+use crate::hw::hat::HatInfo;
+use crate::hw::i2c::I2CResponse;
 use crate::hw::pin_description::{PinDescription, PinDescriptionSet};
 use crate::hw::pin_function::PinFunction;
-use crate::hw::pin_function::PinFunction::{Input, Output};
+use crate::hw::pin_function::PinFunction::{
+    Input, Output, Pwm, I2C1_SCL, I2C1_SDA, UART0_RXD, UART0_TXD,
+};
+use crate::hw::spi::{SPIBus, SPIResponse};
+#[cfg(feature = "hardware")]
+use crate::hw::stimulus::Stimulus;
+use crate::hw::uart::{UARTConfig, UARTEvent};
 use crate::hw::HardwareConfigMessage;
 use crate::hw::{BCMPinNumber, BoardPinNumber, LevelChange, PinLevel};
-use crate::hw::{HardwareDescription, InputPull};
+use crate::hw::{HardwareDescription, InputEdge, InputFilter, InputPull, PinProbe};
 use crate::network_subscription;
 use crate::styles::button_style::ButtonStyle;
 use crate::styles::toggler_style::TogglerStyle;
+use crate::toast_handler::ToastMessage;
 use crate::views::hardware_view::HardwareTarget::{Local, NoHW, Remote};
 use crate::views::hardware_view::HardwareViewMessage::{
-    Activate, ChangeOutputLevel, HardwareSubscription, NewConfig, PinFunctionSelected, UpdateCharts,
+    Activate, AnnotationChanged, ChangeOutputLevel, HardwareSubscription, NewConfig,
+    PinFunctionSelected, UpdateCharts, I2C, SPI, UART,
 };
+use crate::views::i2c_view::{I2CView, I2CViewMessage};
 use crate::views::layout_selector::Layout;
+use crate::views::message_row::MessageMessage::{Error, Info};
+use crate::views::message_row::MessageRowMessage::ShowStatusMessage;
 use crate::views::pin_state::{CHART_UPDATES_PER_SECOND, CHART_WIDTH};
+use crate::views::spi_view::{ChipSelect, SPIView, SPIViewMessage};
+use crate::views::uart_view::{UARTView, UARTViewMessage};
 use crate::widgets::clicker::clicker;
 use crate::widgets::led::led;
 use crate::widgets::{circle::circle, line::line};
@@ -42,6 +65,9 @@ const PIN_ARROW_LINE_WIDTH: f32 = 20.0;
 const PIN_ARROW_CIRCLE_RADIUS: f32 = 5.0;
 const PIN_ARROW_WIDTH: f32 = PIN_ARROW_LINE_WIDTH + PIN_ARROW_CIRCLE_RADIUS * 2.0;
 const PIN_NAME_WIDTH: f32 = 60.0;
+const PIN_LABEL_WIDTH: f32 = 90.0;
+const PIN_LABEL_TEXT_SIZE: f32 = 12.0;
+const PIN_NOTES_WIDTH: f32 = 400.0;
 const PIN_OPTION_WIDTH: f32 = 130.0;
 const TOGGLER_SIZE: f32 = 30.0;
 const TOGGLER_WIDTH: f32 = 95.0; // Just used to calculate Pullup width
@@ -49,9 +75,19 @@ const BUTTON_WIDTH: f32 = 16.0;
 // We want the pullup on an Input to be the same width as the clicker + toggler on an Output
 const PULLUP_WIDTH: f32 = TOGGLER_WIDTH + WIDGET_ROW_SPACING + BUTTON_WIDTH;
 const LED_WIDTH: f32 = 16.0;
+const INPUT_FILTER_WIDTH: f32 = 110.0;
 const WIDGET_ROW_SPACING: f32 = 5.0;
 const PIN_WIDGET_ROW_WIDTH: f32 =
     PULLUP_WIDTH + WIDGET_ROW_SPACING + LED_WIDTH + WIDGET_ROW_SPACING + CHART_WIDTH;
+const PWM_SLIDER_WIDTH: f32 = 100.0;
+const PWM_VALUE_WIDTH: f32 = 60.0;
+// PWM frequency is selected on a logarithmic scale from 1Hz (10^0) to 10kHz (10^4)
+const PWM_MAX_FREQUENCY_EXPONENT: f64 = 4.0;
+// General Purpose Clock frequency is selected on a logarithmic scale from 10kHz to 100MHz
+const GPCLK_MIN_FREQUENCY_EXPONENT: f64 = 4.0;
+const GPCLK_MAX_FREQUENCY_EXPONENT: f64 = 8.0;
+// Debounce times offered for an Input, in milliseconds
+const DEBOUNCE_TIMES_MS: [u32; 5] = [1, 5, 10, 20, 50];
 
 // const PIN_VIEW_SIDE_WIDTH: f32 = PIN_BUTTON_WIDTH
 //     + WIDGET_ROW_SPACING
@@ -83,19 +119,36 @@ pub enum HardwareEventMessage {
     Connected(Sender<HardwareConfigMessage>, HardwareDescription),
     /// This event indicates that the logic level of an input has just changed
     InputChange(BCMPinNumber, LevelChange),
+    /// The response to an I2C request made on the hardware
+    I2CResponse(I2CResponse),
+    /// The response to an SPI transfer made on the hardware
+    SPIResponse(SPIResponse),
+    /// Something happened on the UART of the hardware
+    UARTEvent(UARTEvent),
+    /// Another client in control of the remote hardware changed its config
+    ConfigChanged(HardwareConfigMessage),
+    /// Whether this client has control of the remote hardware (true) or is observing it
+    Control(bool),
+    /// A change sent to the remote hardware could not be applied to it, with the error why
+    ChangeFailed(HardwareConfigMessage, String),
     /// We have lost the connection to the hardware
     Disconnected(String),
 }
 
 /// [HardwareViewMessage] covers all messages that are handled by hardware_view
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone)]
 pub enum HardwareViewMessage {
     Activate(BoardPinNumber),
+    AnnotationChanged(BCMPinNumber, PinAnnotation),
     PinFunctionSelected(BCMPinNumber, PinFunction),
     NewConfig(HardwareConfig),
     HardwareSubscription(HardwareEventMessage),
     ChangeOutputLevel(BCMPinNumber, LevelChange),
     UpdateCharts,
+    I2C(I2CViewMessage),
+    SPI(SPIBus, SPIViewMessage),
+    UART(UARTViewMessage),
 }
 
 fn get_pin_style(pin_description: &PinDescription) -> ButtonStyle {
@@ -165,20 +218,35 @@ fn get_pin_style(pin_description: &PinDescription) -> ButtonStyle {
 
 #[derive(Debug, Clone, Default, PartialEq)]
 pub enum HardwareTarget {
-    #[cfg_attr(not(any(feature = "pi_hw", feature = "fake_hw")), default)]
+    #[cfg_attr(not(feature = "hardware"), default)]
     NoHW,
-    #[cfg_attr(any(feature = "pi_hw", feature = "fake_hw"), default)]
+    #[cfg_attr(feature = "hardware", default)]
     Local,
     Remote(NodeId, Option<RelayUrl>),
 }
 
 pub struct HardwareView {
+    /// The config of the active profile, that is applied to the hardware and edited
     hardware_config: HardwareConfig,
+    /// The other profiles of the config, including the default profile when it is not active
+    profiles: BTreeMap<String, HardwareConfig>,
+    active_profile: String,
     hardware_sender: Option<Sender<HardwareConfigMessage>>,
     hardware_description: Option<HardwareDescription>,
     /// Either desired state of an output, or detected state of input.
     /// Note: Indexed by BoardPinNumber -1 (since BoardPinNumbers start at 1)
     pin_states: HashMap<BCMPinNumber, PinState>,
+    i2c_view: I2CView,
+    spi_views: HashMap<SPIBus, SPIView>,
+    uart_view: UARTView,
+    /// The pin whose notes and colour are being edited, after its pin button was clicked
+    annotating: Option<BCMPinNumber>,
+    /// The backend used for local GPIO, selected on the command line
+    #[cfg(feature = "hardware")]
+    backend: Backend,
+    /// Waveforms to drive the inputs of the fake backend with, loaded from the command line
+    #[cfg(feature = "hardware")]
+    stimulus: Option<Stimulus>,
 }
 
 async fn empty() {}
@@ -187,27 +255,98 @@ impl HardwareView {
     pub fn new() -> Self {
         Self {
             hardware_config: HardwareConfig::default(),
+            profiles: BTreeMap::new(),
+            active_profile: DEFAULT_PROFILE.to_string(),
             hardware_description: None, // Until listener is ready
             hardware_sender: None,      // Until listener is ready
             pin_states: HashMap::new(),
+            i2c_view: I2CView::new(),
+            spi_views: SPIBus::ALL
+                .iter()
+                .map(|bus| (*bus, SPIView::new(*bus)))
+                .collect(),
+            uart_view: UARTView::new(),
+            annotating: None,
+            #[cfg(feature = "hardware")]
+            backend: Backend::default(),
+            #[cfg(feature = "hardware")]
+            stimulus: None,
         }
     }
 
+    /// Select the [Backend] to use when connected to local GPIO, and any [Stimulus] to drive
+    /// its inputs with
+    #[cfg(feature = "hardware")]
+    pub fn set_backend(&mut self, backend: Backend, stimulus: Option<Stimulus>) {
+        self.backend = backend;
+        self.stimulus = stimulus;
+    }
+
+    /// Return the whole config, with the default profile at the top-level and any others in
+    /// its profiles
     pub fn get_config(&self) -> HardwareConfig {
-        self.hardware_config.clone()
+        let mut profiles = self.profiles.clone();
+        profiles.insert(self.active_profile.clone(), self.hardware_config.clone());
+        let mut config = profiles.remove(DEFAULT_PROFILE).unwrap_or_default();
+        config.profiles = profiles;
+        config
+    }
+
+    /// The names of the profiles of the config, starting with the default profile
+    #[must_use]
+    pub fn profile_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .profiles
+            .keys()
+            .chain(std::iter::once(&self.active_profile))
+            .filter(|name| name.as_str() != DEFAULT_PROFILE)
+            .cloned()
+            .collect();
+        names.sort();
+        std::iter::once(DEFAULT_PROFILE.to_string())
+            .chain(names)
+            .collect()
+    }
+
+    /// The name of the profile that is applied to the hardware
+    #[must_use]
+    pub fn active_profile(&self) -> &str {
+        &self.active_profile
+    }
+
+    /// Switch the hardware to another profile of the config, skipping any parts of it the
+    /// hardware does not support, and return the problems found with it
+    pub fn switch_profile(&mut self, name: &str) -> Vec<ConfigProblem> {
+        let Some(next) = self.profiles.remove(name) else {
+            return vec![];
+        };
+        let previous = std::mem::replace(&mut self.hardware_config, next);
+        let previous_name = std::mem::replace(&mut self.active_profile, name.to_string());
+        self.profiles.insert(previous_name, previous);
+
+        let problems = self.remove_config_problems();
+        let pins = &self.hardware_config.pins;
+        self.pin_states
+            .retain(|bcm_pin_number, _| pins.contains_key(bcm_pin_number));
+        self.set_pin_states_after_load();
+
+        if let Some(ref mut hardware_sender) = &mut self.hardware_sender {
+            let _ = hardware_sender.try_send(HardwareConfigMessage::SwitchProfile(
+                name.to_string(),
+                self.hardware_config.clone(),
+            ));
+        }
+        problems
     }
 
     /// Return a String describing the HW Piggui is connected to, or a placeholder string
     #[must_use]
     pub fn hw_description(&self) -> String {
         if let Some(hardware_description) = &self.hardware_description {
-            format!(
-                "Hardware: {}\nRevision: {}\nSerial: {}\nModel: {}",
-                hardware_description.details.hardware,
-                hardware_description.details.revision,
-                hardware_description.details.serial,
-                hardware_description.details.model,
-            )
+            match &hardware_description.hat {
+                None => hardware_description.details.to_string(),
+                Some(hat) => format!("{}\n{hat}", hardware_description.details),
+            }
         } else {
             "No Hardware connected".to_string()
         }
@@ -221,6 +360,20 @@ impl HardwareView {
             .map(|desc| desc.details.model.clone())
     }
 
+    /// Return the [HatInfo] of the HAT attached to the HW Piggui is connected to, if any
+    #[must_use]
+    pub fn hat(&self) -> Option<&HatInfo> {
+        self.hardware_description
+            .as_ref()
+            .and_then(|desc| desc.hat.as_ref())
+    }
+
+    /// Return the [PinDescriptionSet] of the HW Piggui is connected to, if any
+    #[must_use]
+    pub fn pin_descriptions(&self) -> Option<&PinDescriptionSet> {
+        self.hardware_description.as_ref().map(|desc| &desc.pins)
+    }
+
     /// Send the GPIOConfig from the GUI to the hardware to have it applied
     fn update_hw_config(&mut self) {
         if let Some(ref mut hardware_sender) = &mut self.hardware_sender {
@@ -243,7 +396,7 @@ impl HardwareView {
         if &new_function != previous_function {
             self.hardware_config
                 .pins
-                .insert(bcm_pin_number, new_function);
+                .insert(bcm_pin_number, new_function.clone());
 
             self.pin_states.insert(bcm_pin_number, PinState::new());
 
@@ -256,9 +409,24 @@ impl HardwareView {
                     new_function,
                 ));
             }
+
+            // The UART is opened with default settings once both of its pins are configured
+            if self.uart_enabled() && self.hardware_config.uart.is_none() {
+                let uart_config = UARTConfig::default();
+                self.hardware_config.uart = Some(uart_config);
+                if let Some(ref mut listener) = &mut self.hardware_sender {
+                    let _ = listener.try_send(HardwareConfigMessage::NewUARTConfig(uart_config));
+                }
+            }
         }
     }
 
+    /// Return true if the pins of the UART are both configured for it
+    fn uart_enabled(&self) -> bool {
+        let pins = &self.hardware_config.pins;
+        pins.get(&14) == Some(&UART0_TXD) && pins.get(&15) == Some(&UART0_RXD)
+    }
+
     /// Go through all the pins in the [HardwareConfig], make sure a pin state exists for the pin
     /// and then set the current level if it was specified for an Output
     fn set_pin_states_after_load(&mut self) {
@@ -274,11 +442,59 @@ impl HardwareView {
         }
     }
 
-    /// Apply a new config to the connected hardware
-    pub fn new_config(&mut self, new_config: HardwareConfig) {
+    /// Apply a new config to the connected hardware, skipping any parts of it the hardware
+    /// does not support, and return the problems found with it
+    pub fn new_config(&mut self, mut new_config: HardwareConfig) -> Vec<ConfigProblem> {
+        self.profiles = std::mem::take(&mut new_config.profiles);
+        self.active_profile = DEFAULT_PROFILE.to_string();
         self.hardware_config = new_config;
+        let problems = self.remove_config_problems();
         self.set_pin_states_after_load();
         self.update_hw_config();
+        problems
+    }
+
+    /// Show a change another client made to the config of the connected hardware, without
+    /// sending it back to the hardware. Annotations and other profiles are left as they are
+    fn hardware_config_changed(&mut self, change: HardwareConfigMessage) {
+        match change {
+            HardwareConfigMessage::NewConfig(config)
+            | HardwareConfigMessage::SwitchProfile(_, config) => {
+                self.hardware_config.pins = config.pins;
+                self.hardware_config.spi = config.spi;
+                self.hardware_config.uart = config.uart;
+                self.hardware_config.safe_state = config.safe_state;
+                let pins = &self.hardware_config.pins;
+                self.pin_states
+                    .retain(|bcm_pin_number, _| pins.contains_key(bcm_pin_number));
+                self.set_pin_states_after_load();
+            }
+            HardwareConfigMessage::NewPinConfig(bcm_pin_number, pin_function) => {
+                self.hardware_config
+                    .pins
+                    .insert(bcm_pin_number, pin_function);
+                self.pin_states.insert(bcm_pin_number, PinState::new());
+            }
+            HardwareConfigMessage::NewSPIConfig(bus, spi_config) => {
+                self.hardware_config.spi.insert(bus, spi_config);
+            }
+            HardwareConfigMessage::NewUARTConfig(uart_config) => {
+                self.hardware_config.uart = Some(uart_config);
+            }
+            _ => {}
+        }
+    }
+
+    /// Validate the config against the pins of the connected hardware, removing the parts of it
+    /// that cannot be applied, and return the problems found. Until connected to hardware there
+    /// is nothing to validate against
+    fn remove_config_problems(&mut self) -> Vec<ConfigProblem> {
+        let Some(hardware_description) = &self.hardware_description else {
+            return vec![];
+        };
+        let problems = validate(&self.hardware_config, &hardware_description.pins);
+        self.hardware_config.remove_problems(&problems);
+        problems
     }
 
     pub fn update(&mut self, message: HardwareViewMessage) -> Command<Message> {
@@ -298,18 +514,27 @@ impl HardwareView {
             }
 
             NewConfig(config) => {
-                self.new_config(config);
+                let problems = self.new_config(config);
+                if !problems.is_empty() {
+                    return config_problems_found(problems);
+                }
             }
 
             HardwareSubscription(event) => match event {
                 HardwareEventMessage::Connected(config_change_sender, hw_desc) => {
                     self.hardware_sender = Some(config_change_sender);
                     self.hardware_description = Some(hw_desc);
+                    // A config loaded before connecting can only be validated now
+                    let problems = self.remove_config_problems();
                     self.set_pin_states_after_load();
                     self.update_hw_config();
-                    return Command::perform(empty(), |_| {
+                    let connected = Command::perform(empty(), |_| {
                         <Piggui as iced::Application>::Message::Connected
                     });
+                    if problems.is_empty() {
+                        return connected;
+                    }
+                    return Command::batch(vec![connected, config_problems_found(problems)]);
                 }
                 HardwareEventMessage::InputChange(bcm_pin_number, level_change) => {
                     self.pin_states
@@ -317,6 +542,43 @@ impl HardwareView {
                         .or_insert(PinState::new())
                         .set_level(level_change);
                 }
+                HardwareEventMessage::I2CResponse(response) => {
+                    self.i2c_view.add_response(&response);
+                }
+                HardwareEventMessage::UARTEvent(event) => {
+                    self.uart_view.add_event(&event);
+                }
+                HardwareEventMessage::SPIResponse(response) => {
+                    if let SPIResponse::Data { bus, .. } = response {
+                        if let Some(spi_view) = self.spi_views.get_mut(&bus) {
+                            spi_view.add_response(&response);
+                        }
+                    } else {
+                        for spi_view in self.spi_views.values_mut() {
+                            spi_view.add_response(&response);
+                        }
+                    }
+                }
+                HardwareEventMessage::ConfigChanged(change) => {
+                    self.hardware_config_changed(change);
+                }
+                HardwareEventMessage::Control(has_control) => {
+                    let message = if has_control {
+                        "You have control of the hardware"
+                    } else {
+                        "Another client has control of the hardware, you are observing it"
+                    };
+                    return Command::perform(empty(), move |_| {
+                        Message::InfoRow(ShowStatusMessage(Info(message.into())))
+                    });
+                }
+                // The view is rolled back by the actual state the hardware sends after this
+                HardwareEventMessage::ChangeFailed(change, error) => {
+                    let title = change_failed_title(&change);
+                    return Command::perform(empty(), move |_| {
+                        Message::InfoRow(ShowStatusMessage(Error(title, error)))
+                    });
+                }
                 HardwareEventMessage::Disconnected(message) => {
                     return Command::perform(empty(), |_| {
                         <Piggui as iced::Application>::Message::ConnectionError(message)
@@ -337,7 +599,103 @@ impl HardwareView {
                 }
             }
 
-            Activate(pin_number) => println!("Pin {pin_number} clicked"),
+            I2C(i2c_message) => {
+                if let Some(request) = self.i2c_view.update(i2c_message) {
+                    if let Some(ref mut listener) = &mut self.hardware_sender {
+                        let _ = listener.try_send(HardwareConfigMessage::I2CRequest(request));
+                    }
+                }
+            }
+
+            SPI(bus, spi_message) => {
+                let config = self
+                    .hardware_config
+                    .spi
+                    .get(&bus)
+                    .copied()
+                    .unwrap_or_default();
+                let chip_selects = self.spi_chip_selects(bus);
+                let Some(message) = self
+                    .spi_views
+                    .get_mut(&bus)
+                    .and_then(|spi_view| spi_view.update(spi_message, &config, &chip_selects))
+                else {
+                    return Command::none();
+                };
+
+                let config_changed = match &message {
+                    HardwareConfigMessage::NewSPIConfig(bus, spi_config) => {
+                        self.hardware_config.spi.insert(*bus, *spi_config);
+                        true
+                    }
+                    _ => false,
+                };
+
+                if let Some(ref mut listener) = &mut self.hardware_sender {
+                    let _ = listener.try_send(message);
+                }
+
+                if config_changed {
+                    return Command::perform(empty(), |_| {
+                        <Piggui as iced::Application>::Message::ConfigChangesMade
+                    });
+                }
+            }
+
+            UART(uart_message) => {
+                let config = self.hardware_config.uart.unwrap_or_default();
+                let Some(message) = self.uart_view.update(uart_message, &config) else {
+                    return Command::none();
+                };
+
+                let config_changed = match &message {
+                    HardwareConfigMessage::NewUARTConfig(uart_config) => {
+                        self.hardware_config.uart = Some(*uart_config);
+                        true
+                    }
+                    _ => false,
+                };
+
+                if let Some(ref mut listener) = &mut self.hardware_sender {
+                    let _ = listener.try_send(message);
+                }
+
+                if config_changed {
+                    return Command::perform(empty(), |_| {
+                        <Piggui as iced::Application>::Message::ConfigChangesMade
+                    });
+                }
+            }
+
+            Activate(board_pin_number) => {
+                // Clicking a pin with a BCM number opens or closes the editor of its annotation
+                let bcm_pin_number = self.pin_descriptions().and_then(|pins| {
+                    pins.pins()
+                        .iter()
+                        .find(|pin| pin.bpn == board_pin_number)
+                        .and_then(|pin| pin.bcm)
+                });
+                if let Some(bcm_pin_number) = bcm_pin_number {
+                    if self.annotating == Some(bcm_pin_number) {
+                        self.annotating = None;
+                    } else {
+                        self.annotating = Some(bcm_pin_number);
+                    }
+                }
+            }
+
+            AnnotationChanged(bcm_pin_number, annotation) => {
+                if annotation.is_empty() {
+                    self.hardware_config.annotations.remove(&bcm_pin_number);
+                } else {
+                    self.hardware_config
+                        .annotations
+                        .insert(bcm_pin_number, annotation);
+                }
+                return Command::perform(empty(), |_| {
+                    <Piggui as iced::Application>::Message::ConfigChangesMade
+                });
+            }
         }
 
         Command::none()
@@ -347,7 +705,7 @@ impl HardwareView {
         &self,
         layout: Layout,
         hardware_target: &HardwareTarget,
-    ) -> Element<HardwareViewMessage> {
+    ) -> Element<'_, HardwareViewMessage> {
         if hardware_target == &NoHW {
             return Row::new().into();
         }
@@ -365,6 +723,57 @@ impl HardwareView {
         Row::new().into()
     }
 
+    /// Views of the buses that are enabled by the current config, shown only when connected
+    pub fn bus_views(&self, hardware_target: &HardwareTarget) -> Element<'_, HardwareViewMessage> {
+        let mut column = Column::new().spacing(10);
+
+        if hardware_target == &NoHW || self.hardware_description.is_none() {
+            return column.into();
+        }
+
+        let pins = &self.hardware_config.pins;
+        if pins.get(&2) == Some(&I2C1_SDA) && pins.get(&3) == Some(&I2C1_SCL) {
+            column = column.push(self.i2c_view.view().map(I2C));
+        }
+
+        for bus in SPIBus::ALL {
+            let enabled = bus
+                .data_functions()
+                .iter()
+                .all(|function| pins.values().any(|pin_function| pin_function == function));
+            if let (true, Some(spi_view)) = (enabled, self.spi_views.get(&bus)) {
+                let config = self
+                    .hardware_config
+                    .spi
+                    .get(&bus)
+                    .copied()
+                    .unwrap_or_default();
+                column = column.push(
+                    spi_view
+                        .view(&config, self.spi_chip_selects(bus))
+                        .map(move |message| SPI(bus, message)),
+                );
+            }
+        }
+
+        if self.uart_enabled() {
+            let config = self.hardware_config.uart.unwrap_or_default();
+            column = column.push(self.uart_view.view(&config).map(UART));
+        }
+
+        column.into()
+    }
+
+    /// The chip selects of an SPI `bus` that have a pin configured for them
+    fn spi_chip_selects(&self, bus: SPIBus) -> Vec<ChipSelect> {
+        bus.chip_select_functions()
+            .iter()
+            .enumerate()
+            .filter(|(_, function)| self.hardware_config.pins.values().any(|f| &f == function))
+            .map(|(index, _)| ChipSelect(index as u8))
+            .collect()
+    }
+
     /// Create subscriptions for ticks for updating charts of waveforms and events coming from hardware
     pub fn subscription(
         &self,
@@ -380,7 +789,10 @@ impl HardwareView {
             NoHW => {}
             Local => {
                 #[cfg(feature = "hardware")]
-                subscriptions.push(hardware_subscription::subscribe().map(HardwareSubscription));
+                subscriptions.push(
+                    hardware_subscription::subscribe(self.backend, self.stimulus.clone())
+                        .map(HardwareSubscription),
+                );
             }
             Remote(nodeid, relay) => {
                 subscriptions.push(
@@ -393,6 +805,78 @@ impl HardwareView {
         Subscription::batch(subscriptions)
     }
 
+    /// The state a pin was found in when connecting to the hardware, if it has a [BCMPinNumber]
+    fn pin_probe(&self, pin_description: &PinDescription) -> Option<&PinProbe> {
+        let bcm_pin_number = pin_description.bcm?;
+        self.hardware_description
+            .as_ref()
+            .and_then(|desc| desc.probe.get(&bcm_pin_number))
+    }
+
+    /// The label, notes and colour the user has given a pin, if it has a [BCMPinNumber]
+    fn pin_annotation(&self, pin_description: &PinDescription) -> Option<&PinAnnotation> {
+        let bcm_pin_number = pin_description.bcm?;
+        self.hardware_config.annotations.get(&bcm_pin_number)
+    }
+
+    /// A row of widgets to edit the notes and colour of a pin, shown below the pin after its
+    /// pin button has been clicked
+    fn annotation_editor<'a>(
+        &'a self,
+        pin_description: &'a PinDescription,
+    ) -> Option<Row<'a, HardwareViewMessage>> {
+        let bcm_pin_number = pin_description.bcm?;
+        if self.annotating != Some(bcm_pin_number) {
+            return None;
+        }
+
+        let annotation = self
+            .pin_annotation(pin_description)
+            .cloned()
+            .unwrap_or_default();
+
+        let notes_annotation = annotation.clone();
+        let notes = text_input("Notes", &annotation.notes)
+            .on_input(move |notes| {
+                AnnotationChanged(
+                    bcm_pin_number,
+                    PinAnnotation {
+                        notes,
+                        ..notes_annotation.clone()
+                    },
+                )
+            })
+            .size(PIN_LABEL_TEXT_SIZE)
+            .width(Length::Fixed(PIN_NOTES_WIDTH));
+
+        let colour = pick_list(
+            LabelColour::ALL,
+            Some(annotation.colour.unwrap_or_default()),
+            move |colour| {
+                AnnotationChanged(
+                    bcm_pin_number,
+                    PinAnnotation {
+                        colour: (colour != LabelColour::default()).then_some(colour),
+                        ..annotation.clone()
+                    },
+                )
+            },
+        )
+        .width(Length::Fixed(PIN_OPTION_WIDTH));
+
+        let done = button(Text::new("Done")).on_press(Activate(pin_description.bpn));
+
+        Some(
+            Row::new()
+                .push(Text::new(format!("{} Notes:", pin_description.name)))
+                .push(notes)
+                .push(colour)
+                .push(done)
+                .spacing(WIDGET_ROW_SPACING)
+                .align_items(Alignment::Center),
+        )
+    }
+
     /// View that lays out the pins in a single column ordered by BCM pin number
     pub fn bcm_pin_layout_view<'a>(
         &'a self,
@@ -406,12 +890,18 @@ impl HardwareView {
                 self.hardware_config.pins.get(&pin_description.bcm.unwrap()),
                 Right,
                 self.pin_states.get(&pin_description.bcm.unwrap_or(0)),
+                self.pin_probe(pin_description),
+                self.pin_annotation(pin_description),
             );
 
             column = column
                 .push(pin_row)
                 .spacing(BCM_SPACE_BETWEEN_PIN_ROWS)
                 .align_items(Alignment::Center);
+
+            if let Some(editor) = self.annotation_editor(pin_description) {
+                column = column.push(editor);
+            }
         }
 
         column.into()
@@ -431,21 +921,27 @@ impl HardwareView {
                 self.hardware_config.pins.get(&pair[0].bcm.unwrap_or(0)),
                 Left,
                 self.pin_states.get(&pair[0].bcm.unwrap_or(0)),
+                self.pin_probe(&pair[0]),
+                self.pin_annotation(&pair[0]),
             );
 
-            let right_row = create_pin_view_side(
-                &pair[1],
-                self.hardware_config.pins.get(&pair[1].bcm.unwrap_or(0)),
-                Right,
-                self.pin_states.get(&pair[1].bcm.unwrap_or(0)),
-            );
-
-            let row = Row::new()
+            let mut row = Row::new()
                 .push(left_row)
-                .push(right_row)
                 .spacing(BOARD_LAYOUT_WIDTH_BETWEEN_PIN_ROWS)
                 .align_items(Alignment::Center);
 
+            // With an odd number of pins the last row only has a left side
+            if let Some(right) = pair.get(1) {
+                row = row.push(create_pin_view_side(
+                    right,
+                    self.hardware_config.pins.get(&right.bcm.unwrap_or(0)),
+                    Right,
+                    self.pin_states.get(&right.bcm.unwrap_or(0)),
+                    self.pin_probe(right),
+                    self.pin_annotation(right),
+                ));
+            }
+
             column = column
                 .push(row)
                 .push(iced::widget::Space::new(
@@ -453,6 +949,12 @@ impl HardwareView {
                     Length::Fixed(VERTICAL_SPACE_BETWEEN_PIN_ROWS),
                 ))
                 .align_items(Alignment::Center);
+
+            for pin_description in pair {
+                if let Some(editor) = self.annotation_editor(pin_description) {
+                    column = column.push(editor);
+                }
+            }
         }
 
         column.into()
@@ -462,6 +964,7 @@ impl HardwareView {
 /// Prepare a pick_list widget with the Input's pullup options
 fn pullup_picklist(
     pull: &Option<InputPull>,
+    filter: &Option<InputFilter>,
     bcm_pin_number: BCMPinNumber,
 ) -> Element<'static, HardwareViewMessage> {
     let mut sub_options = vec![InputPull::PullUp, InputPull::PullDown, InputPull::None];
@@ -471,14 +974,163 @@ fn pullup_picklist(
         sub_options.retain(|&option| option != *selected_pull);
     }
 
+    let filter = *filter;
     pick_list(sub_options, *pull, move |selected_pull| {
-        PinFunctionSelected(bcm_pin_number, Input(Some(selected_pull)))
+        PinFunctionSelected(bcm_pin_number, Input(Some(selected_pull), filter))
     })
     .width(Length::Fixed(PULLUP_WIDTH))
     .placeholder("Select Pullup")
     .into()
 }
 
+/// A debounce time of an Input, as shown in its pick_list
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Debounce(Option<u32>);
+
+impl Display for Debounce {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            None => write!(f, "No Debounce"),
+            Some(debounce_ms) => write!(f, "{debounce_ms}ms"),
+        }
+    }
+}
+
+/// The [InputFilter] to use for an Input, none if it would report all changes of level
+fn input_filter(edge: InputEdge, debounce_ms: Option<u32>) -> Option<InputFilter> {
+    let filter = InputFilter { edge, debounce_ms };
+    (filter != InputFilter::default()).then_some(filter)
+}
+
+/// Prepare a row of pick_list widgets to select the edges of an Input that are reported and
+/// its debounce time
+fn input_filter_picklists(
+    pull: &Option<InputPull>,
+    filter: &Option<InputFilter>,
+    bcm_pin_number: BCMPinNumber,
+) -> Row<'static, HardwareViewMessage> {
+    let pull = *pull;
+    let filter = filter.unwrap_or_default();
+
+    let edge_pick = pick_list(InputEdge::ALL, Some(filter.edge), move |edge| {
+        PinFunctionSelected(
+            bcm_pin_number,
+            Input(pull, input_filter(edge, filter.debounce_ms)),
+        )
+    })
+    .width(Length::Fixed(INPUT_FILTER_WIDTH));
+
+    let debounce_options: Vec<Debounce> = std::iter::once(Debounce(None))
+        .chain(DEBOUNCE_TIMES_MS.iter().map(|&ms| Debounce(Some(ms))))
+        .collect();
+    let debounce_pick = pick_list(
+        debounce_options,
+        Some(Debounce(filter.debounce_ms)),
+        move |debounce| {
+            PinFunctionSelected(
+                bcm_pin_number,
+                Input(pull, input_filter(filter.edge, debounce.0)),
+            )
+        },
+    )
+    .width(Length::Fixed(INPUT_FILTER_WIDTH));
+
+    Row::new()
+        .push(edge_pick)
+        .push(debounce_pick)
+        .spacing(WIDGET_ROW_SPACING)
+}
+
+/// Prepare a row of widgets to control the frequency and duty cycle of a PWM output
+fn pwm_controls(
+    bcm_pin_number: BCMPinNumber,
+    frequency: f64,
+    duty_cycle: f64,
+    direction: Direction,
+) -> Row<'static, HardwareViewMessage> {
+    let frequency_slider = slider(
+        0.0..=PWM_MAX_FREQUENCY_EXPONENT,
+        frequency.log10(),
+        move |exponent: f64| {
+            PinFunctionSelected(
+                bcm_pin_number,
+                Pwm {
+                    frequency: 10f64.powf(exponent).round(),
+                    duty_cycle,
+                },
+            )
+        },
+    )
+    .step(0.01)
+    .width(Length::Fixed(PWM_SLIDER_WIDTH));
+    let frequency_value = Text::new(format!("{frequency:.0}Hz")).width(PWM_VALUE_WIDTH);
+
+    let duty_cycle_slider = slider(0.0..=100.0, duty_cycle * 100.0, move |percent: f64| {
+        PinFunctionSelected(
+            bcm_pin_number,
+            Pwm {
+                frequency,
+                duty_cycle: percent / 100.0,
+            },
+        )
+    })
+    .step(1.0)
+    .width(Length::Fixed(PWM_SLIDER_WIDTH));
+    let duty_cycle_value = Text::new(format!("{:.0}%", duty_cycle * 100.0)).width(PWM_VALUE_WIDTH);
+
+    let frequency_tooltip = Tooltip::new(frequency_slider, "PWM frequency", Position::Top);
+    let duty_cycle_tooltip = Tooltip::new(duty_cycle_slider, "PWM duty cycle", Position::Top);
+
+    if direction == Left {
+        Row::new()
+            .push(duty_cycle_value)
+            .push(duty_cycle_tooltip)
+            .push(frequency_value)
+            .push(frequency_tooltip)
+    } else {
+        Row::new()
+            .push(frequency_tooltip)
+            .push(frequency_value)
+            .push(duty_cycle_tooltip)
+            .push(duty_cycle_value)
+    }
+}
+
+/// Prepare a row of widgets to control the frequency of General Purpose Clock number `clock`
+fn gpclk_controls(
+    bcm_pin_number: BCMPinNumber,
+    clock: u8,
+    frequency: f64,
+    direction: Direction,
+) -> Row<'static, HardwareViewMessage> {
+    let frequency_slider = slider(
+        GPCLK_MIN_FREQUENCY_EXPONENT..=GPCLK_MAX_FREQUENCY_EXPONENT,
+        frequency.log10(),
+        move |exponent: f64| {
+            let frequency = 10f64.powf(exponent).round();
+            PinFunctionSelected(
+                bcm_pin_number,
+                PinFunction::new_gpclk(clock, frequency).unwrap_or(PinFunction::None),
+            )
+        },
+    )
+    .step(0.01)
+    .width(Length::Fixed(PWM_SLIDER_WIDTH * 2.0));
+    let frequency_value = if frequency >= 1_000_000.0 {
+        format!("{:.3}MHz", frequency / 1_000_000.0)
+    } else {
+        format!("{:.1}kHz", frequency / 1000.0)
+    };
+    let frequency_value = Text::new(frequency_value).width(PWM_VALUE_WIDTH);
+    let frequency_tooltip = Tooltip::new(frequency_slider, "Clock frequency", Position::Top);
+
+    if direction == Left {
+        Row::new().push(frequency_value).push(frequency_tooltip)
+    } else {
+        Row::new().push(frequency_tooltip).push(frequency_value)
+    }
+}
+
 /// Create the widget that either shows an input pin's state,
 /// or allows the user to control the state of an output pin
 /// This should only be called for pins that have a valid BCMPinNumber
@@ -502,16 +1154,19 @@ fn get_pin_widget<'a>(
     };
 
     let row: Row<HardwareViewMessage> = match pin_function {
-        Some(Input(pull)) => {
-            let pullup_pick = pullup_picklist(pull, bcm_pin_number.unwrap());
+        Some(Input(pull, filter)) => {
+            let pullup_pick = pullup_picklist(pull, filter, bcm_pin_number.unwrap());
+            let filter_picks = input_filter_picklists(pull, filter, bcm_pin_number.unwrap());
             if direction == Left {
                 Row::new()
                     .push(pin_state.view(Left))
                     .push(led(LED_WIDTH, LED_WIDTH, pin_state.get_level()))
+                    .push(filter_picks)
                     .push(pullup_pick)
             } else {
                 Row::new()
                     .push(pullup_pick)
+                    .push(filter_picks)
                     .push(led(LED_WIDTH, LED_WIDTH, pin_state.get_level()))
                     .push(pin_state.view(Right))
             }
@@ -564,6 +1219,16 @@ fn get_pin_widget<'a>(
             }
         }
 
+        Some(Pwm {
+            frequency,
+            duty_cycle,
+        }) => pwm_controls(bcm_pin_number.unwrap(), *frequency, *duty_cycle, direction),
+
+        Some(function) if function.gpclk().is_some() => {
+            let (clock, frequency) = function.gpclk().unwrap_or((0, 0.0));
+            gpclk_controls(bcm_pin_number.unwrap(), clock, frequency, direction)
+        }
+
         _ => Row::new(),
     };
 
@@ -573,6 +1238,51 @@ fn get_pin_widget<'a>(
         .into()
 }
 
+/// Show the user the `problems` found with the config, which have been removed from it, so it
+/// is marked as changed, and saving it will not silently write a different file
+fn config_problems_found(problems: Vec<ConfigProblem>) -> Command<Message> {
+    Command::batch(vec![
+        Command::perform(empty(), |_| {
+            Message::Toast(ToastMessage::ConfigProblemsToast(problems))
+        }),
+        Command::perform(empty(), |_| {
+            <Piggui as iced::Application>::Message::ConfigChangesMade
+        }),
+    ])
+}
+
+/// The message shown when a `change` sent to the hardware could not be applied to it
+fn change_failed_title(change: &HardwareConfigMessage) -> String {
+    match change {
+        HardwareConfigMessage::NewConfig(_) => "Config could not be applied".into(),
+        HardwareConfigMessage::SwitchProfile(name, _) => {
+            format!("Could not switch to profile '{name}'")
+        }
+        HardwareConfigMessage::NewPinConfig(bcm_pin_number, _) => {
+            format!("Pin #{bcm_pin_number} could not be configured")
+        }
+        HardwareConfigMessage::IOLevelChanged(bcm_pin_number, _) => {
+            format!("Level of pin #{bcm_pin_number} could not be set")
+        }
+        HardwareConfigMessage::NewSPIConfig(bus, _) => format!("{bus} could not be configured"),
+        HardwareConfigMessage::NewUARTConfig(_) => "UART could not be configured".into(),
+        _ => "Change could not be applied to the hardware".into(),
+    }
+}
+
+/// The [Color] to draw a pin's name in, white unless the user has chosen another
+fn label_colour(colour: Option<LabelColour>) -> Color {
+    match colour.unwrap_or_default() {
+        LabelColour::White => Color::WHITE,
+        LabelColour::Red => Color::new(1.0, 0.2, 0.2, 1.0),
+        LabelColour::Orange => Color::new(1.0, 0.647, 0.0, 1.0),
+        LabelColour::Yellow => Color::new(1.0, 0.92, 0.016, 1.0),
+        LabelColour::Green => Color::new(0.0, 0.8, 0.0, 1.0),
+        LabelColour::Blue => Color::new(0.3, 0.5, 1.0, 1.0),
+        LabelColour::Purple => Color::new(0.933, 0.510, 0.933, 1.0),
+    }
+}
+
 // Filter options for PickList
 fn filter_options(
     options: &[PinFunction],
@@ -580,15 +1290,15 @@ fn filter_options(
 ) -> Vec<PinFunction> {
     let mut config_options: Vec<_> = options
         .iter()
-        .filter(|&&option| match selected_function {
-            Some(PinFunction::Input(Some(_))) => {
-                matches!(option, PinFunction::Output(None) | PinFunction::None)
-            }
-            Some(PinFunction::Output(Some(_))) => {
-                matches!(option, PinFunction::Input(None) | PinFunction::None)
-            }
+        .filter(|&option| match &selected_function {
+            Some(PinFunction::Input(..)) => !matches!(option, PinFunction::Input(..)),
+            Some(PinFunction::Output(Some(_))) => !matches!(option, PinFunction::Output(_)),
+            Some(PinFunction::Pwm { .. }) => !matches!(option, PinFunction::Pwm { .. }),
+            Some(PinFunction::GPCLK0 { .. }) => !matches!(option, PinFunction::GPCLK0 { .. }),
+            Some(PinFunction::GPCLK1 { .. }) => !matches!(option, PinFunction::GPCLK1 { .. }),
+            Some(PinFunction::GPCLK2 { .. }) => !matches!(option, PinFunction::GPCLK2 { .. }),
             Some(selected) => selected != option,
-            None => option != PinFunction::None,
+            None => *option != PinFunction::None,
         })
         .cloned()
         .collect();
@@ -609,6 +1319,8 @@ fn create_pin_view_side<'a>(
     selected_function: Option<&'a PinFunction>,
     direction: Direction,
     pin_state: Option<&'a PinState>,
+    probe: Option<&PinProbe>,
+    annotation: Option<&'a PinAnnotation>,
 ) -> Row<'a, HardwareViewMessage> {
     let pin_widget = if let Some(state) = pin_state {
         // Create a widget that is either used to visualize an input or control an output
@@ -636,7 +1348,12 @@ fn create_pin_view_side<'a>(
                 PinFunctionSelected(bcm_pin_number, pin_function)
             })
             .width(Length::Fixed(PIN_OPTION_WIDTH))
-            .placeholder("Select function"),
+            // Show what an unconfigured pin is already doing, if known
+            .placeholder(
+                probe
+                    .map(|probe| probe.to_string())
+                    .unwrap_or_else(|| "Select function".to_string()),
+            ),
         );
 
         pin_option = pin_option.push(pin_options_row);
@@ -646,13 +1363,47 @@ fn create_pin_view_side<'a>(
         .width(Length::Fixed(PIN_NAME_WIDTH))
         .align_items(Alignment::Center);
 
-    // Create the Pin name
+    // Create the Pin name, in the colour the user has chosen for it
+    let colour = annotation.and_then(|annotation| annotation.colour);
     let pin_name = Row::new()
-        .push(Text::new(pin_description.name.to_string()))
+        .push(Text::new(pin_description.name.to_string()).style(label_colour(colour)))
         .align_items(Alignment::Center);
 
     pin_name_column = pin_name_column.push(pin_name).width(PIN_NAME_WIDTH);
 
+    // Create the user's label for the pin, edited in place, with any notes as its tooltip
+    let mut pin_label_column = Column::new()
+        .width(Length::Fixed(PIN_LABEL_WIDTH))
+        .align_items(Alignment::Center);
+
+    if let Some(bcm_pin_number) = pin_description.bcm {
+        let label = annotation.map(|annotation| annotation.label.as_str());
+        let edited = annotation.cloned().unwrap_or_default();
+        let label_input = text_input("Label", label.unwrap_or_default())
+            .on_input(move |label| {
+                AnnotationChanged(
+                    bcm_pin_number,
+                    PinAnnotation {
+                        label,
+                        ..edited.clone()
+                    },
+                )
+            })
+            .size(PIN_LABEL_TEXT_SIZE)
+            .width(Length::Fixed(PIN_LABEL_WIDTH));
+
+        match annotation.filter(|annotation| !annotation.notes.is_empty()) {
+            Some(annotation) => {
+                pin_label_column = pin_label_column.push(Tooltip::new(
+                    label_input,
+                    annotation.notes.as_str(),
+                    Position::Top,
+                ))
+            }
+            None => pin_label_column = pin_label_column.push(label_input),
+        }
+    }
+
     let mut pin_arrow = Row::new()
         .align_items(Alignment::Center)
         .width(Length::Fixed(PIN_ARROW_WIDTH));
@@ -679,6 +1430,7 @@ fn create_pin_view_side<'a>(
         Row::new()
             .push(pin_widget)
             .push(pin_option)
+            .push(pin_label_column)
             .push(pin_name_column.align_items(Alignment::End))
             .push(pin_arrow)
             .push(pin_button_column)
@@ -687,6 +1439,7 @@ fn create_pin_view_side<'a>(
             .push(pin_button_column)
             .push(pin_arrow)
             .push(pin_name_column.align_items(Alignment::Start))
+            .push(pin_label_column)
             .push(pin_option)
             .push(pin_widget)
     };
@@ -711,12 +1464,129 @@ mod test {
         assert_eq!(hw_view.hw_model(), None);
     }
 
+    #[test]
+    fn switch_profiles() {
+        use super::*;
+
+        let mut test = HardwareConfig::default();
+        test.pins.insert(22, PinFunction::Output(Some(false)));
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+        config.profiles.insert("test".into(), test);
+
+        let mut hw_view = HardwareView::new();
+        assert!(hw_view.new_config(config).is_empty());
+        assert_eq!(hw_view.profile_names(), vec!["default", "test"]);
+        assert_eq!(hw_view.active_profile(), "default");
+
+        assert!(hw_view.switch_profile("test").is_empty());
+        assert_eq!(hw_view.active_profile(), "test");
+        assert_eq!(hw_view.profile_names(), vec!["default", "test"]);
+
+        // Edits go to the active profile, and the whole config has the default at the top-level
+        let _ = hw_view.update(PinFunctionSelected(4, Input(None, None)));
+        let config = hw_view.get_config();
+        assert_eq!(config.pins.get(&17), Some(&PinFunction::Output(Some(true))));
+        let test = config.profiles.get("test").expect("No 'test' profile");
+        assert_eq!(test.pins.len(), 2);
+        assert_eq!(test.pins.get(&4), Some(&Input(None, None)));
+
+        // Switching to an unknown profile changes nothing
+        assert!(hw_view.switch_profile("bring-up").is_empty());
+        assert_eq!(hw_view.active_profile(), "test");
+    }
+
+    #[test]
+    fn config_changed_by_other_client() {
+        use super::*;
+
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+        let annotation = PinAnnotation {
+            label: "Pump relay".into(),
+            ..Default::default()
+        };
+        config.annotations.insert(17, annotation.clone());
+        let mut hw_view = HardwareView::new();
+        assert!(hw_view.new_config(config).is_empty());
+
+        let mut other = HardwareConfig::default();
+        other.pins.insert(22, Input(None, None));
+        let _ = hw_view.update(HardwareSubscription(HardwareEventMessage::ConfigChanged(
+            HardwareConfigMessage::NewConfig(other),
+        )));
+        let config = hw_view.get_config();
+        assert_eq!(config.pins.len(), 1);
+        assert_eq!(config.pins.get(&22), Some(&Input(None, None)));
+        assert_eq!(config.annotations.get(&17), Some(&annotation));
+
+        let _ = hw_view.update(HardwareSubscription(HardwareEventMessage::ConfigChanged(
+            HardwareConfigMessage::NewPinConfig(4, PinFunction::Output(None)),
+        )));
+        assert_eq!(
+            hw_view.get_config().pins.get(&4),
+            Some(&PinFunction::Output(None))
+        );
+    }
+
+    #[test]
+    fn failed_pin_config_rolled_back() {
+        use super::*;
+
+        let mut config = HardwareConfig::default();
+        config.pins.insert(17, PinFunction::Output(Some(true)));
+        let mut hw_view = HardwareView::new();
+        assert!(hw_view.new_config(config).is_empty());
+
+        let _ = hw_view.update(PinFunctionSelected(17, Input(None, None)));
+        assert_eq!(hw_view.get_config().pins.get(&17), Some(&Input(None, None)));
+
+        // piglet reports the failure, followed by the actual function of the pin
+        let _ = hw_view.update(HardwareSubscription(HardwareEventMessage::ChangeFailed(
+            HardwareConfigMessage::NewPinConfig(17, Input(None, None)),
+            "Pin is busy".into(),
+        )));
+        let _ = hw_view.update(HardwareSubscription(HardwareEventMessage::ConfigChanged(
+            HardwareConfigMessage::NewPinConfig(17, PinFunction::Output(Some(true))),
+        )));
+        assert_eq!(
+            hw_view.get_config().pins.get(&17),
+            Some(&PinFunction::Output(Some(true)))
+        );
+    }
+
+    #[test]
+    fn annotation_changed() {
+        use super::*;
+
+        let mut hw_view = HardwareView::new();
+        let annotation = PinAnnotation {
+            label: "Pump relay".into(),
+            ..Default::default()
+        };
+        let _ = hw_view.update(AnnotationChanged(17, annotation.clone()));
+        assert_eq!(hw_view.get_config().annotations.get(&17), Some(&annotation));
+
+        // Clearing the label of a pin removes its annotation from the config
+        let _ = hw_view.update(AnnotationChanged(17, PinAnnotation::default()));
+        assert!(hw_view.get_config().annotations.is_empty());
+    }
+
+    #[test]
+    fn default_label_colour_is_white() {
+        use super::*;
+
+        assert_eq!(label_colour(None), Color::WHITE);
+        assert_eq!(label_colour(Some(LabelColour::White)), Color::WHITE);
+        assert_ne!(label_colour(Some(LabelColour::Red)), Color::WHITE);
+    }
+
     #[test]
     fn test_filter_options() {
         use super::*;
 
         let options = vec![
-            PinFunction::Input(None),
+            PinFunction::Input(None, None),
             PinFunction::Output(None),
             PinFunction::None,
         ];
@@ -725,22 +1595,134 @@ mod test {
         let result = filter_options(&options, None);
         assert_eq!(
             result,
-            vec![PinFunction::Input(None), PinFunction::Output(None)]
+            vec![PinFunction::Input(None, None), PinFunction::Output(None)]
         );
 
         // Test case: Input selected
-        let result = filter_options(&options, Some(PinFunction::Input(None)));
+        let result = filter_options(&options, Some(PinFunction::Input(None, None)));
         assert_eq!(result, vec![PinFunction::Output(None), PinFunction::None]);
 
         // Test case: Output selected
         let result = filter_options(&options, Some(PinFunction::Output(None)));
-        assert_eq!(result, vec![PinFunction::Input(None), PinFunction::None]);
+        assert_eq!(
+            result,
+            vec![PinFunction::Input(None, None), PinFunction::None]
+        );
 
         // Test case: None selected
         let result = filter_options(&options, Some(PinFunction::None));
         assert_eq!(
             result,
-            vec![PinFunction::Input(None), PinFunction::Output(None)]
+            vec![PinFunction::Input(None, None), PinFunction::Output(None)]
+        );
+    }
+
+    #[test]
+    fn test_filter_options_pwm() {
+        use super::*;
+
+        let pwm_option = PinFunction::Pwm {
+            frequency: 1000.0,
+            duty_cycle: 0.5,
+        };
+        let options = vec![
+            PinFunction::Input(None, None),
+            PinFunction::Output(None),
+            pwm_option.clone(),
+        ];
+
+        // Test case: PWM selected, with different settings to those of the option
+        let result = filter_options(
+            &options,
+            Some(PinFunction::Pwm {
+                frequency: 50.0,
+                duty_cycle: 0.1,
+            }),
+        );
+        assert_eq!(
+            result,
+            vec![
+                PinFunction::Input(None, None),
+                PinFunction::Output(None),
+                PinFunction::None
+            ]
+        );
+
+        // Test case: Output with a level selected, PWM should still be offered
+        let result = filter_options(&options, Some(PinFunction::Output(Some(true))));
+        assert_eq!(
+            result,
+            vec![
+                PinFunction::Input(None, None),
+                pwm_option,
+                PinFunction::None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_options_gpclk() {
+        use super::*;
+
+        let options = vec![
+            PinFunction::Input(None, None),
+            PinFunction::GPCLK0 {
+                frequency: 1_000_000.0,
+            },
+        ];
+
+        // GPCLK0 selected, with a different frequency to the option, is not offered again
+        let result = filter_options(
+            &options,
+            Some(PinFunction::GPCLK0 {
+                frequency: 25_000.0,
+            }),
+        );
+        assert_eq!(
+            result,
+            vec![PinFunction::Input(None, None), PinFunction::None]
+        );
+    }
+
+    #[test]
+    fn test_filter_options_alt() {
+        use super::*;
+        use crate::hw::pin_function::AltMode;
+        use std::borrow::Cow;
+
+        let alt1 = PinFunction::Alt {
+            mode: AltMode::Alt1,
+            name: Cow::Borrowed("SMI_SA3"),
+        };
+        let alt3 = PinFunction::Alt {
+            mode: AltMode::Alt3,
+            name: Cow::Borrowed("SPI3_MOSI"),
+        };
+        let options = vec![PinFunction::Input(None, None), alt1.clone(), alt3.clone()];
+
+        // All alternate functions are offered when nothing is selected
+        let result = filter_options(&options, None);
+        assert_eq!(result, options);
+
+        // The selected alternate function is not offered again, the others are
+        let result = filter_options(&options, Some(alt3));
+        assert_eq!(
+            result,
+            vec![PinFunction::Input(None, None), alt1, PinFunction::None]
+        );
+    }
+
+    #[test]
+    fn default_input_filter_is_none() {
+        use super::*;
+
+        assert_eq!(input_filter(InputEdge::Both, None), None);
+        assert_eq!(
+            input_filter(InputEdge::Both, Some(10)),
+            Some(InputFilter {
+                edge: InputEdge::Both,
+                debounce_ms: Some(10)
+            })
         );
     }
 }
@@ -0,0 +1,255 @@
+use iced::widget::{button, text_input, Column, Row, Text};
+use iced::{Alignment, Element, Length};
+
+use crate::hw::i2c::{I2CAddress, I2CRequest, I2CResponse};
+
+const I2C_VIEW_WIDTH: f32 = 260.0;
+const FIELD_WIDTH: f32 = 60.0;
+const MAX_LOG_LINES: usize = 8;
+
+/// [I2CViewMessage] covers all messages that are handled by the [I2CView]
+#[derive(Debug, Clone)]
+pub enum I2CViewMessage {
+    AddressEntered(String),
+    RegisterEntered(String),
+    DataEntered(String),
+    LengthEntered(String),
+    Scan,
+    Read,
+    Write,
+}
+
+/// [I2CView] is a panel to scan the I2C bus for devices, and to read and write their registers
+pub struct I2CView {
+    address: String,
+    register: String,
+    data: String,
+    length: String,
+    log: Vec<String>,
+}
+
+impl I2CView {
+    pub fn new() -> Self {
+        Self {
+            address: String::new(),
+            register: String::new(),
+            data: String::new(),
+            length: "1".to_string(),
+            log: vec![],
+        }
+    }
+
+    /// Update state based on the [I2CViewMessage] received, returning an [I2CRequest] to be
+    /// made on the hardware if the user requested one and the values entered are valid
+    pub fn update(&mut self, message: I2CViewMessage) -> Option<I2CRequest> {
+        let request = match message {
+            I2CViewMessage::AddressEntered(address) => {
+                self.address = address;
+                return None;
+            }
+            I2CViewMessage::RegisterEntered(register) => {
+                self.register = register;
+                return None;
+            }
+            I2CViewMessage::DataEntered(data) => {
+                self.data = data;
+                return None;
+            }
+            I2CViewMessage::LengthEntered(length) => {
+                self.length = length;
+                return None;
+            }
+            I2CViewMessage::Scan => Ok(I2CRequest::Scan),
+            I2CViewMessage::Read => self.read_request(),
+            I2CViewMessage::Write => self.write_request(),
+        };
+
+        match request {
+            Ok(request) => Some(request),
+            Err(message) => {
+                self.add_log_line(message);
+                None
+            }
+        }
+    }
+
+    /// Add the response to a request to the log of activity on the bus
+    pub fn add_response(&mut self, response: &I2CResponse) {
+        self.add_log_line(response.to_string());
+    }
+
+    fn add_log_line(&mut self, line: String) {
+        self.log.push(line);
+        if self.log.len() > MAX_LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    fn read_request(&self) -> Result<I2CRequest, String> {
+        let address = parse_address(&self.address)?;
+        let length = self
+            .length
+            .trim()
+            .parse::<usize>()
+            .map_err(|_| format!("Invalid length '{}'", self.length))?;
+        Ok(match parse_register(&self.register)? {
+            Some(register) => I2CRequest::ReadRegister {
+                address,
+                register,
+                length,
+            },
+            None => I2CRequest::Read { address, length },
+        })
+    }
+
+    fn write_request(&self) -> Result<I2CRequest, String> {
+        let address = parse_address(&self.address)?;
+        let data = parse_bytes(&self.data)?;
+        Ok(match parse_register(&self.register)? {
+            Some(register) => I2CRequest::WriteRegister {
+                address,
+                register,
+                data,
+            },
+            None => I2CRequest::Write { address, data },
+        })
+    }
+
+    /// Create the view of the panel, with fields for values, buttons for actions and the log
+    pub fn view(&self) -> Element<'_, I2CViewMessage> {
+        let fields = Row::new()
+            .push(
+                text_input("Address", &self.address)
+                    .on_input(I2CViewMessage::AddressEntered)
+                    .width(FIELD_WIDTH),
+            )
+            .push(
+                text_input("Register", &self.register)
+                    .on_input(I2CViewMessage::RegisterEntered)
+                    .width(FIELD_WIDTH),
+            )
+            .push(
+                text_input("Length", &self.length)
+                    .on_input(I2CViewMessage::LengthEntered)
+                    .width(FIELD_WIDTH),
+            )
+            .spacing(5);
+
+        let data = text_input("Data bytes (hex)", &self.data)
+            .on_input(I2CViewMessage::DataEntered)
+            .width(Length::Fill);
+
+        let buttons = Row::new()
+            .push(button("Scan").on_press(I2CViewMessage::Scan))
+            .push(button("Read").on_press(I2CViewMessage::Read))
+            .push(button("Write").on_press(I2CViewMessage::Write))
+            .spacing(5);
+
+        let mut column = Column::new()
+            .push(Text::new("I2C Bus"))
+            .push(fields)
+            .push(data)
+            .push(buttons)
+            .spacing(5)
+            .width(I2C_VIEW_WIDTH)
+            .align_items(Alignment::Start);
+
+        for line in &self.log {
+            column = column.push(Text::new(line.as_str()).size(12));
+        }
+
+        column.into()
+    }
+}
+
+/// Parse a byte entered in hex, with or without a "0x" prefix
+fn parse_byte(text: &str) -> Result<u8, String> {
+    let trimmed = text.trim();
+    let digits = trimmed.strip_prefix("0x").unwrap_or(trimmed);
+    u8::from_str_radix(digits, 16).map_err(|_| format!("Invalid hex byte '{trimmed}'"))
+}
+
+/// Parse a 7-bit I2C address entered in hex
+fn parse_address(text: &str) -> Result<I2CAddress, String> {
+    match parse_byte(text) {
+        Ok(address) if address <= 0x7F => Ok(address as I2CAddress),
+        _ => Err(format!("Invalid I2C address '{}'", text.trim())),
+    }
+}
+
+/// Parse an optional register number, an empty field means no register
+fn parse_register(text: &str) -> Result<Option<u8>, String> {
+    if text.trim().is_empty() {
+        Ok(None)
+    } else {
+        parse_byte(text).map(Some)
+    }
+}
+
+/// Parse a list of hex bytes separated by spaces or commas
+pub(crate) fn parse_bytes(text: &str) -> Result<Vec<u8>, String> {
+    text.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|byte| !byte.is_empty())
+        .map(parse_byte)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::i2c::{I2CRequest, I2CResponse};
+    use crate::views::i2c_view::{parse_bytes, I2CView, I2CViewMessage};
+
+    #[test]
+    fn parse_hex_bytes() {
+        assert_eq!(parse_bytes("0x01 02,ff"), Ok(vec![0x01, 0x02, 0xFF]));
+        assert!(parse_bytes("0x100").is_err());
+        assert_eq!(parse_bytes(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn register_read_request() {
+        let mut view = I2CView::new();
+        let _ = view.update(I2CViewMessage::AddressEntered("0x48".into()));
+        let _ = view.update(I2CViewMessage::RegisterEntered("00".into()));
+        let _ = view.update(I2CViewMessage::LengthEntered("2".into()));
+        assert_eq!(
+            view.update(I2CViewMessage::Read),
+            Some(I2CRequest::ReadRegister {
+                address: 0x48,
+                register: 0,
+                length: 2
+            })
+        );
+    }
+
+    #[test]
+    fn write_request_without_register() {
+        let mut view = I2CView::new();
+        let _ = view.update(I2CViewMessage::AddressEntered("20".into()));
+        let _ = view.update(I2CViewMessage::DataEntered("12 34".into()));
+        assert_eq!(
+            view.update(I2CViewMessage::Write),
+            Some(I2CRequest::Write {
+                address: 0x20,
+                data: vec![0x12, 0x34]
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_address_logged() {
+        let mut view = I2CView::new();
+        let _ = view.update(I2CViewMessage::AddressEntered("0x80".into()));
+        assert_eq!(view.update(I2CViewMessage::Read), None);
+        assert_eq!(view.log, vec!["Invalid I2C address '0x80'".to_string()]);
+    }
+
+    #[test]
+    fn log_is_limited() {
+        let mut view = I2CView::new();
+        for _ in 0..20 {
+            view.add_response(&I2CResponse::Devices(vec![]));
+        }
+        assert_eq!(view.log.len(), super::MAX_LOG_LINES);
+    }
+}
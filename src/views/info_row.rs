@@ -1,4 +1,4 @@
-use iced::widget::{container, Row};
+use iced::widget::{container, pick_list, Row};
 use iced::{Color, Command, Element, Length};
 use iced_aw::menu;
 use iced_aw::menu::{MenuBar, StyleSheet};
@@ -72,12 +72,26 @@ impl InfoRow {
             ..theme.appearance(&MenuBarStyle::Default)
         });
 
+        let mut row = Row::new()
+            .push(version_button())
+            .push(mb)
+            .push(unsaved_status::view(unsaved_changes));
+
+        // Offer to switch profile when the config has more than the default one
+        let profiles = hardware_view.profile_names();
+        if profiles.len() > 1 {
+            row = row.push(
+                pick_list(
+                    profiles,
+                    Some(hardware_view.active_profile().to_string()),
+                    Message::ProfileSelected,
+                )
+                .width(Length::Shrink),
+            );
+        }
+
         container(
-            Row::new()
-                .push(version_button())
-                .push(mb)
-                .push(unsaved_status::view(unsaved_changes))
-                .push(iced::widget::Space::with_width(Length::Fill)) // This takes up remaining space
+            row.push(iced::widget::Space::with_width(Length::Fill)) // This takes up remaining space
                 .push(self.message_row.view().map(Message::InfoRow))
                 .spacing(20.0)
                 .padding([0.0, 0.0, 0.0, 0.0]),
@@ -1,3 +1,4 @@
+use crate::hw::pin_description::PinDescriptionSet;
 use crate::Message;
 use iced::widget::pick_list;
 use iced::{Element, Length, Size};
@@ -27,24 +28,70 @@ impl std::fmt::Display for Layout {
 const LAYOUTS: [Layout; 2] = [Layout::BoardLayout, Layout::BCMLayout];
 
 const BOARD_LAYOUT_SIZE: Size = Size {
-    width: 1570.0,
+    width: 1760.0,
     height: 780.0,
 };
 
 const BCM_LAYOUT_SIZE: Size = Size {
-    width: 860.0,
+    width: 955.0,
     height: 976.0,
 };
 
-#[derive(Clone, PartialEq, Default)]
+/// The rows of pins the layout sizes above fit: the 40-pin header has 20 rows of two pins, and
+/// 26 GPIOs that can be configured
+const BOARD_LAYOUT_ROWS: usize = 20;
+const BCM_LAYOUT_ROWS: usize = 26;
+
+/// Height of a row of pins, including the space between rows
+const PIN_ROW_HEIGHT: f32 = 35.0;
+
+#[derive(Clone, PartialEq)]
 pub struct LayoutSelector {
     selected_layout: Layout,
+    /// Rows of pins in each layout, for the hardware connected to
+    board_rows: usize,
+    bcm_rows: usize,
+}
+
+impl Default for LayoutSelector {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LayoutSelector {
     pub fn new() -> Self {
         LayoutSelector {
             selected_layout: Layout::default(),
+            board_rows: BOARD_LAYOUT_ROWS,
+            bcm_rows: BCM_LAYOUT_ROWS,
+        }
+    }
+
+    /// Fit the layouts to the pins of the hardware connected to. If the window size needed for
+    /// the selected layout has changed, return the new size
+    pub fn set_pin_descriptions(&mut self, pin_descriptions: &PinDescriptionSet) -> Option<Size> {
+        let board_rows = pin_descriptions.pins().len().div_ceil(2);
+        let bcm_rows = pin_descriptions.bcm_pins_sorted().len();
+        if (board_rows, bcm_rows) == (self.board_rows, self.bcm_rows) {
+            return None;
+        }
+        let previous = self.window_size();
+        self.board_rows = board_rows;
+        self.bcm_rows = bcm_rows;
+        let size = self.window_size();
+        (size != previous).then_some(size)
+    }
+
+    /// The window size needed for the selected layout
+    fn window_size(&self) -> Size {
+        let (size, rows, fitted_rows) = match self.selected_layout {
+            Layout::BoardLayout => (BOARD_LAYOUT_SIZE, self.board_rows, BOARD_LAYOUT_ROWS),
+            Layout::BCMLayout => (BCM_LAYOUT_SIZE, self.bcm_rows, BCM_LAYOUT_ROWS),
+        };
+        Size {
+            width: size.width,
+            height: size.height + (rows as f32 - fitted_rows as f32) * PIN_ROW_HEIGHT,
         }
     }
 
@@ -55,10 +102,7 @@ impl LayoutSelector {
     /// Set the new layout as being selected and return the window size required
     pub fn update(&mut self, new_layout: Layout) -> Size {
         self.selected_layout = new_layout;
-        match self.selected_layout {
-            Layout::BoardLayout => BOARD_LAYOUT_SIZE,
-            Layout::BCMLayout => BCM_LAYOUT_SIZE,
-        }
+        self.window_size()
     }
 
     // Return the currently selected layout
@@ -81,9 +125,12 @@ impl LayoutSelector {
 
 #[cfg(test)]
 mod test {
+    use crate::hw::pin_description::{PinDescription, PinDescriptionSet};
+    use crate::hw::pin_function::PinFunction;
     use crate::views::layout_selector::{
-        Layout, LayoutSelector, BCM_LAYOUT_SIZE, BOARD_LAYOUT_SIZE,
+        Layout, LayoutSelector, BCM_LAYOUT_SIZE, BOARD_LAYOUT_SIZE, PIN_ROW_HEIGHT,
     };
+    use std::borrow::Cow;
 
     #[test]
     fn default_is_board() {
@@ -99,6 +146,50 @@ mod test {
         );
     }
 
+    /// A set of `count` pins that are all GPIOs
+    fn gpio_pins(count: u8) -> PinDescriptionSet {
+        PinDescriptionSet::from(
+            (1..=count)
+                .map(|bpn| PinDescription {
+                    bpn,
+                    bcm: Some(bpn),
+                    name: Cow::Owned(format!("GPIO{bpn}")),
+                    options: Cow::Borrowed(&[
+                        PinFunction::Input(None, None),
+                        PinFunction::Output(None),
+                    ]),
+                })
+                .collect::<Vec<PinDescription>>(),
+        )
+    }
+
+    #[test]
+    fn fits_fewer_pins() {
+        let mut layout_selector = LayoutSelector::new();
+        let size = layout_selector
+            .set_pin_descriptions(&gpio_pins(26))
+            .expect("Window size did not change");
+        assert_eq!(size.height, BOARD_LAYOUT_SIZE.height - 7.0 * PIN_ROW_HEIGHT);
+        assert_eq!(layout_selector.set_pin_descriptions(&gpio_pins(26)), None);
+    }
+
+    #[test]
+    fn fits_more_gpios() {
+        let mut layout_selector = LayoutSelector::new();
+        layout_selector.update(Layout::BCMLayout);
+        let size = layout_selector
+            .set_pin_descriptions(&gpio_pins(30))
+            .expect("Window size did not change");
+        assert_eq!(size.height, BCM_LAYOUT_SIZE.height + 4.0 * PIN_ROW_HEIGHT);
+    }
+
+    #[test]
+    fn other_layout_rows_keep_size() {
+        // 40 GPIOs is more than the BCM layout fits, but the board layout is selected
+        let mut layout_selector = LayoutSelector::new();
+        assert_eq!(layout_selector.set_pin_descriptions(&gpio_pins(40)), None);
+    }
+
     #[test]
     fn switch_to_bcm() {
         let mut layout_selector = LayoutSelector::new();
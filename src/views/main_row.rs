@@ -16,6 +16,12 @@ pub fn view<'a>(
     main_row = main_row.push(
         Column::new()
             .push(configuration_column::view(layout_selector))
+            .push(
+                hardware_view
+                    .bus_views(hardware_target)
+                    .map(Message::Hardware),
+            )
+            .spacing(10)
             .align_items(Alignment::Start)
             .width(Length::Shrink)
             .height(Length::Shrink),
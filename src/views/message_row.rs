@@ -103,7 +103,7 @@ impl MessageRow {
     }
 
     /// Create the view that represents a status row at the bottom of the screen
-    pub fn view(&self) -> Element<MessageRowMessage> {
+    pub fn view(&self) -> Element<'_, MessageRowMessage> {
         let (text_color, message_text) = match &self.message_queue.current_message {
             None => (Color::TRANSPARENT, "".into()),
             Some(msg) => {
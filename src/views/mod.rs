@@ -1,11 +1,14 @@
 pub mod configuration_column;
 pub mod hardware_menu;
 pub mod hardware_view;
+pub mod i2c_view;
 pub mod info_row;
 pub mod layout_selector;
 pub mod main_row;
 pub mod message_row;
 pub mod pin_state;
+pub mod spi_view;
+pub mod uart_view;
 pub mod unsaved_status;
 pub mod version;
 pub mod waveform;
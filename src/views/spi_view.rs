@@ -0,0 +1,258 @@
+use iced::widget::{button, pick_list, text_input, Column, Row, Text};
+use iced::{Alignment, Element, Length};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use crate::hw::spi::{SPIBus, SPIConfig, SPIMode, SPIResponse, SPITransfer};
+use crate::hw::HardwareConfigMessage;
+use crate::views::i2c_view::parse_bytes;
+
+const SPI_VIEW_WIDTH: f32 = 260.0;
+const FIELD_WIDTH: f32 = 90.0;
+const MAX_LOG_LINES: usize = 8;
+
+/// A chip select line of an SPI bus, as shown in the chip select selector
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChipSelect(pub u8);
+
+impl Display for ChipSelect {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "CE{}", self.0)
+    }
+}
+
+/// [SPIViewMessage] covers all messages that are handled by an [SPIView]
+#[derive(Debug, Clone)]
+pub enum SPIViewMessage {
+    ModeSelected(SPIMode),
+    ClockSpeedEntered(String),
+    ClockSpeedSubmitted,
+    ChipSelectSelected(ChipSelect),
+    DataEntered(String),
+    Transfer,
+}
+
+/// [SPIView] is a panel to change the settings of an SPI bus and make transfers on it
+pub struct SPIView {
+    bus: SPIBus,
+    clock_speed: String,
+    chip_select: Option<ChipSelect>,
+    data: String,
+    log: Vec<String>,
+}
+
+impl SPIView {
+    pub fn new(bus: SPIBus) -> Self {
+        Self {
+            bus,
+            clock_speed: String::new(),
+            chip_select: None,
+            data: String::new(),
+            log: vec![],
+        }
+    }
+
+    /// Update state based on the [SPIViewMessage] received, using the current `config` of the
+    /// bus and the `chip_selects` that are configured on it. Returns a [HardwareConfigMessage]
+    /// to be sent to the hardware if the settings changed or a transfer was requested
+    pub fn update(
+        &mut self,
+        message: SPIViewMessage,
+        config: &SPIConfig,
+        chip_selects: &[ChipSelect],
+    ) -> Option<HardwareConfigMessage> {
+        match message {
+            SPIViewMessage::ModeSelected(mode) => {
+                return Some(HardwareConfigMessage::NewSPIConfig(
+                    self.bus,
+                    SPIConfig { mode, ..*config },
+                ));
+            }
+            SPIViewMessage::ClockSpeedEntered(clock_speed) => self.clock_speed = clock_speed,
+            SPIViewMessage::ClockSpeedSubmitted => match self.clock_speed.trim().parse::<u32>() {
+                Ok(clock_speed) if clock_speed > 0 => {
+                    self.clock_speed.clear();
+                    return Some(HardwareConfigMessage::NewSPIConfig(
+                        self.bus,
+                        SPIConfig {
+                            clock_speed,
+                            ..*config
+                        },
+                    ));
+                }
+                _ => {
+                    let line = format!("Invalid clock speed '{}'", self.clock_speed.trim());
+                    self.add_log_line(line);
+                }
+            },
+            SPIViewMessage::ChipSelectSelected(chip_select) => self.chip_select = Some(chip_select),
+            SPIViewMessage::DataEntered(data) => self.data = data,
+            SPIViewMessage::Transfer => {
+                let Some(chip_select) = self.selected_chip_select(chip_selects) else {
+                    self.add_log_line(format!("No chip select configured for {}", self.bus));
+                    return None;
+                };
+                match parse_bytes(&self.data) {
+                    Ok(data) => {
+                        return Some(HardwareConfigMessage::SPITransfer(SPITransfer {
+                            bus: self.bus,
+                            chip_select: chip_select.0,
+                            data,
+                        }))
+                    }
+                    Err(message) => self.add_log_line(message),
+                }
+            }
+        }
+
+        None
+    }
+
+    /// The chip select chosen by the user, if it is still configured, or else the first one
+    fn selected_chip_select(&self, chip_selects: &[ChipSelect]) -> Option<ChipSelect> {
+        self.chip_select
+            .filter(|chip_select| chip_selects.contains(chip_select))
+            .or(chip_selects.first().copied())
+    }
+
+    /// Add the response to a transfer to the log of activity on the bus
+    pub fn add_response(&mut self, response: &SPIResponse) {
+        self.add_log_line(response.to_string());
+    }
+
+    fn add_log_line(&mut self, line: String) {
+        self.log.push(line);
+        if self.log.len() > MAX_LOG_LINES {
+            self.log.remove(0);
+        }
+    }
+
+    /// Create the view of the panel, showing the bus settings in `config` and allowing a
+    /// transfer using one of the `chip_selects` configured
+    pub fn view(
+        &self,
+        config: &SPIConfig,
+        chip_selects: Vec<ChipSelect>,
+    ) -> Element<'_, SPIViewMessage> {
+        let selected = self.selected_chip_select(&chip_selects);
+
+        let settings = Row::new()
+            .push(
+                pick_list(
+                    SPIMode::ALL,
+                    Some(config.mode),
+                    SPIViewMessage::ModeSelected,
+                )
+                .width(FIELD_WIDTH),
+            )
+            .push(
+                text_input(&format!("{} Hz", config.clock_speed), &self.clock_speed)
+                    .on_input(SPIViewMessage::ClockSpeedEntered)
+                    .on_submit(SPIViewMessage::ClockSpeedSubmitted)
+                    .width(FIELD_WIDTH),
+            )
+            .push(pick_list(
+                chip_selects,
+                selected,
+                SPIViewMessage::ChipSelectSelected,
+            ))
+            .spacing(5);
+
+        let data = text_input("Data bytes (hex)", &self.data)
+            .on_input(SPIViewMessage::DataEntered)
+            .on_submit(SPIViewMessage::Transfer)
+            .width(Length::Fill);
+
+        let mut column = Column::new()
+            .push(Text::new(format!("{} Bus", self.bus)))
+            .push(settings)
+            .push(data)
+            .push(button("Transfer").on_press(SPIViewMessage::Transfer))
+            .spacing(5)
+            .width(SPI_VIEW_WIDTH)
+            .align_items(Alignment::Start);
+
+        for line in &self.log {
+            column = column.push(Text::new(line.as_str()).size(12));
+        }
+
+        column.into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::spi::{SPIBus, SPIConfig, SPIMode, SPITransfer};
+    use crate::hw::HardwareConfigMessage;
+    use crate::views::spi_view::{ChipSelect, SPIView, SPIViewMessage};
+
+    #[test]
+    fn mode_change_keeps_clock_speed() {
+        let mut view = SPIView::new(SPIBus::SPI0);
+        let config = SPIConfig {
+            mode: SPIMode::Mode0,
+            clock_speed: 250_000,
+        };
+        let message = view.update(SPIViewMessage::ModeSelected(SPIMode::Mode2), &config, &[]);
+        assert!(matches!(
+            message,
+            Some(HardwareConfigMessage::NewSPIConfig(
+                SPIBus::SPI0,
+                SPIConfig {
+                    mode: SPIMode::Mode2,
+                    clock_speed: 250_000
+                }
+            ))
+        ));
+    }
+
+    #[test]
+    fn invalid_clock_speed_logged() {
+        let mut view = SPIView::new(SPIBus::SPI0);
+        let config = SPIConfig::default();
+        let _ = view.update(
+            SPIViewMessage::ClockSpeedEntered("fast".into()),
+            &config,
+            &[],
+        );
+        assert!(view
+            .update(SPIViewMessage::ClockSpeedSubmitted, &config, &[])
+            .is_none());
+        assert_eq!(view.log, vec!["Invalid clock speed 'fast'".to_string()]);
+    }
+
+    #[test]
+    fn transfer_uses_first_configured_chip_select() {
+        let mut view = SPIView::new(SPIBus::SPI1);
+        let config = SPIConfig::default();
+        let chip_selects = [ChipSelect(1), ChipSelect(2)];
+        let _ = view.update(
+            SPIViewMessage::DataEntered("de ad".into()),
+            &config,
+            &chip_selects,
+        );
+        let message = view.update(SPIViewMessage::Transfer, &config, &chip_selects);
+        match message {
+            Some(HardwareConfigMessage::SPITransfer(transfer)) => assert_eq!(
+                transfer,
+                SPITransfer {
+                    bus: SPIBus::SPI1,
+                    chip_select: 1,
+                    data: vec![0xDE, 0xAD]
+                }
+            ),
+            _ => panic!("Expected an SPI transfer"),
+        }
+    }
+
+    #[test]
+    fn transfer_without_chip_select() {
+        let mut view = SPIView::new(SPIBus::SPI0);
+        let message = view.update(SPIViewMessage::Transfer, &SPIConfig::default(), &[]);
+        assert!(message.is_none());
+        assert_eq!(
+            view.log,
+            vec!["No chip select configured for SPI0".to_string()]
+        );
+    }
+}
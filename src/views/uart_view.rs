@@ -0,0 +1,261 @@
+use iced::widget::{button, checkbox, container, pick_list, text_input, Column, Row, Text};
+use iced::{Alignment, Element, Length};
+
+use crate::hw::uart::{UARTConfig, UARTEvent, UARTParity, UART_BAUD_RATES};
+use crate::hw::HardwareConfigMessage;
+use crate::views::i2c_view::parse_bytes;
+
+const UART_VIEW_WIDTH: f32 = 260.0;
+const TERMINAL_HEIGHT: f32 = 160.0;
+/// Maximum number of received bytes kept for display
+const MAX_RECEIVED_BYTES: usize = 4096;
+/// Number of lines of received data shown in the terminal
+const TERMINAL_LINES: usize = 10;
+/// Number of bytes per line when received data is shown in hex
+const HEX_BYTES_PER_LINE: usize = 8;
+
+/// [UARTViewMessage] covers all messages that are handled by the [UARTView]
+#[derive(Debug, Clone)]
+pub enum UARTViewMessage {
+    BaudRateSelected(u32),
+    ParitySelected(UARTParity),
+    StopBitsSelected(u8),
+    HexToggled(bool),
+    InputEntered(String),
+    Send,
+    Clear,
+}
+
+/// [UARTView] is a terminal for the UART, showing the bytes received on it and sending bytes
+/// entered as text or hex
+pub struct UARTView {
+    input: String,
+    hex: bool,
+    received: Vec<u8>,
+    error: Option<String>,
+}
+
+impl UARTView {
+    pub fn new() -> Self {
+        Self {
+            input: String::new(),
+            hex: false,
+            received: vec![],
+            error: None,
+        }
+    }
+
+    /// Update state based on the [UARTViewMessage] received, using the current `config` of the
+    /// UART. Returns a [HardwareConfigMessage] to be sent to the hardware if the settings were
+    /// changed or bytes are to be sent
+    pub fn update(
+        &mut self,
+        message: UARTViewMessage,
+        config: &UARTConfig,
+    ) -> Option<HardwareConfigMessage> {
+        match message {
+            UARTViewMessage::BaudRateSelected(baud_rate) => {
+                return Some(HardwareConfigMessage::NewUARTConfig(UARTConfig {
+                    baud_rate,
+                    ..*config
+                }));
+            }
+            UARTViewMessage::ParitySelected(parity) => {
+                return Some(HardwareConfigMessage::NewUARTConfig(UARTConfig {
+                    parity,
+                    ..*config
+                }));
+            }
+            UARTViewMessage::StopBitsSelected(stop_bits) => {
+                return Some(HardwareConfigMessage::NewUARTConfig(UARTConfig {
+                    stop_bits,
+                    ..*config
+                }));
+            }
+            UARTViewMessage::HexToggled(hex) => self.hex = hex,
+            UARTViewMessage::InputEntered(input) => self.input = input,
+            UARTViewMessage::Send => {
+                let data = if self.hex {
+                    parse_bytes(&self.input)
+                } else {
+                    Ok(self.input.as_bytes().to_vec())
+                };
+                match data {
+                    Ok(data) if data.is_empty() => {}
+                    Ok(data) => {
+                        self.input.clear();
+                        self.error = None;
+                        return Some(HardwareConfigMessage::UARTWrite(data));
+                    }
+                    Err(message) => self.error = Some(message),
+                }
+            }
+            UARTViewMessage::Clear => {
+                self.received.clear();
+                self.error = None;
+            }
+        }
+
+        None
+    }
+
+    /// Add an event from the UART: received bytes are added to the terminal, keeping only the
+    /// most recent, and errors are shown until the next successful send
+    pub fn add_event(&mut self, event: &UARTEvent) {
+        match event {
+            UARTEvent::Received(data) => {
+                self.received.extend_from_slice(data);
+                if self.received.len() > MAX_RECEIVED_BYTES {
+                    let excess = self.received.len() - MAX_RECEIVED_BYTES;
+                    self.received.drain(..excess);
+                }
+            }
+            UARTEvent::Error(message) => self.error = Some(message.clone()),
+        }
+    }
+
+    /// The last lines of received data, as text or as hex bytes
+    fn terminal_lines(&self) -> Vec<String> {
+        let lines: Vec<String> = if self.hex {
+            self.received
+                .chunks(HEX_BYTES_PER_LINE)
+                .map(|chunk| {
+                    chunk
+                        .iter()
+                        .map(|byte| format!("{byte:02x}"))
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                })
+                .collect()
+        } else {
+            String::from_utf8_lossy(&self.received)
+                .lines()
+                .map(|line| line.to_string())
+                .collect()
+        };
+        let skip = lines.len().saturating_sub(TERMINAL_LINES);
+        lines.into_iter().skip(skip).collect()
+    }
+
+    /// Create the view of the terminal, showing the UART settings in `config`
+    pub fn view(&self, config: &UARTConfig) -> Element<'_, UARTViewMessage> {
+        let settings = Row::new()
+            .push(pick_list(
+                UART_BAUD_RATES,
+                Some(config.baud_rate),
+                UARTViewMessage::BaudRateSelected,
+            ))
+            .push(pick_list(
+                UARTParity::ALL,
+                Some(config.parity),
+                UARTViewMessage::ParitySelected,
+            ))
+            .push(pick_list(
+                [1u8, 2u8],
+                Some(config.stop_bits),
+                UARTViewMessage::StopBitsSelected,
+            ))
+            .spacing(5)
+            .align_items(Alignment::Center);
+
+        let mut terminal = Column::new();
+        for line in self.terminal_lines() {
+            terminal = terminal.push(Text::new(line).size(12));
+        }
+
+        let input = Row::new()
+            .push(
+                text_input(if self.hex { "Hex bytes" } else { "Text" }, &self.input)
+                    .on_input(UARTViewMessage::InputEntered)
+                    .on_submit(UARTViewMessage::Send)
+                    .width(Length::Fill),
+            )
+            .push(button("Send").on_press(UARTViewMessage::Send))
+            .spacing(5);
+
+        let controls = Row::new()
+            .push(checkbox("Hex", self.hex).on_toggle(UARTViewMessage::HexToggled))
+            .push(button("Clear").on_press(UARTViewMessage::Clear))
+            .spacing(10)
+            .align_items(Alignment::Center);
+
+        let mut column = Column::new()
+            .push(Text::new("UART"))
+            .push(settings)
+            .push(
+                container(terminal)
+                    .height(TERMINAL_HEIGHT)
+                    .width(Length::Fill),
+            )
+            .push(input)
+            .push(controls)
+            .spacing(5)
+            .width(UART_VIEW_WIDTH)
+            .align_items(Alignment::Start);
+
+        if let Some(error) = &self.error {
+            column = column.push(Text::new(error.as_str()).size(12));
+        }
+
+        column.into()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::hw::uart::{UARTConfig, UARTEvent};
+    use crate::hw::HardwareConfigMessage;
+    use crate::views::uart_view::{UARTView, UARTViewMessage, MAX_RECEIVED_BYTES};
+
+    #[test]
+    fn send_text() {
+        let mut view = UARTView::new();
+        let config = UARTConfig::default();
+        let _ = view.update(UARTViewMessage::InputEntered("hi".into()), &config);
+        match view.update(UARTViewMessage::Send, &config) {
+            Some(HardwareConfigMessage::UARTWrite(data)) => assert_eq!(data, b"hi"),
+            _ => panic!("Expected a UART write"),
+        }
+        assert!(view.input.is_empty());
+    }
+
+    #[test]
+    fn send_hex() {
+        let mut view = UARTView::new();
+        let config = UARTConfig::default();
+        let _ = view.update(UARTViewMessage::HexToggled(true), &config);
+        let _ = view.update(UARTViewMessage::InputEntered("0d 0a".into()), &config);
+        match view.update(UARTViewMessage::Send, &config) {
+            Some(HardwareConfigMessage::UARTWrite(data)) => assert_eq!(data, vec![0x0D, 0x0A]),
+            _ => panic!("Expected a UART write"),
+        }
+    }
+
+    #[test]
+    fn invalid_hex_not_sent() {
+        let mut view = UARTView::new();
+        let config = UARTConfig::default();
+        let _ = view.update(UARTViewMessage::HexToggled(true), &config);
+        let _ = view.update(UARTViewMessage::InputEntered("zz".into()), &config);
+        assert!(view.update(UARTViewMessage::Send, &config).is_none());
+        assert_eq!(view.error, Some("Invalid hex byte 'zz'".to_string()));
+    }
+
+    #[test]
+    fn received_shown_as_text_and_hex() {
+        let mut view = UARTView::new();
+        view.add_event(&UARTEvent::Received(b"one\ntwo".to_vec()));
+        assert_eq!(view.terminal_lines(), vec!["one", "two"]);
+        view.hex = true;
+        assert_eq!(view.terminal_lines(), vec!["6f 6e 65 0a 74 77 6f"]);
+    }
+
+    #[test]
+    fn received_is_limited() {
+        let mut view = UARTView::new();
+        view.add_event(&UARTEvent::Received(vec![0; MAX_RECEIVED_BYTES]));
+        view.add_event(&UARTEvent::Received(vec![1; 10]));
+        assert_eq!(view.received.len(), MAX_RECEIVED_BYTES);
+        assert_eq!(view.received.last(), Some(&1));
+    }
+}
@@ -176,7 +176,7 @@ impl<'a, Message> Widget<Message, Theme, Renderer> for Manager<'a, Message> {
                 instants.truncate(new);
             }
             (old, new) if old < new => {
-                instants.extend(std::iter::repeat(Some(Instant::now())).take(new - old));
+                instants.extend(std::iter::repeat_n(Some(Instant::now()), new - old));
             }
             _ => {}
         }